@@ -1,21 +1,26 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, 
-    StdResult, Uint128,Attribute,attr,
+    to_binary, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order, Response, 
+    StdError, StdResult, Storage, Uint128,Attribute,attr,Event,to_vec,Decimal,
 };
 use cw_storage_plus::Bound;
-use cw_utils::{maybe_addr};
+use sha2::{Digest, Sha256};
+use cw_utils::{ensure_from_older_version, maybe_addr};
 use cw1155::{
     OperatorsResponse, BalanceResponse, BatchBalanceResponse,
     Cw1155BatchReceiveMsg, Cw1155ExecuteMsg, Cw1155QueryMsg, Cw1155ReceiveMsg, Expiration,TokenSupply,
     AllowanceResponse, TokenId, TokenInfoResponse, TokensResponse,AllBalanceResponse,TokenSupplyResponse,
-    TokenSuppliesResponse,ContractInfoResponse,
+    TokenSuppliesResponse,ContractInfoResponse,ClawbackLogEntry,ClawbackLogResponse,TransferPolicy,
+    TransferPolicyResponse,StatsResponse,IsMetadataFrozenResponse,
+    IsVoucherRedeemedResponse,MintVoucher,OwnerOfResponse,TokenType,
+    TokenAllowanceResponse,TokenAllowanceInfo,TokenAllowancesResponse,CooperativeScope,CooperativeScopeResponse,
+    TokenDefinition,TokenDefinitionInfo,TokenDefinitionsResponse,RoyaltyInfoResponse,
 };
 use cw2::set_contract_version;
 
 use crate::error::ContractError;
-use crate::msg::InstantiateMsg;
-use crate::state::{APPROVES, BALANCES, CONTRACT_INFO,TOKEN_RUNNING_NO,TOKEN_SUPPLIES,COOPERATIVES, TOKENS,ContractInfo,TransferAction,CooperativeData};
+use crate::msg::{InstantiateMsg, MigrateMsg};
+use crate::state::{APPROVES, APPROVES_PRUNE_CURSOR, APPROVES_OWNER_PRUNE_CURSORS, BALANCES, BOUND_BALANCES, TOKEN_TRANSFER_POLICIES, TRANSFER_POLICY, CLAWBACK_LOG, CLAWBACK_RENOUNCED, CONTRACT_INFO, ClawbackRecord,TOKEN_RUNNING_NO,TOKEN_SUPPLIES,COOPERATIVES, TOKENS,TOKEN_STATS,METADATA_FROZEN,FROZEN_TOKENS,BASE_URI,VOUCHER_SIGNERS,REDEEMED_VOUCHERS,TOKEN_TYPES,NFT_OWNERS,TOKEN_APPROVES,TokenApproval,TOKEN_CATEGORIES,CATEGORY_TOKENS,TOKEN_ROYALTIES,TOKEN_DEFINITIONS,DEFINITION_MIGRATION,HOLDER_MIGRATION,HolderMigration,is_holder_counted,base_token_id,is_metadata_frozen,resolve_token_uri,load_token_type,serial_token_id,ContractInfo,TransferAction,CooperativeData};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw1155-base";
@@ -23,6 +28,12 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const DEFAULT_LIMIT: u32 = 10;
 const MAX_LIMIT: u32 = 30;
+// max entries a single prune may look at
+const MAX_PRUNE_LIMIT: u32 = 100;
+// operators of the owner checked for expiry each time an operator sends for it
+const TOUCH_PRUNE_LIMIT: usize = 5;
+// max entries a single migration step may look at
+const MAX_MIGRATE_LIMIT: u32 = 100;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -43,10 +54,30 @@ pub fn instantiate(
         owner : info.sender.clone(),
     };
     CONTRACT_INFO.save(deps.storage, &data)?;
+    TRANSFER_POLICY.save(deps.storage, &msg.transfer_policy.unwrap_or_default())?;
     
     Ok(Response::default())
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let unmarked = TOKEN_DEFINITIONS.keys(deps.storage, None, None, Order::Ascending).next().is_none()
+        && TOKENS.keys(deps.storage, None, None, Order::Ascending).next().is_some();
+    if unmarked && DEFINITION_MIGRATION.may_load(deps.storage)?.is_none() {
+        // Older versions only defined running numbers, MigrateDefinitions marks them page by page
+        DEFINITION_MIGRATION.save(deps.storage, &0u128)?;
+    }
+    let uncounted = TOKEN_STATS.keys(deps.storage, None, None, Order::Ascending).next().is_none()
+        && BALANCES.keys(deps.storage, None, None, Order::Ascending).next().is_some();
+    if uncounted && HOLDER_MIGRATION.may_load(deps.storage)?.is_none() {
+        // Balances from before stats are counted by MigrateHolders page by page
+        HOLDER_MIGRATION.save(deps.storage, &HolderMigration { cursor: None })?;
+    }
+    Ok(Response::default())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -110,9 +141,9 @@ pub fn execute(
             msg,
         } => execute_batch_send_from(deps,env,info, from, contract, batch, msg),
         
-        Cw1155ExecuteMsg::Mint {to,token_id,amount} => execute_mint(deps,info, to, token_id, amount),
+        Cw1155ExecuteMsg::Mint {to,token_id,amount,msg} => execute_mint(deps,info, to, token_id, amount, msg),
 
-        Cw1155ExecuteMsg::BatchMint { to, batch } => execute_batch_mint(deps,info, to, batch),
+        Cw1155ExecuteMsg::BatchMint { to, batch, msg } => execute_batch_mint(deps,info, to, batch, msg),
         
         Cw1155ExecuteMsg::Burn {from,token_id,amount} => execute_burn(deps,env,info, from, token_id, amount),
 
@@ -122,15 +153,40 @@ pub fn execute(
         
         Cw1155ExecuteMsg::RevokeAll { operator } => execute_revoke_all(deps,info, operator),
 
-        Cw1155ExecuteMsg::DefineToken {token_uri,max_supply} => execute_define_token(deps,info,token_uri,max_supply),
+        Cw1155ExecuteMsg::Approve { operator, token_id, amount, expires } => execute_approve(deps,env,info, operator, token_id, amount, expires),
+
+        Cw1155ExecuteMsg::PruneExpired { owner, limit } => execute_prune_expired(deps,env,info, owner, limit),
+        Cw1155ExecuteMsg::MigrateDefinitions { limit } => execute_migrate_definitions(deps,info,limit),
+        Cw1155ExecuteMsg::MigrateHolders { limit } => execute_migrate_holders(deps,info,limit),
+
+        Cw1155ExecuteMsg::DefineToken {token_uri,max_supply,transfer_policy,token_type,category,royalty} => execute_define_token(deps,info,TokenDefinition{
+            token_id : None,
+            token_uri,
+            max_supply,
+            transfer_policy,
+            token_type,
+            category,
+            royalty,
+        }),
+        Cw1155ExecuteMsg::DefineTokens { tokens } => execute_define_tokens(deps,info,tokens),
 
         Cw1155ExecuteMsg::UpdateTokenUri {token_id,token_uri} => execute_update_token_uri(deps,info,token_id,token_uri),
+        Cw1155ExecuteMsg::UpdateBaseUri { base_uri } => execute_update_base_uri(deps,info,base_uri),
+        Cw1155ExecuteMsg::FreezeMetadata { token_id } => execute_freeze_metadata(deps,info,token_id),
+        Cw1155ExecuteMsg::SetVoucherSigner { pubkey } => execute_set_voucher_signer(deps,info,pubkey),
+        Cw1155ExecuteMsg::RedeemVoucher { voucher, signature } => execute_redeem_voucher(deps,env,voucher,signature),
     
         Cw1155ExecuteMsg::UpdateMaxSupply{token_id,max_supply} => execute_update_max_supply(deps,info,token_id,max_supply),
     
-        Cw1155ExecuteMsg::SetCooperative { cooperative , can_mint_for  , can_burn_from } => execute_set_cooperative(deps,info , cooperative , can_mint_for  , can_burn_from),
+        Cw1155ExecuteMsg::SetCooperative { cooperative , can_mint_for  , can_burn_from, scope } => execute_set_cooperative(deps,info , cooperative , can_mint_for  , can_burn_from, scope),
+
+        Cw1155ExecuteMsg::SetTokenCategory { token_id, category } => execute_set_token_category(deps,info,token_id,category),
 
         Cw1155ExecuteMsg::UnsetCooperative { cooperative} => execute_unset_cooperative(deps,info,cooperative),
+
+        Cw1155ExecuteMsg::ForceTransfer { from, to, token_id, amount, reason } => execute_force_transfer(deps,env,info, from, to, token_id, amount, reason),
+
+        Cw1155ExecuteMsg::RenounceClawback {} => execute_renounce_clawback(deps,info),
     }
 }
 
@@ -167,12 +223,73 @@ pub fn execute_change_owner(
     Ok(res)
 }
 
+/// Policy that applies to token_id, its own one or else the collection one.
+fn load_transfer_policy(storage: &dyn Storage, token_id: &str) -> StdResult<TransferPolicy> {
+    match TOKEN_TRANSFER_POLICIES.may_load(storage, token_id)? {
+        Some(policy) => Ok(policy),
+        None => Ok(TRANSFER_POLICY.may_load(storage)?.unwrap_or_default()),
+    }
+}
+
+/// Fails when the token policy forbids operator to move amount out of from,
+/// BindOnTransfer amounts get bound to the receiver.
+fn check_transfer_policy(
+    deps: DepsMut,
+    operator: &Addr,
+    from: &Addr,
+    to: &Addr,
+    token_id: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    match load_transfer_policy(deps.storage, token_id)? {
+        TransferPolicy::Free => {},
+        TransferPolicy::Soulbound => {
+            return Err(ContractError::TransferNotAllowed{token_id: token_id.to_string()});
+        },
+        TransferPolicy::CooperativeOnly => {
+            if !check_as_contract_owner(deps.as_ref(),operator) && !check_as_cooperative(deps.as_ref(),operator,false,false) {
+                return Err(ContractError::TransferNotAllowed{token_id: token_id.to_string()});
+            }
+        },
+        TransferPolicy::BindOnTransfer => {
+            let balance = BALANCES.may_load(deps.storage, (from, token_id))?.unwrap_or_default();
+            let bound = BOUND_BALANCES.may_load(deps.storage, (from, token_id))?.unwrap_or_default();
+            if amount > balance.saturating_sub(bound) {
+                return Err(ContractError::TransferNotAllowed{token_id: token_id.to_string()});
+            }
+            BOUND_BALANCES.update(
+                deps.storage,
+                (to, token_id),
+                |bound: Option<Uint128>| -> StdResult<_> {
+                    Ok(bound.unwrap_or_default().checked_add(amount)?)
+                },
+            )?;
+        },
+    }
+    Ok(())
+}
+
+/// Bound part can never be more than the balance, burns and forced transfers take it down.
+fn clamp_bound_balance(storage: &mut dyn Storage, owner: &Addr, token_id: &str) -> StdResult<()> {
+    if let Some(bound) = BOUND_BALANCES.may_load(storage, (owner, token_id))? {
+        let balance = BALANCES.may_load(storage, (owner, token_id))?.unwrap_or_default();
+        if balance.is_zero() {
+            BOUND_BALANCES.remove(storage, (owner, token_id));
+        } else if bound > balance {
+            BOUND_BALANCES.save(storage, (owner, token_id), &balance)?;
+        }
+    }
+    Ok(())
+}
+
 /// When from is None: mint new coins
 /// When to is None: burn coins
 /// When both are None: no token balance is changed, pointless but valid
+/// Transfer policies are only checked when operator is set.
 /// Make sure permissions are checked before calling this.
 fn execute_transfer_internal(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    operator: Option<&Addr>,
     from: Option<&Addr>,
     to: Option<&Addr>,
     token_id: &str,
@@ -188,6 +305,10 @@ fn execute_transfer_internal(
         return Err(ContractError::InvalidTransferAddress{});
     }
 
+    if let (TransferAction::Transfer, Some(operator), Some(from_addr), Some(to_addr)) = (action, operator, from, to) {
+        check_transfer_policy(deps.branch(), operator, from_addr, to_addr, token_id, amount)?;
+    }
+
     if let Some(from_addr) = from {
         BALANCES.update(
             deps.storage,
@@ -196,6 +317,7 @@ fn execute_transfer_internal(
                 Ok(balance.unwrap_or_default().checked_sub(amount)?)
             },
         )?;
+        clamp_bound_balance(deps.storage, from_addr, token_id)?;
     }
 
     if let Some(to_addr) = to {
@@ -207,6 +329,7 @@ fn execute_transfer_internal(
             },
         )?;
     }
+    update_nft_owner(deps.storage, to, token_id, amount)?;
 
     
     if action != TransferAction::Transfer{
@@ -230,6 +353,7 @@ fn execute_transfer_internal(
             TOKEN_SUPPLIES.save(deps.storage,&token_id,&new_token_supply)?;
         }
     }
+    update_token_stats(deps.storage, action, from, to, token_id, amount)?;
 
     let mut resp_attrs : Vec<Attribute> = vec![];
     resp_attrs.push(attr("action", action.to_string()));
@@ -245,7 +369,8 @@ fn execute_transfer_internal(
 }
 
 fn execute_transfer_batch_internal(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    operator: Option<&Addr>,
     from: Option<&Addr>,
     to: Option<&Addr>,
     batch: &Vec<(TokenId, Uint128)>,
@@ -267,6 +392,10 @@ fn execute_transfer_batch_internal(
         }
 
         let amt = *amount;
+        if let (TransferAction::Transfer, Some(operator), Some(from_addr), Some(to_addr)) = (action, operator, from, to) {
+            check_transfer_policy(deps.branch(), operator, from_addr, to_addr, token_id, amt)?;
+        }
+
         if let Some(from_addr) = from {
             BALANCES.update(
                 deps.storage,
@@ -275,6 +404,7 @@ fn execute_transfer_batch_internal(
                     Ok(balance.unwrap_or_default().checked_sub(amt)?)
                 },
             )?;
+            clamp_bound_balance(deps.storage, from_addr, token_id)?;
         }
 
         if let Some(to_addr) = to {
@@ -286,6 +416,7 @@ fn execute_transfer_batch_internal(
                 },
             )?;
         }
+        update_nft_owner(deps.storage, to, token_id, amt)?;
 
         if action != TransferAction::Transfer{
             let token_supply = TOKEN_SUPPLIES.load(deps.storage,&token_id)?;
@@ -308,6 +439,7 @@ fn execute_transfer_batch_internal(
                 TOKEN_SUPPLIES.save(deps.storage,&token_id,&new_token_supply)?;
             }
         }
+        update_token_stats(deps.storage, action, from, to, token_id, amt)?;
 
         resp_attrs.push(attr(format!("token_id[{}]",index),token_id));
         resp_attrs.push(attr(format!("amount[{}]",index),amt));
@@ -319,6 +451,102 @@ fn execute_transfer_batch_internal(
     Ok(Response::new().add_attributes(resp_attrs))
 }
 
+/// Keeps NFT_OWNERS in step with the balances of NonFungible tokens
+fn update_nft_owner(
+    storage: &mut dyn Storage,
+    to: Option<&Addr>,
+    token_id: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() || !matches!(load_token_type(storage, token_id)?, TokenType::NonFungible { .. }) {
+        return Ok(());
+    }
+    match to {
+        Some(to_addr) => NFT_OWNERS.save(storage, token_id, to_addr),
+        None => {
+            NFT_OWNERS.remove(storage, token_id);
+            Ok(())
+        }
+    }
+}
+
+/// Id a mint of token_id credits, NonFungible tokens only mint amount 1.
+/// Minting a serial base type defines its next serial, which starts with the base's uri and policy.
+fn resolve_mint_id(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    amount: Uint128,
+) -> Result<String, ContractError> {
+    let serials = match load_token_type(storage, token_id)? {
+        TokenType::Fungible { .. } => return Ok(token_id.to_string()),
+        TokenType::NonFungible { serials } => serials,
+    };
+    if amount != Uint128::one() {
+        return Err(ContractError::NonFungibleAmount{});
+    }
+    if !serials {
+        return Ok(token_id.to_string());
+    }
+
+    // the base supply counts every serial ever minted
+    let mut base_supply = TOKEN_SUPPLIES.load(storage, token_id)?;
+    base_supply.total_supply += Uint128::one();
+    if base_supply.total_supply > base_supply.max_supply {
+        return Err(ContractError::ExceedMaxSupply{});
+    }
+    TOKEN_SUPPLIES.save(storage, token_id, &base_supply)?;
+
+    let serial_id = serial_token_id(token_id, base_supply.total_supply.u128());
+    let token_uri = TOKENS.load(storage, token_id)?;
+    TOKENS.save(storage, &serial_id, &token_uri)?;
+    TOKEN_SUPPLIES.save(storage, &serial_id, &TokenSupply {
+        total_supply: Uint128::zero(),
+        max_supply: Uint128::one(),
+    })?;
+    TOKEN_TYPES.save(storage, &serial_id, &TokenType::NonFungible { serials: false })?;
+    if let Some(policy) = TOKEN_TRANSFER_POLICIES.may_load(storage, token_id)? {
+        TOKEN_TRANSFER_POLICIES.save(storage, &serial_id, &policy)?;
+    }
+    Ok(serial_id)
+}
+
+/// Counts the movement in TOKEN_STATS, call after balances were updated.
+fn update_token_stats(
+    storage: &mut dyn Storage,
+    action: TransferAction,
+    from: Option<&Addr>,
+    to: Option<&Addr>,
+    token_id: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    let mut stats = TOKEN_STATS.may_load(storage, token_id)?.unwrap_or_default();
+    match action {
+        TransferAction::Mint => stats.minted += amount,
+        TransferAction::Burn => stats.burned += amount,
+        TransferAction::Transfer => stats.transfers += 1,
+        TransferAction::None => {}
+    }
+
+    // a holder appears when the whole balance just arrived, and leaves when it hit zero.
+    // Balances the holder backfill did not get to yet are counted by it instead.
+    if !amount.is_zero() && from != to {
+        if let Some(from_addr) = from {
+            if is_holder_counted(storage, from_addr, token_id)?
+                && BALANCES.may_load(storage, (from_addr, token_id))?.unwrap_or_default().is_zero()
+            {
+                stats.holders = stats.holders.saturating_sub(1);
+            }
+        }
+        if let Some(to_addr) = to {
+            if is_holder_counted(storage, to_addr, token_id)?
+                && BALANCES.may_load(storage, (to_addr, token_id))?.unwrap_or_default() == amount
+            {
+                stats.holders += 1;
+            }
+        }
+    }
+    TOKEN_STATS.save(storage, token_id, &stats)
+}
 
 /// returns true iff the sender can execute approve or reject on the contract
 fn check_can_approve(deps: Deps,env : &Env, owner: &Addr, operator: &Addr) -> bool {
@@ -331,29 +559,71 @@ fn check_can_approve(deps: Deps,env : &Env, owner: &Addr, operator: &Addr) -> bo
         });
 }
 
+/// An operator sending for from needs a live ApproveAll and to be a cooperative,
+/// or else spends from's Approve allowance of every token in batch.
 fn check_can_send(
-    deps: Deps,
+    deps: DepsMut,
     env: &Env,
     from: &Addr,
     sender: &Addr,
+    batch: &[(TokenId, Uint128)],
 ) -> Result<(), ContractError> {
-        if !check_as_contract_owner(deps,&sender) 
-        {
-            if *from != *sender {
-                if !check_as_cooperative(deps,&sender,false,false) {
-                    return Err(ContractError::Unauthorized{});
-                }
-            }
-            if !check_can_approve(deps, env, &from, &sender){
-                return Err(ContractError::Unauthorized{});
-            }
-            
+        if !check_as_contract_owner(deps.as_ref(),&sender) && *from != *sender {
+            spend_operator_approval(deps, env, from, sender, batch)?;
         }
         return Ok(());   
 }
 
-pub fn execute_transfer(
+/// ApproveAll covers everything for a cooperative, otherwise every token of batch spends its Approve allowance.
+/// A failed check reverts any delete, so expired ApproveAll entries are only cleared here on success.
+fn spend_operator_approval(
     deps: DepsMut,
+    env: &Env,
+    owner: &Addr,
+    operator: &Addr,
+    batch: &[(TokenId, Uint128)],
+) -> Result<(), ContractError> {
+    let approved_all = check_can_approve(deps.as_ref(), env, owner, operator);
+    if !approved_all || !check_as_cooperative(deps.as_ref(), operator, false, false) {
+        for (token_id, amount) in batch {
+            spend_token_allowance(deps.storage, &env.block, owner, operator, token_id, *amount)?;
+        }
+    }
+    if !approved_all {
+        // an ApproveAll still stored here has expired
+        APPROVES.remove(deps.storage, (owner, operator));
+    }
+    prune_expired_approvals(deps.storage, &env.block, Some(owner), TOUCH_PRUNE_LIMIT)?;
+    Ok(())
+}
+
+/// Takes amount off the owner's Approve for operator, Unauthorized without a live one
+fn spend_token_allowance(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    owner: &Addr,
+    operator: &Addr,
+    token_id: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let key = (owner, operator, token_id);
+    let approval = match TOKEN_APPROVES.may_load(storage, key)? {
+        Some(approval) if !approval.expires.is_expired(block) => approval,
+        _ => return Err(ContractError::Unauthorized{}),
+    };
+    let amount_left = approval.amount
+        .checked_sub(amount)
+        .map_err(|_| ContractError::InsufficientAllowance{token_id: token_id.to_string()})?;
+    if amount_left.is_zero() {
+        TOKEN_APPROVES.remove(storage, key);
+    } else {
+        TOKEN_APPROVES.save(storage, key, &TokenApproval { amount: amount_left, ..approval })?;
+    }
+    Ok(())
+}
+
+pub fn execute_transfer(
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     to : String,
@@ -361,14 +631,14 @@ pub fn execute_transfer(
     amount: Uint128,
 ) -> Result<Response, ContractError> {
 
-    check_can_send(deps.as_ref(), &env, &info.sender, &info.sender)?;
+    check_can_send(deps.branch(), &env, &info.sender, &info.sender, &[(token_id.clone(), amount)])?;
     
     let to_addr = deps.api.addr_validate(&to)?;
-    Ok(execute_transfer_internal( deps,Some(&info.sender),Some(&to_addr),&token_id,amount)?)
+    Ok(execute_transfer_internal(deps, Some(&info.sender), Some(&info.sender),Some(&to_addr),&token_id,amount)?)
 }
 
 pub fn execute_send(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     contract : String,
@@ -377,10 +647,10 @@ pub fn execute_send(
     msg: Binary,
 ) -> Result<Response, ContractError> {
 
-    check_can_send(deps.as_ref(), &env, &info.sender, &info.sender)?;
+    check_can_send(deps.branch(), &env, &info.sender, &info.sender, &[(token_id.clone(), amount)])?;
 
     let to_addr = deps.api.addr_validate(&contract)?;
-    let rsp = execute_transfer_internal( deps,Some(&info.sender),Some(&to_addr),&token_id,amount)?;
+    let rsp = execute_transfer_internal(deps, Some(&info.sender), Some(&info.sender),Some(&to_addr),&token_id,amount)?;
     let send = Cw1155ReceiveMsg {
         operator: info.sender.to_string(),
         from: Some(info.sender.to_string()),
@@ -392,7 +662,7 @@ pub fn execute_send(
 }
 
 pub fn execute_transfer_from(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     from: String,
@@ -403,14 +673,14 @@ pub fn execute_transfer_from(
 
     let from_addr = deps.api.addr_validate(&from)?;
     
-    check_can_send(deps.as_ref(), &env, &from_addr, &info.sender)?;
+    check_can_send(deps.branch(), &env, &from_addr, &info.sender, &[(token_id.clone(), amount)])?;
 
     let to_addr = deps.api.addr_validate(&to)?;
-    Ok(execute_transfer_internal( deps,Some(&from_addr),Some(&to_addr),&token_id,amount)?)
+    Ok(execute_transfer_internal(deps, Some(&info.sender), Some(&from_addr),Some(&to_addr),&token_id,amount)?)
 }
 
 pub fn execute_send_from(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     from: String,
@@ -422,10 +692,10 @@ pub fn execute_send_from(
 
     let from_addr = deps.api.addr_validate(&from)?;
     
-    check_can_send(deps.as_ref(), &env, &from_addr, &info.sender)?;
+    check_can_send(deps.branch(), &env, &from_addr, &info.sender, &[(token_id.clone(), amount)])?;
 
     let to_addr = deps.api.addr_validate(&contract)?;
-    let rsp = execute_transfer_internal( deps,Some(&from_addr),Some(&to_addr),&token_id,amount)?;
+    let rsp = execute_transfer_internal(deps, Some(&info.sender), Some(&from_addr),Some(&to_addr),&token_id,amount)?;
     let send = Cw1155ReceiveMsg {
         operator: info.sender.to_string(),
         from: Some(from),
@@ -454,6 +724,26 @@ pub fn check_as_cooperative(
         }
     );
 }
+/// A cooperative only mints and burns token ids inside its scope
+pub fn check_cooperative_scope(
+    deps: Deps,
+    cooperative: &Addr,
+    token_id: &str,
+) -> Result<(), ContractError> {
+    let base_id = base_token_id(token_id);
+    let in_scope = match COOPERATIVES.load(deps.storage,cooperative)?.scope {
+        CooperativeScope::All => true,
+        CooperativeScope::TokenIds { token_ids } => token_ids.iter().any(|id| id == token_id || id == base_id),
+        CooperativeScope::Category { category } => {
+            TOKEN_CATEGORIES.may_load(deps.storage,base_id)?.as_ref() == Some(&category)
+        }
+    };
+    if !in_scope {
+        return Err(ContractError::OutOfCooperativeScope{token_id:token_id.to_string()});
+    }
+    Ok(())
+}
+
 pub fn check_as_contract_owner(
     deps: Deps,
     address_to_check: &Addr,
@@ -473,6 +763,7 @@ pub fn execute_mint(
     to: String,
     token_id: TokenId,
     amount: Uint128,
+    msg: Option<Binary>,
 ) -> Result<Response, ContractError> {
     
     if amount == Uint128::zero() {
@@ -483,14 +774,89 @@ pub fn execute_mint(
         if !check_as_cooperative(deps.as_ref(),&info.sender,true,false) {
             return Err(ContractError::Unauthorized {});
         }
+        check_cooperative_scope(deps.as_ref(),&info.sender,&token_id)?;
     }
 
     let to_addr = deps.api.addr_validate(&to)?;
-    Ok(execute_transfer_internal(deps, None, Some(&to_addr), &token_id, amount)?)
+    let token_id = resolve_mint_id(deps.storage, &token_id, amount)?;
+    let mut rsp = execute_transfer_internal(deps, Some(&info.sender), None, Some(&to_addr), &token_id, amount)?;
+    if let Some(msg) = msg {
+        let send = Cw1155ReceiveMsg {
+            operator: info.sender.to_string(),
+            from: None,
+            amount,
+            token_id,
+            msg,
+        };
+        rsp = rsp.add_message(send.into_cosmos_msg(to)?);
+    }
+    Ok(rsp)
 }
 
-pub fn execute_burn(
+pub fn execute_set_voucher_signer(
     deps: DepsMut,
+    info: MessageInfo,
+    pubkey: Option<Binary>,
+) -> Result<Response, ContractError> {
+
+    if !check_as_contract_owner(deps.as_ref(),&info.sender) &&
+       !check_as_cooperative(deps.as_ref(),&info.sender,true,false) {
+        return Err(ContractError::Unauthorized{});
+    }
+    match &pubkey {
+        Some(pubkey) if !is_secp256k1_pubkey(pubkey) => return Err(ContractError::InvalidVoucherPubkey{}),
+        Some(pubkey) => VOUCHER_SIGNERS.save(deps.storage,&info.sender,pubkey)?,
+        None => VOUCHER_SIGNERS.remove(deps.storage,&info.sender),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action","set_voucher_signer"),
+        attr("signer",info.sender),
+        attr("pubkey",pubkey.map(|pubkey| pubkey.to_base64()).unwrap_or_default()),
+    ]))
+}
+
+/// 33 byte compressed or 65 byte uncompressed SEC1 encoding, as secp256k1_verify takes it
+fn is_secp256k1_pubkey(pubkey: &[u8]) -> bool {
+    matches!((pubkey.len(), pubkey.first()), (33, Some(0x02 | 0x03)) | (65, Some(0x04)))
+}
+
+/// Mints as if the signer sent Mint, so it needs the signer's mint rights
+pub fn execute_redeem_voucher(
+    deps: DepsMut,
+    env: Env,
+    voucher: MintVoucher,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+
+    let signer = deps.api.addr_validate(&voucher.signer)?;
+    let pubkey = VOUCHER_SIGNERS.may_load(deps.storage,&signer)?
+        .ok_or(ContractError::VoucherSignerNotSet{})?;
+    if voucher.expires.is_expired(&env.block) {
+        return Err(ContractError::VoucherExpired{});
+    }
+    if REDEEMED_VOUCHERS.has(deps.storage,(&signer,voucher.nonce)) {
+        return Err(ContractError::VoucherRedeemed{nonce:voucher.nonce});
+    }
+
+    let digest = Sha256::new()
+        .chain_update(env.contract.address.as_bytes())
+        .chain_update(to_vec(&voucher)?)
+        .finalize();
+    if !deps.api.secp256k1_verify(&digest,&signature,&pubkey).map_err(StdError::from)? {
+        return Err(ContractError::InvalidVoucherSignature{});
+    }
+    REDEEMED_VOUCHERS.save(deps.storage,(&signer,voucher.nonce),&true)?;
+
+    let info = MessageInfo { sender: signer, funds: vec![] };
+    let response = execute_mint(deps,info,voucher.recipient,voucher.token_id,voucher.amount,None)?;
+    Ok(response.add_attribute("voucher_nonce",voucher.nonce.to_string()))
+}
+
+/// Burning for someone else takes a cooperative with burn rights for token_id,
+/// approved by from like any other operator (ApproveAll or Approve allowance).
+pub fn execute_burn(
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     from: String,
@@ -501,31 +867,32 @@ pub fn execute_burn(
     let from_addr = deps.api.addr_validate(&from)?;
     if !check_as_contract_owner(deps.as_ref(),&info.sender) {
         if from_addr != info.sender{
-            if !check_as_cooperative(deps.as_ref(),&info.sender,false,true) ||
-               !check_can_approve(deps.as_ref(),&env,&from_addr,&info.sender) {
+            if !check_as_cooperative(deps.as_ref(),&info.sender,false,true) {
                 return Err(ContractError::Unauthorized{});
             }
+            check_cooperative_scope(deps.as_ref(),&info.sender,&token_id)?;
+            spend_operator_approval(deps.branch(),&env,&from_addr,&info.sender,&[(token_id.clone(), amount)])?;
         }
     }
     // whoever can transfer these tokens can burn
-    Ok(execute_transfer_internal(deps, Some(&from_addr), None, &token_id, amount)?)
+    Ok(execute_transfer_internal(deps, Some(&info.sender), Some(&from_addr), None, &token_id, amount)?)
 }
 
 pub fn execute_batch_transfer(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     to : String,
     batch: Vec<(TokenId, Uint128)>,
 ) -> Result<Response, ContractError> {
     
-    check_can_send(deps.as_ref(), &env, &info.sender, &info.sender)?;
+    check_can_send(deps.branch(), &env, &info.sender, &info.sender, &batch)?;
     let to_addr = deps.api.addr_validate(&to)?;
-    Ok(execute_transfer_batch_internal(deps,Some(&info.sender),Some(&to_addr),&batch)?)
+    Ok(execute_transfer_batch_internal(deps, Some(&info.sender), Some(&info.sender),Some(&to_addr),&batch)?)
 }
 
 pub fn execute_batch_transfer_from(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     from: String,
@@ -535,14 +902,14 @@ pub fn execute_batch_transfer_from(
     
     let from_addr = deps.api.addr_validate(&from)?;
 
-    check_can_send(deps.as_ref(), &env, &from_addr, &info.sender)?;
+    check_can_send(deps.branch(), &env, &from_addr, &info.sender, &batch)?;
 
     let to_addr = deps.api.addr_validate(&to)?;
-    Ok(execute_transfer_batch_internal(deps,Some(&from_addr),Some(&to_addr),&batch)?)
+    Ok(execute_transfer_batch_internal(deps, Some(&info.sender), Some(&from_addr),Some(&to_addr),&batch)?)
 }
 
 pub fn execute_batch_send(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     contract : String,
@@ -552,9 +919,9 @@ pub fn execute_batch_send(
     
     let to_addr = deps.api.addr_validate(&contract)?;
 
-    check_can_send(deps.as_ref(), &env, &info.sender, &info.sender)?;
+    check_can_send(deps.branch(), &env, &info.sender, &info.sender, &batch)?;
 
-    let rsp = execute_transfer_batch_internal(deps,Some(&info.sender),Some(&to_addr),&batch)?;
+    let rsp = execute_transfer_batch_internal(deps, Some(&info.sender), Some(&info.sender),Some(&to_addr),&batch)?;
     let send = Cw1155BatchReceiveMsg {
         operator: info.sender.to_string(),
         from: Some(info.sender.to_string()),
@@ -565,7 +932,7 @@ pub fn execute_batch_send(
 }
 
 pub fn execute_batch_send_from(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     from: String,
@@ -577,9 +944,9 @@ pub fn execute_batch_send_from(
     let from_addr = deps.api.addr_validate(&from)?;
     let to_addr = deps.api.addr_validate(&contract)?;
 
-    check_can_send(deps.as_ref(), &env, &from_addr, &info.sender)?;
+    check_can_send(deps.branch(), &env, &from_addr, &info.sender, &batch)?;
 
-    let rsp = execute_transfer_batch_internal(deps,Some(&from_addr),Some(&to_addr),&batch)?;
+    let rsp = execute_transfer_batch_internal(deps, Some(&info.sender), Some(&from_addr),Some(&to_addr),&batch)?;
     let send = Cw1155BatchReceiveMsg {
         operator: info.sender.to_string(),
         from: Some(from),
@@ -594,20 +961,38 @@ pub fn execute_batch_mint(
     info: MessageInfo,
     to: String,
     batch: Vec<(TokenId, Uint128)>,
+    msg: Option<Binary>,
 ) -> Result<Response, ContractError> {
 
     if !check_as_contract_owner(deps.as_ref(),&info.sender) {
         if !check_as_cooperative(deps.as_ref(),&info.sender,true,false) {
             return Err(ContractError::Unauthorized {});
         }
+        for (token_id, _) in batch.iter() {
+            check_cooperative_scope(deps.as_ref(),&info.sender,token_id)?;
+        }
     }
 
     let to_addr = deps.api.addr_validate(&to)?;
-    Ok(execute_transfer_batch_internal(deps, None, Some(&to_addr), &batch)?)
+    let batch = batch
+        .into_iter()
+        .map(|(token_id, amount)| Ok((resolve_mint_id(deps.storage, &token_id, amount)?, amount)))
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    let mut rsp = execute_transfer_batch_internal(deps, Some(&info.sender), None, Some(&to_addr), &batch)?;
+    if let Some(msg) = msg {
+        let send = Cw1155BatchReceiveMsg {
+            operator: info.sender.to_string(),
+            from: None,
+            batch,
+            msg,
+        };
+        rsp = rsp.add_message(send.into_cosmos_msg(to)?);
+    }
+    Ok(rsp)
 }
 
 pub fn execute_batch_burn(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     from: String,
@@ -617,13 +1002,16 @@ pub fn execute_batch_burn(
     let from_addr = deps.api.addr_validate(&from)?;
     if !check_as_contract_owner(deps.as_ref(),&info.sender) {
         if from_addr != info.sender{
-            if !check_as_cooperative(deps.as_ref(),&info.sender,false,true) ||
-               !check_can_approve(deps.as_ref(),&env,&from_addr,&info.sender) {
+            if !check_as_cooperative(deps.as_ref(),&info.sender,false,true) {
                 return Err(ContractError::Unauthorized{});
             }
+            for (token_id, _) in batch.iter() {
+                check_cooperative_scope(deps.as_ref(),&info.sender,token_id)?;
+            }
+            spend_operator_approval(deps.branch(),&env,&from_addr,&info.sender,&batch)?;
         }
     }
-    Ok(execute_transfer_batch_internal(deps, Some(&from_addr), None, &batch)?)
+    Ok(execute_transfer_batch_internal(deps, Some(&info.sender), Some(&from_addr), None, &batch)?)
 }
 
 pub fn execute_approve_all(
@@ -666,127 +1054,468 @@ pub fn execute_revoke_all(
     ]))
 }
 
-
-pub fn execute_define_token(
+pub fn execute_approve(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    token_uri: String,
-    max_supply : Uint128,
+    operator: String,
+    token_id: TokenId,
+    amount: Uint128,
+    expires: Option<Expiration>,
 ) -> Result<Response, ContractError> {
 
-    if !check_as_contract_owner(deps.as_ref(),&info.sender){
-        return Err(ContractError::Unauthorized{});
+    // reject expired data as invalid
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    if !TOKENS.has(deps.storage, &token_id) {
+        return Err(ContractError::TokenUndefined{token_id});
     }
-    
-    let mut token_id = TOKEN_RUNNING_NO.may_load(deps.storage)?.unwrap_or_default();
-    let token_id_str: String = token_id.to_string();
-    TOKENS.save(deps.storage,&token_id_str,&token_uri)?;
 
-    token_id = token_id + 1;
-    TOKEN_RUNNING_NO.save(deps.storage,&token_id)?;
-    
-    let token_supply = TokenSupply{
-        total_supply : Uint128::from(0u128),
-        max_supply : max_supply,
-    };
-    TOKEN_SUPPLIES.save(deps.storage,&token_id_str,&token_supply)?;
+    let operator_addr = deps.api.addr_validate(&operator)?;
+    let key = (&info.sender, &operator_addr, token_id.as_str());
+    if amount.is_zero() {
+        TOKEN_APPROVES.remove(deps.storage, key);
+    } else {
+        TOKEN_APPROVES.save(deps.storage, key, &TokenApproval { amount, expires })?;
+    }
 
     Ok(Response::new().add_attributes(vec![
-        attr("action","define_token"),
-        attr("creator",info.sender.to_string()),
-        attr("token_id",token_id_str),
+        attr("action","approve"),
+        attr("sender",info.sender),
+        attr("operator",operator),
+        attr("token_id",token_id),
+        attr("amount",amount),
     ]))
 }
 
-pub fn execute_update_token_uri(
+/// Deletes the expired ApproveAll entries among the next limit operators, returns how many.
+/// An owner's operators and the whole map each keep their own cursor, wrapping around at the end,
+/// so repeated calls reach every entry however many live ones come first.
+pub fn prune_expired_approvals(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    owner: Option<&Addr>,
+    limit: usize,
+) -> StdResult<u32> {
+    let scanned: Vec<((Addr, Addr), Expiration)> = match owner {
+        Some(owner) => {
+            let cursor = APPROVES_OWNER_PRUNE_CURSORS.may_load(storage, owner)?;
+            let start = cursor.as_ref().map(Bound::exclusive);
+            let scanned = APPROVES
+                .prefix(owner)
+                .range(storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(operator, expires)| ((owner.clone(), operator), expires)))
+                .collect::<StdResult<Vec<_>>>()?;
+            match scanned.last() {
+                Some(((_, last), _)) if scanned.len() == limit => APPROVES_OWNER_PRUNE_CURSORS.save(storage, owner, last)?,
+                _ => APPROVES_OWNER_PRUNE_CURSORS.remove(storage, owner),
+            }
+            scanned
+        }
+        None => {
+            let cursor = APPROVES_PRUNE_CURSOR.may_load(storage)?;
+            let start = cursor.as_ref().map(|(owner, operator)| Bound::exclusive((owner, operator)));
+            let scanned = APPROVES
+                .range(storage, start, None, Order::Ascending)
+                .take(limit)
+                .collect::<StdResult<Vec<_>>>()?;
+            match scanned.last() {
+                Some((last, _)) if scanned.len() == limit => APPROVES_PRUNE_CURSOR.save(storage, last)?,
+                _ => APPROVES_PRUNE_CURSOR.remove(storage),
+            }
+            scanned
+        }
+    };
+
+    let mut pruned = 0u32;
+    for ((owner, operator), expires) in scanned {
+        if expires.is_expired(block) {
+            APPROVES.remove(storage, (&owner, &operator));
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+pub fn execute_prune_expired(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    token_id : String,
-    token_uri: String,
+    owner: Option<String>,
+    limit: u32,
 ) -> Result<Response, ContractError> {
 
-    if !check_as_contract_owner(deps.as_ref(),&info.sender){
-        return Err(ContractError::Unauthorized{});
-    }
-    if !TOKENS.has(deps.storage,&token_id){
-        return Err(ContractError::TokenUndefined{token_id:token_id});
-    }
+    let owner_addr = owner.map(|owner| deps.api.addr_validate(&owner)).transpose()?;
+    let limit = limit.min(MAX_PRUNE_LIMIT) as usize;
+    let pruned = prune_expired_approvals(deps.storage, &env.block, owner_addr.as_ref(), limit)?;
 
-    TOKENS.save(deps.storage,&token_id,&token_uri)?;
     Ok(Response::new().add_attributes(vec![
-        attr("action","update_token_uri"),
-        attr("token_id",token_id),
-        attr("token_uri",token_uri),
+        attr("action","prune_expired"),
+        attr("by",info.sender),
+        attr("pruned",pruned.to_string()),
     ]))
 }
 
-pub fn execute_update_max_supply(
+/// Marks the next limit running numbers as definitions, the last one ends the migration.
+pub fn execute_migrate_definitions(
     deps: DepsMut,
     info: MessageInfo,
-    token_id : String,
-    max_supply: Uint128,
+    limit: u32,
 ) -> Result<Response, ContractError> {
-
-    if !check_as_contract_owner(deps.as_ref(),&info.sender){
-        return Err(ContractError::Unauthorized{});
-    }
-    if !TOKENS.has(deps.storage,&token_id){
-        return Err(ContractError::TokenUndefined{token_id:token_id});
+    let cursor = DEFINITION_MIGRATION
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoDefinitionMigration{})?;
+    let limit = limit.clamp(1, MAX_MIGRATE_LIMIT) as u128;
+
+    let running_no = TOKEN_RUNNING_NO.may_load(deps.storage)?.unwrap_or_default();
+    let end = running_no.min(cursor + limit);
+    for token_no in cursor..end {
+        let token_id = token_no.to_string();
+        if TOKENS.has(deps.storage, &token_id) {
+            TOKEN_DEFINITIONS.save(deps.storage, &token_id, &true)?;
+        }
     }
 
-    let token_supply = TOKEN_SUPPLIES.load(deps.storage,&token_id)?;
-    if max_supply < token_supply.total_supply {
-        return Err(ContractError::ExceedMaxSupply{});
+    let done = end == running_no;
+    if done {
+        DEFINITION_MIGRATION.remove(deps.storage);
+    } else {
+        DEFINITION_MIGRATION.save(deps.storage, &end)?;
     }
-
-    let new_token_supply = TokenSupply{
-        max_supply : max_supply,
-        ..token_supply
-    };
-    TOKEN_SUPPLIES.save(deps.storage,&token_id,&new_token_supply)?;
-    
     Ok(Response::new().add_attributes(vec![
-        attr("action","update_max_supply"),
-        attr("token_id",token_id),
-        attr("max_supply",max_supply),
+        attr("action","migrate_definitions"),
+        attr("by",info.sender),
+        attr("migrated",(end - cursor).to_string()),
+        attr("done",done.to_string()),
     ]))
 }
 
-pub fn execute_set_cooperative(
+/// Counts the holders of the next limit balances, the last page ends the migration.
+pub fn execute_migrate_holders(
     deps: DepsMut,
     info: MessageInfo,
-    cooperative: String,
-    can_mint_for : bool,
-    can_burn_from : bool,
-
+    limit: u32,
 ) -> Result<Response, ContractError> {
-    
-    if !check_as_contract_owner(deps.as_ref(),&info.sender){
-        return Err(ContractError::Unauthorized {});
+    let mut migration = HOLDER_MIGRATION
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoHolderMigration{})?;
+    let limit = limit.clamp(1, MAX_MIGRATE_LIMIT) as usize;
+
+    let start = migration.cursor.as_ref().map(|(owner, token_id)| Bound::exclusive((owner, token_id.as_str())));
+    let page = BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    for ((_, token_id), balance) in page.iter() {
+        if !balance.is_zero() {
+            let mut stats = TOKEN_STATS.may_load(deps.storage, token_id)?.unwrap_or_default();
+            stats.holders += 1;
+            TOKEN_STATS.save(deps.storage, token_id, &stats)?;
+        }
     }
-    let cooperative_addr = deps.api.addr_validate(&cooperative)?;
-    let cooperative_data = CooperativeData{
-        can_mint_for : can_mint_for,
-        can_burn_from : can_burn_from,
-    };
-    COOPERATIVES.save(deps.storage,&cooperative_addr,&cooperative_data)?;
-    let res = Response::new()
-        .add_attribute("action", "set_cooperative")
-        .add_attribute("cooperative", cooperative_addr)
-        .add_attribute("can_mint_for", can_mint_for.to_string())
-        .add_attribute("can_burn_from", can_burn_from.to_string());
 
-    Ok(res)
+    let done = page.len() < limit;
+    if done {
+        HOLDER_MIGRATION.remove(deps.storage);
+    } else {
+        migration.cursor = page.last().map(|(key, _)| key.clone());
+        HOLDER_MIGRATION.save(deps.storage, &migration)?;
+    }
+    Ok(Response::new().add_attributes(vec![
+        attr("action","migrate_holders"),
+        attr("by",info.sender),
+        attr("migrated",page.len().to_string()),
+        attr("done",done.to_string()),
+    ]))
 }
 
-pub fn execute_unset_cooperative(
+pub fn execute_define_token(
     deps: DepsMut,
     info: MessageInfo,
-    cooperative: String,
+    definition : TokenDefinition,
 ) -> Result<Response, ContractError> {
-    
+
     if !check_as_contract_owner(deps.as_ref(),&info.sender){
-        return Err(ContractError::Unauthorized {});
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let token_id_str = define_token(deps,definition)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action","define_token"),
+        attr("creator",info.sender.to_string()),
+        attr("token_id",token_id_str),
+    ]))
+}
+
+pub fn execute_define_tokens(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    tokens : Vec<TokenDefinition>,
+) -> Result<Response, ContractError> {
+
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let mut rsp = Response::new().add_attributes(vec![
+        attr("action","define_tokens"),
+        attr("creator",info.sender.to_string()),
+    ]);
+    for (i,definition) in tokens.into_iter().enumerate() {
+        let token_id = define_token(deps.branch(),definition)?;
+        rsp = rsp.add_attribute(format!("token_id[{}]",i),token_id);
+    }
+    Ok(rsp)
+}
+
+/// Saves one token definition and returns its id, the next running number unless it picks one
+fn define_token(
+    deps: DepsMut,
+    definition : TokenDefinition,
+) -> Result<String, ContractError> {
+
+    let running_no = TOKEN_RUNNING_NO.may_load(deps.storage)?.unwrap_or_default();
+    let chosen_id = definition.token_id.is_some();
+    let token_id = match definition.token_id {
+        Some(token_id) => {
+            // numbers belong to the running number and `:<number>` to serials
+            let serial_like = token_id.rsplit_once(':').is_some_and(|(_, serial)| serial.parse::<u128>().is_ok());
+            if token_id.is_empty() || token_id.parse::<u128>().is_ok() || serial_like {
+                return Err(ContractError::InvalidTokenId{token_id});
+            }
+            if TOKENS.has(deps.storage,&token_id) {
+                return Err(ContractError::TokenAlreadyDefined{token_id});
+            }
+            token_id
+        }
+        None => running_no.to_string(),
+    };
+    if definition.token_type == Some(TokenType::NonFungible{serials:false}) && definition.max_supply != Uint128::one() {
+        return Err(ContractError::NonFungibleSupply{token_id});
+    }
+    if let Some(royalty) = &definition.royalty {
+        deps.api.addr_validate(&royalty.payment_address)?;
+        if royalty.share > Decimal::one() {
+            return Err(ContractError::InvalidRoyalty{});
+        }
+        TOKEN_ROYALTIES.save(deps.storage,&token_id,royalty)?;
+    }
+
+    if !chosen_id {
+        TOKEN_RUNNING_NO.save(deps.storage,&(running_no + 1))?;
+    }
+    TOKENS.save(deps.storage,&token_id,&definition.token_uri)?;
+    TOKEN_DEFINITIONS.save(deps.storage,&token_id,&true)?;
+    if let Some(policy) = definition.transfer_policy {
+        TOKEN_TRANSFER_POLICIES.save(deps.storage,&token_id,&policy)?;
+    }
+    if let Some(token_type) = definition.token_type {
+        TOKEN_TYPES.save(deps.storage,&token_id,&token_type)?;
+    }
+    if let Some(category) = definition.category {
+        TOKEN_CATEGORIES.save(deps.storage,&token_id,&category)?;
+        CATEGORY_TOKENS.save(deps.storage,(&category,&token_id),&true)?;
+    }
+
+    let token_supply = TokenSupply{
+        total_supply : Uint128::from(0u128),
+        max_supply : definition.max_supply,
+    };
+    TOKEN_SUPPLIES.save(deps.storage,&token_id,&token_supply)?;
+    Ok(token_id)
+}
+
+pub fn execute_update_token_uri(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id : String,
+    token_uri: Option<String>,
+) -> Result<Response, ContractError> {
+
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized{});
+    }
+    if !TOKENS.has(deps.storage,&token_id){
+        return Err(ContractError::TokenUndefined{token_id:token_id});
+    }
+    if is_metadata_frozen(deps.storage,&token_id)? {
+        return Err(ContractError::MetadataFrozen{token_id});
+    }
+
+    TOKENS.save(deps.storage,&token_id,&token_uri)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action","update_token_uri"),
+        attr("token_id",token_id),
+        attr("token_uri",token_uri.unwrap_or_default()),
+    ]))
+}
+
+pub fn execute_update_base_uri(
+    deps: DepsMut,
+    info: MessageInfo,
+    base_uri : Option<String>,
+) -> Result<Response, ContractError> {
+
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized{});
+    }
+    if METADATA_FROZEN.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(ContractError::CollectionMetadataFrozen{});
+    }
+    match &base_uri {
+        Some(base_uri) => BASE_URI.save(deps.storage,base_uri)?,
+        None => BASE_URI.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action","update_base_uri"),
+        attr("by",info.sender),
+        attr("base_uri",base_uri.unwrap_or_default()),
+    ]))
+}
+
+pub fn execute_freeze_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id : Option<String>,
+) -> Result<Response, ContractError> {
+
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized{});
+    }
+    let event = match &token_id {
+        Some(token_id) => {
+            let token_uri = TOKENS.may_load(deps.storage,token_id)?
+                .ok_or_else(|| ContractError::TokenUndefined{token_id:token_id.clone()})?;
+            // pin the uri, a later base_uri update must not move it
+            if token_uri.is_none() {
+                let resolved = resolve_token_uri(deps.storage,token_id,None)?;
+                TOKENS.save(deps.storage,token_id,&resolved)?;
+            }
+            FROZEN_TOKENS.save(deps.storage,token_id,&true)?;
+            Event::new("metadata_frozen").add_attribute("token_id",token_id)
+        }
+        None => {
+            METADATA_FROZEN.save(deps.storage,&true)?;
+            Event::new("metadata_frozen").add_attribute("scope","collection")
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action","freeze_metadata"),
+        attr("by",info.sender),
+        attr("token_id",token_id.unwrap_or_default()),
+    ]).add_event(event))
+}
+
+pub fn execute_update_max_supply(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id : String,
+    max_supply: Uint128,
+) -> Result<Response, ContractError> {
+
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized{});
+    }
+    if !TOKENS.has(deps.storage,&token_id){
+        return Err(ContractError::TokenUndefined{token_id:token_id});
+    }
+
+    if load_token_type(deps.storage,&token_id)? == (TokenType::NonFungible{serials:false}) && max_supply > Uint128::one() {
+        return Err(ContractError::NonFungibleSupply{token_id});
+    }
+
+    let token_supply = TOKEN_SUPPLIES.load(deps.storage,&token_id)?;
+    if max_supply < token_supply.total_supply {
+        return Err(ContractError::ExceedMaxSupply{});
+    }
+
+    let new_token_supply = TokenSupply{
+        max_supply : max_supply,
+        ..token_supply
+    };
+    TOKEN_SUPPLIES.save(deps.storage,&token_id,&new_token_supply)?;
+    
+    Ok(Response::new().add_attributes(vec![
+        attr("action","update_max_supply"),
+        attr("token_id",token_id),
+        attr("max_supply",max_supply),
+    ]))
+}
+
+pub fn execute_set_token_category(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id : TokenId,
+    category : Option<String>,
+) -> Result<Response, ContractError> {
+
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized{});
+    }
+    if !TOKENS.has(deps.storage,&token_id){
+        return Err(ContractError::TokenUndefined{token_id});
+    }
+    if let Some(old) = TOKEN_CATEGORIES.may_load(deps.storage,&token_id)? {
+        CATEGORY_TOKENS.remove(deps.storage,(&old,&token_id));
+    }
+    match &category {
+        Some(category) => {
+            TOKEN_CATEGORIES.save(deps.storage,&token_id,category)?;
+            CATEGORY_TOKENS.save(deps.storage,(category,&token_id),&true)?;
+        }
+        None => TOKEN_CATEGORIES.remove(deps.storage,&token_id),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action","set_token_category"),
+        attr("token_id",token_id),
+        attr("category",category.unwrap_or_default()),
+    ]))
+}
+
+pub fn execute_set_cooperative(
+    deps: DepsMut,
+    info: MessageInfo,
+    cooperative: String,
+    can_mint_for : bool,
+    can_burn_from : bool,
+    scope : Option<CooperativeScope>,
+
+) -> Result<Response, ContractError> {
+    
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized {});
+    }
+    let cooperative_addr = deps.api.addr_validate(&cooperative)?;
+    let cooperative_data = CooperativeData{
+        can_mint_for : can_mint_for,
+        can_burn_from : can_burn_from,
+        scope : scope.unwrap_or_default(),
+    };
+    COOPERATIVES.save(deps.storage,&cooperative_addr,&cooperative_data)?;
+    let res = Response::new()
+        .add_attribute("action", "set_cooperative")
+        .add_attribute("cooperative", cooperative_addr)
+        .add_attribute("can_mint_for", can_mint_for.to_string())
+        .add_attribute("can_burn_from", can_burn_from.to_string());
+
+    Ok(res)
+}
+
+pub fn execute_unset_cooperative(
+    deps: DepsMut,
+    info: MessageInfo,
+    cooperative: String,
+) -> Result<Response, ContractError> {
+    
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized {});
     }
     
     let cooperative_addr = deps.api.addr_validate(&cooperative)?;
@@ -797,6 +1526,75 @@ pub fn execute_unset_cooperative(
     Ok(res)
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn execute_force_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    token_id: TokenId,
+    amount: Uint128,
+    reason: String,
+) -> Result<Response, ContractError> {
+
+    //Only contract owner can force transfer.
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized {});
+    }
+    if CLAWBACK_RENOUNCED.may_load(deps.storage)?.unwrap_or(false) {
+        return Err(ContractError::ClawbackRenounced {});
+    }
+    if reason.trim().is_empty() {
+        return Err(ContractError::EmptyClawbackReason {});
+    }
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let from_addr = deps.api.addr_validate(&from)?;
+    let to_addr = deps.api.addr_validate(&to)?;
+
+    let id = CLAWBACK_LOG
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(0, |last| last + 1);
+    let record = ClawbackRecord {
+        operator: info.sender.clone(),
+        from: from_addr.clone(),
+        to: to_addr.clone(),
+        token_id: token_id.clone(),
+        amount,
+        reason: reason.clone(),
+        height: env.block.height,
+        time: env.block.time,
+    };
+    CLAWBACK_LOG.save(deps.storage, id, &record)?;
+
+    // approvals and transfer policies are bypassed on purpose
+    let rsp = execute_transfer_internal(deps, None, Some(&from_addr), Some(&to_addr), &token_id, amount)?;
+    Ok(rsp.add_attributes(vec![
+        attr("clawback_by", info.sender),
+        attr("reason", reason),
+        attr("clawback_id", id.to_string()),
+    ]))
+}
+
+pub fn execute_renounce_clawback(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+
+    if !check_as_contract_owner(deps.as_ref(),&info.sender){
+        return Err(ContractError::Unauthorized {});
+    }
+    CLAWBACK_RENOUNCED.save(deps.storage, &true)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action","renounce_clawback"),
+        attr("by",info.sender),
+    ]))
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: Cw1155QueryMsg) -> StdResult<Binary> {
@@ -821,15 +1619,33 @@ pub fn query(deps: Deps, env: Env, msg: Cw1155QueryMsg) -> StdResult<Binary> {
         Cw1155QueryMsg::AllOperators {owner,include_expired,start_after,limit,} => {
             to_binary(&query_all_approvals(deps,env,owner,include_expired,start_after,limit)?)
         }
+        Cw1155QueryMsg::TokenAllowance { owner, operator, token_id } => {
+            to_binary(&query_token_allowance(deps,owner,operator,token_id)?)
+        }
+        Cw1155QueryMsg::TokenAllowances { owner, include_expired, start_after, limit } => {
+            to_binary(&query_token_allowances(deps,env,owner,include_expired,start_after,limit)?)
+        }
         Cw1155QueryMsg::TokenInfo { token_id } => {
             to_binary(&query_token_info(deps,token_id)?)
         }
+        Cw1155QueryMsg::OwnerOf { token_id } => {
+            to_binary(&query_owner_of(deps,token_id)?)
+        }
         Cw1155QueryMsg::Tokens { owner,start_after,limit} => {
             to_binary(&query_tokens(deps, owner, start_after, limit)?)
         }
         Cw1155QueryMsg::AllTokens { start_after, limit } => {
             to_binary(&query_all_tokens(deps, start_after, limit)?)
         }
+        Cw1155QueryMsg::TokensByCategory { category, start_after, limit } => {
+            to_binary(&query_tokens_by_category(deps, category, start_after, limit)?)
+        }
+        Cw1155QueryMsg::TokenDefinitions { start_after, limit } => {
+            to_binary(&query_token_definitions(deps, start_after, limit)?)
+        }
+        Cw1155QueryMsg::RoyaltyInfo { token_id, sale_price } => {
+            to_binary(&query_royalty_info(deps, token_id, sale_price)?)
+        }
         Cw1155QueryMsg::TokenSupply {token_id} => {
             to_binary(&query_token_supply(deps, token_id)?)
         }
@@ -841,6 +1657,19 @@ pub fn query(deps: Deps, env: Env, msg: Cw1155QueryMsg) -> StdResult<Binary> {
         Cw1155QueryMsg::LastTokenID {} => {
             to_binary(&query_last_token_id(deps)?)
         }
+        Cw1155QueryMsg::TransferPolicy { token_id } => {
+            to_binary(&query_transfer_policy(deps, token_id)?)
+        }
+        Cw1155QueryMsg::BoundBalance { owner, token_id } => {
+            to_binary(&query_bound_balance(deps, owner, token_id)?)
+        }
+        Cw1155QueryMsg::ClawbackLog { start_after, limit } => {
+            to_binary(&query_clawback_log(deps, start_after, limit)?)
+        }
+        Cw1155QueryMsg::Stats { token_id } => to_binary(&query_stats(deps, token_id)?),
+        Cw1155QueryMsg::IsMetadataFrozen { token_id } => to_binary(&query_metadata_frozen(deps, token_id)?),
+        Cw1155QueryMsg::IsVoucherRedeemed { signer, nonce } => to_binary(&query_voucher_redeemed(deps, signer, nonce)?),
+        Cw1155QueryMsg::CooperativeScope { address } => to_binary(&query_cooperative_scope(deps, address)?),
     }
 }
 
@@ -876,6 +1705,55 @@ fn query_all_approvals(
     Ok(OperatorsResponse { operators })
 }
 
+fn query_token_allowance(
+    deps: Deps,
+    owner: String,
+    operator: String,
+    token_id: TokenId,
+) -> StdResult<TokenAllowanceResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let operator_addr = deps.api.addr_validate(&operator)?;
+    let approval = TOKEN_APPROVES.may_load(deps.storage, (&owner_addr, &operator_addr, &token_id))?;
+    Ok(match approval {
+        Some(approval) => TokenAllowanceResponse { amount: approval.amount, expires: approval.expires },
+        None => TokenAllowanceResponse { amount: Uint128::zero(), expires: Expiration::default() },
+    })
+}
+
+fn query_token_allowances(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    include_expired: bool,
+    start_after: Option<(String, TokenId)>,
+    limit: Option<u32>,
+) -> StdResult<TokenAllowancesResponse> {
+
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start_after = start_after
+        .map(|(operator, token_id)| Ok::<_, StdError>((deps.api.addr_validate(&operator)?, token_id)))
+        .transpose()?;
+    let start = start_after.as_ref().map(|(operator, token_id)| Bound::exclusive((operator, token_id.as_str())));
+
+    let allowances = TOKEN_APPROVES
+        .sub_prefix(&owner_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|r| include_expired || r.is_err() || !r.as_ref().unwrap().1.expires.is_expired(&env.block))
+        .take(limit)
+        .map(|item| {
+            item.map(|((operator, token_id), approval)| TokenAllowanceInfo {
+                operator: operator.to_string(),
+                token_id,
+                amount: approval.amount,
+                expires: approval.expires,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(TokenAllowancesResponse { allowances })
+}
+
 fn query_tokens(
     deps: Deps,
     owner: String,
@@ -912,11 +1790,130 @@ fn query_all_tokens(
     Ok(TokensResponse { tokens })
 }
 
-fn query_last_token_id(
+fn query_tokens_by_category(
     deps: Deps,
-) -> StdResult<u128> {
-    Ok(TOKEN_RUNNING_NO.may_load(deps.storage)?.unwrap_or_default())
-}
+    category: String,
+    start_after: Option<TokenId>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let tokens = CATEGORY_TOKENS
+        .prefix(&category)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(TokensResponse { tokens })
+}
+
+fn query_token_definitions(
+    deps: Deps,
+    start_after: Option<TokenId>,
+    limit: Option<u32>,
+) -> StdResult<TokenDefinitionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+    let definitions = TOKEN_DEFINITIONS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let token_id = item?;
+            Ok(TokenDefinitionInfo {
+                token_uri: TOKENS.load(deps.storage, &token_id)?,
+                supply: TOKEN_SUPPLIES.load(deps.storage, &token_id)?,
+                transfer_policy: load_transfer_policy(deps.storage, &token_id)?,
+                token_type: load_token_type(deps.storage, &token_id)?,
+                category: TOKEN_CATEGORIES.may_load(deps.storage, &token_id)?,
+                royalty: TOKEN_ROYALTIES.may_load(deps.storage, &token_id)?,
+                token_id,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(TokenDefinitionsResponse { definitions })
+}
+
+fn query_royalty_info(
+    deps: Deps,
+    token_id: TokenId,
+    sale_price: Uint128,
+) -> StdResult<RoyaltyInfoResponse> {
+    let royalty = match TOKEN_ROYALTIES.may_load(deps.storage, &token_id)? {
+        Some(royalty) => Some(royalty),
+        None => TOKEN_ROYALTIES.may_load(deps.storage, base_token_id(&token_id))?,
+    };
+    Ok(match royalty {
+        Some(royalty) => RoyaltyInfoResponse {
+            address: royalty.payment_address,
+            royalty_amount: sale_price * royalty.share,
+        },
+        None => RoyaltyInfoResponse { address: String::new(), royalty_amount: Uint128::zero() },
+    })
+}
+
+fn query_transfer_policy(
+    deps: Deps,
+    token_id: Option<TokenId>,
+) -> StdResult<TransferPolicyResponse> {
+    let policy = match token_id {
+        Some(token_id) => {
+            if !TOKENS.has(deps.storage, &token_id) {
+                return Err(StdError::not_found(format!("token {}", token_id)));
+            }
+            load_transfer_policy(deps.storage, &token_id)?
+        }
+        None => TRANSFER_POLICY.may_load(deps.storage)?.unwrap_or_default(),
+    };
+    Ok(TransferPolicyResponse { policy })
+}
+
+fn query_bound_balance(
+    deps: Deps,
+    owner: String,
+    token_id: TokenId,
+) -> StdResult<BalanceResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let balance = BOUND_BALANCES
+        .may_load(deps.storage, (&owner_addr, &token_id))?
+        .unwrap_or_default();
+    Ok(BalanceResponse { balance })
+}
+
+fn query_clawback_log(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ClawbackLogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start = start_after.map(Bound::exclusive);
+    let entries = CLAWBACK_LOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, record)| ClawbackLogEntry {
+                id,
+                operator: record.operator.to_string(),
+                from: record.from.to_string(),
+                to: record.to.to_string(),
+                token_id: record.token_id,
+                amount: record.amount,
+                reason: record.reason,
+                height: record.height,
+                time: record.time,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    let renounced = CLAWBACK_RENOUNCED.may_load(deps.storage)?.unwrap_or(false);
+    Ok(ClawbackLogResponse { renounced, entries })
+}
+
+fn query_last_token_id(
+    deps: Deps,
+) -> StdResult<u128> {
+    Ok(TOKEN_RUNNING_NO.may_load(deps.storage)?.unwrap_or_default())
+}
 
 fn query_balance(
    deps: Deps,
@@ -952,22 +1949,16 @@ fn query_batch_balance(
  ) -> StdResult<AllBalanceResponse> {
 
     let owner_addr = deps.api.addr_validate(&owner)?;
-    
-    let mut token_id = 0u128;
+
+    // every id the owner holds, chosen ids and serials included
     let mut tokenids : Vec<String> = vec![];
     let mut amounts : Vec<Uint128> = vec![];
-    
-    let zero_balance = Uint128::from(0u128);
-    let last_token_id = TOKEN_RUNNING_NO.may_load(deps.storage)?.unwrap_or_default();
-    loop {
-        if token_id >= last_token_id {break;}
-        let token_id_str :String = token_id.to_string();
-        let balance = BALANCES.may_load(deps.storage, (&owner_addr, &token_id_str))?.unwrap_or_default();
-        if balance > zero_balance {
-            tokenids.push(token_id_str);
+    for item in BALANCES.prefix(&owner_addr).range(deps.storage, None, None, Order::Ascending) {
+        let (token_id, balance) = item?;
+        if !balance.is_zero() {
+            tokenids.push(token_id);
             amounts.push(balance);
         }
-        token_id = token_id + 1u128;
     }
     Ok(AllBalanceResponse { tokenids : tokenids,
                             amounts : amounts })
@@ -989,9 +1980,60 @@ fn query_batch_balance(
     deps: Deps,
     token_id : String,
  ) -> StdResult<TokenInfoResponse> {
-    let url = TOKENS.load(deps.storage, &token_id)?;
-    Ok(TokenInfoResponse{ url })
+    let token_uri = TOKENS.load(deps.storage, &token_id)?;
+    let url = resolve_token_uri(deps.storage, &token_id, token_uri)?.unwrap_or_default();
+    let token_type = load_token_type(deps.storage, &token_id)?;
+    Ok(TokenInfoResponse{ url, token_type })
  }
+
+fn query_owner_of(deps: Deps, token_id: String) -> StdResult<OwnerOfResponse> {
+    if let TokenType::Fungible { .. } = load_token_type(deps.storage, &token_id)? {
+        return Err(StdError::generic_err(format!("Token {} is fungible", token_id)));
+    }
+    let owner = NFT_OWNERS.load(deps.storage, &token_id)?;
+    Ok(OwnerOfResponse { owner: owner.to_string() })
+}
+
+fn query_cooperative_scope(deps: Deps, address: String) -> StdResult<CooperativeScopeResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let cooperative = COOPERATIVES.load(deps.storage, &address)?;
+    Ok(CooperativeScopeResponse {
+        can_mint_for: cooperative.can_mint_for,
+        can_burn_from: cooperative.can_burn_from,
+        scope: cooperative.scope,
+    })
+}
+
+fn query_voucher_redeemed(deps: Deps, signer: String, nonce: u64) -> StdResult<IsVoucherRedeemedResponse> {
+    let signer = deps.api.addr_validate(&signer)?;
+    Ok(IsVoucherRedeemedResponse {
+        redeemed: REDEEMED_VOUCHERS.has(deps.storage, (&signer, nonce)),
+    })
+}
+
+fn query_metadata_frozen(deps: Deps, token_id: Option<String>) -> StdResult<IsMetadataFrozenResponse> {
+    let frozen = match token_id {
+        Some(token_id) => is_metadata_frozen(deps.storage, &token_id)?,
+        None => METADATA_FROZEN.may_load(deps.storage)?.unwrap_or_default(),
+    };
+    Ok(IsMetadataFrozenResponse { frozen })
+}
+
+fn query_stats(deps: Deps, token_id: String) -> StdResult<StatsResponse> {
+    if !TOKENS.has(deps.storage, &token_id) {
+        return Err(StdError::not_found(format!("token {}", token_id)));
+    }
+    let stats = TOKEN_STATS
+        .may_load(deps.storage, &token_id)?
+        .unwrap_or_default();
+    Ok(StatsResponse {
+        minted: stats.minted,
+        burned: stats.burned,
+        transfers: stats.transfers,
+        unique_holders: stats.holders,
+    })
+}
+
  
  fn query_token_supply(
     deps: Deps,
@@ -1022,12 +2064,15 @@ fn query_batch_balance(
         name: info.name,
         symbol: info.symbol,
         owner : info.owner.to_string(),
+        base_uri : BASE_URI.may_load(deps.storage)?,
     })
  }
 
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::SubMsg;
+    use cw1155::Royalty;
     use super::*;
 
     #[test]
@@ -1058,6 +2103,7 @@ mod tests {
         let msg = InstantiateMsg {
             name : String::from("NFT TOKEN TWO"),
             symbol : String::from("NFTTWO"),
+            transfer_policy : None,
         };
 
         let creator = mock_info("operator", &[]);
@@ -1067,8 +2113,12 @@ mod tests {
         let creator = mock_info("operator", &[]);
         let res = execute(deps.as_mut(),mock_env(),creator,
             Cw1155ExecuteMsg::DefineToken {
-                token_uri : String::from("www.token1.com"),
+                token_uri : Some(String::from("www.token1.com")),
                 max_supply : Uint128::from(10000u128),
+                transfer_policy : None,
+                token_type : None,
+                category : None,
+                royalty : None,
             },
         ).unwrap();
         let token1 = res.attributes[2].value.clone();
@@ -1076,8 +2126,12 @@ mod tests {
         let creator = mock_info("operator", &[]);
         let res = execute(deps.as_mut(),mock_env(),creator,
             Cw1155ExecuteMsg::DefineToken {
-                token_uri : String::from("www.token2.com"),
+                token_uri : Some(String::from("www.token2.com")),
                 max_supply : Uint128::from(10000u128),
+                transfer_policy : None,
+                token_type : None,
+                category : None,
+                royalty : None,
             },
         ).unwrap();
         let token2 = res.attributes[2].value.clone();
@@ -1085,8 +2139,12 @@ mod tests {
         let creator = mock_info("operator", &[]);
         let res = execute(deps.as_mut(),mock_env(),creator,
             Cw1155ExecuteMsg::DefineToken {
-                token_uri : String::from("www.token3.com"),
+                token_uri : Some(String::from("www.token3.com")),
                 max_supply : Uint128::from(10000u128),
+                transfer_policy : None,
+                token_type : None,
+                category : None,
+                royalty : None,
             },
         ).unwrap();
         let token3 = res.attributes[2].value.clone();
@@ -1095,6 +2153,7 @@ mod tests {
             to: user1.clone(),
             token_id: token1.clone(),
             amount: 1u64.into(),
+            msg: None,
         };
         assert!(matches!(
             execute(
@@ -1178,6 +2237,7 @@ mod tests {
                 cooperative: minter.clone(),
                 can_mint_for: true,
                 can_burn_from: true,
+                scope: None,
             },
         )
         .unwrap();
@@ -1236,6 +2296,7 @@ mod tests {
                 Cw1155ExecuteMsg::BatchMint {
                     to: user2.clone(),
                     batch: vec![(token2.clone(), 1u64.into()), (token3.clone(), 1u64.into())],
+                    msg: None,
                 },
             )
             .unwrap(),
@@ -1409,6 +2470,7 @@ mod tests {
         let msg = InstantiateMsg {
             name : String::from("NFT TOKEN ONE"),
             symbol : String::from("NFTONE"),
+            transfer_policy : None,
         };
 
         let creator = mock_info("operator", &[]);
@@ -1422,8 +2484,12 @@ mod tests {
             mock_env(),
             creator,
             Cw1155ExecuteMsg::DefineToken {
-               token_uri : String::from("www.token1.com"),
+               token_uri : Some(String::from("www.token1.com")),
                max_supply : Uint128::from(10000u128),
+               transfer_policy : None,
+               token_type : None,
+               category : None,
+               royalty : None,
             },
         )
         .unwrap();
@@ -1436,8 +2502,12 @@ mod tests {
             mock_env(),
             creator,
             Cw1155ExecuteMsg::DefineToken {
-                token_uri : String::from("www.token2.com"),
+                token_uri : Some(String::from("www.token2.com")),
                max_supply : Uint128::from(10000u128),
+                transfer_policy : None,
+                token_type : None,
+                category : None,
+                royalty : None,
             },
         )
         .unwrap();
@@ -1452,6 +2522,7 @@ mod tests {
                 to: user1.clone(),
                 token_id: token2.clone(),
                 amount: 1u64.into(),
+                msg: None,
             },
         )
         .unwrap();
@@ -1477,7 +2548,7 @@ mod tests {
                         operator: user1.clone(),
                         from: Some(user1.clone()),
                         batch: vec![(token2.clone(), 1u64.into())],
-                        msg: dummy_msg,
+                        msg: dummy_msg.clone(),
                     }
                     .into_cosmos_msg(receiver.clone())
                     .unwrap()
@@ -1488,6 +2559,59 @@ mod tests {
                 .add_attribute("from", &user1)
                 .add_attribute("to", &receiver)
         );
+
+        // minting into a contract calls the receive hook with no sender
+        let creator = mock_info("operator", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            creator.clone(),
+            Cw1155ExecuteMsg::Mint {
+                to: receiver.clone(),
+                token_id: token2.clone(),
+                amount: 2u64.into(),
+                msg: Some(dummy_msg.clone()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(
+                Cw1155ReceiveMsg {
+                    operator: String::from("operator"),
+                    from: None,
+                    amount: 2u64.into(),
+                    token_id: token2.clone(),
+                    msg: dummy_msg.clone(),
+                }
+                .into_cosmos_msg(receiver.clone())
+                .unwrap()
+            )]
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            creator,
+            Cw1155ExecuteMsg::BatchMint {
+                to: receiver.clone(),
+                batch: vec![(token2.clone(), 3u64.into())],
+                msg: Some(dummy_msg.clone()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(
+                Cw1155BatchReceiveMsg {
+                    operator: String::from("operator"),
+                    from: None,
+                    batch: vec![(token2, 3u64.into())],
+                    msg: dummy_msg,
+                }
+                .into_cosmos_msg(receiver)
+                .unwrap()
+            )]
+        );
     }
 
     #[test]
@@ -1501,6 +2625,7 @@ mod tests {
         let msg = InstantiateMsg {
             name : String::from("NFT TOKEN FIVE"),
             symbol : String::from("NFTFIVE"),
+            transfer_policy : None,
         };
 
         let creator = mock_info("operator", &[]);
@@ -1516,8 +2641,12 @@ mod tests {
                 mock_env(),
                 creator,
                 Cw1155ExecuteMsg::DefineToken {
-                    token_uri : String::from(""), 
-                    max_supply : Uint128::from(10000u128)
+                    token_uri : Some(String::from("")), 
+                    max_supply : Uint128::from(10000u128),
+                    transfer_policy : None,
+                    token_type : None,
+                    category : None,
+                    royalty : None,
                 },
             )
             .unwrap();
@@ -1537,6 +2666,7 @@ mod tests {
                     .iter()
                     .map(|token_id| (token_id.clone(), 1u64.into()))
                     .collect::<Vec<_>>(),
+                msg: None,
             },
         )
         .unwrap();
@@ -1635,7 +2765,7 @@ mod tests {
                     token_id: "5".to_owned()
                 },
             ),
-            to_binary(&TokenInfoResponse { url: "".to_owned() })
+            to_binary(&TokenInfoResponse { url: "".to_owned(), token_type: TokenType::default() })
         );
 
         for user in users[1..].iter() {
@@ -1686,6 +2816,7 @@ mod tests {
         let msg = InstantiateMsg {
             name : String::from("NFT TOKEN FOUR"),
             symbol : String::from("NFTFOUR"),
+            transfer_policy : None,
         };
 
         let creator = mock_info("operator", &[]);
@@ -1698,8 +2829,12 @@ mod tests {
             env.clone(),
             creator,
             Cw1155ExecuteMsg::DefineToken {
-                token_uri: String::from("www.abc.xyz"),
-                max_supply : Uint128::from(10000u128)
+                token_uri: Some(String::from("www.abc.xyz")),
+                max_supply : Uint128::from(10000u128),
+                transfer_policy : None,
+                token_type : None,
+                category : None,
+                royalty : None,
             },
         )
         .unwrap();
@@ -1715,6 +2850,7 @@ mod tests {
                 to: user1.clone(),
                 token_id: token1,
                 amount: 1u64.into(),
+                msg: None,
             },
         )
         .unwrap();
@@ -1774,6 +2910,7 @@ mod tests {
         let msg = InstantiateMsg {
             name : String::from("NFT TOKEN THREE"),
             symbol : String::from("NFTTHREE"),
+            transfer_policy : None,
         };
 
         let creator = mock_info("operator", &[]);
@@ -1786,8 +2923,12 @@ mod tests {
             env.clone(),
             creator,
             Cw1155ExecuteMsg::DefineToken {
-                token_uri : String::from("www.bbb.xyz"),
+                token_uri : Some(String::from("www.bbb.xyz")),
                 max_supply: Uint128::from(10000u128),
+                transfer_policy : None,
+                token_type : None,
+                category : None,
+                royalty : None,
             },
         )
         .unwrap();
@@ -1803,6 +2944,7 @@ mod tests {
                 to: user1.clone(),
                 token_id: token1.clone(),
                 amount: Uint128::from(10000u128),
+                msg: None,
             },
         )
         .unwrap();
@@ -1817,10 +2959,1368 @@ mod tests {
                 to: user1.clone(),
                 token_id: token1.clone(),
                 amount : Uint128::from(1u128),
+                msg: None,
             },
         )
         .unwrap_err();
 
         assert_eq!(err, ContractError::ExceedMaxSupply{});
     }
+
+    #[test]
+    fn prune_expired_approvals_works() {
+        let mut deps = mock_dependencies();
+        let user1 = String::from("user1");
+        let user2 = String::from("user2");
+        let user3 = String::from("user3");
+
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name : String::from("NFT TOKEN FIVE"),
+            symbol : String::from("NFTFIVE"),
+            transfer_policy : None,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("operator", &[]), msg).unwrap();
+
+        let expires = Some(Expiration::AtHeight(env.block.height + 10));
+        for (owner, operator, expires) in [
+            (&user1, &user2, expires),
+            (&user1, &user3, None),
+            (&user2, &user3, expires),
+        ] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner, &[]),
+                Cw1155ExecuteMsg::ApproveAll {
+                    operator: operator.clone(),
+                    expires,
+                },
+            )
+            .unwrap();
+        }
+
+        let mut env = env;
+        env.block.height += 10;
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            Cw1155ExecuteMsg::PruneExpired { owner: Some(user1.clone()), limit: 10 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2].value, "1");
+        assert!(!APPROVES.has(&deps.storage, (&Addr::unchecked(&user1), &Addr::unchecked(&user2))));
+        assert!(APPROVES.has(&deps.storage, (&Addr::unchecked(&user1), &Addr::unchecked(&user3))));
+
+        // sweep over everyone, one entry per call
+        let prune_msg = Cw1155ExecuteMsg::PruneExpired { owner: None, limit: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), prune_msg.clone()).unwrap();
+        assert_eq!(res.attributes[2].value, "0");
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), prune_msg).unwrap();
+        assert_eq!(res.attributes[2].value, "1");
+        assert!(!APPROVES.has(&deps.storage, (&Addr::unchecked(&user2), &Addr::unchecked(&user3))));
+
+        // an owner's sweep resumes too, live operators first do not hide expired ones
+        let expires = Some(Expiration::AtHeight(env.block.height + 1));
+        for operator in ["user4", "user5"] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(&user1, &[]),
+                Cw1155ExecuteMsg::ApproveAll { operator: operator.to_string(), expires },
+            )
+            .unwrap();
+        }
+        env.block.height += 1;
+        let prune_msg = Cw1155ExecuteMsg::PruneExpired { owner: Some(user1.clone()), limit: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), prune_msg.clone()).unwrap();
+        assert_eq!(res.attributes[2].value, "0");
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), prune_msg).unwrap();
+        assert_eq!(res.attributes[2].value, "1");
+        assert!(!APPROVES.has(&deps.storage, (&Addr::unchecked(&user1), &Addr::unchecked("user4"))));
+
+        // sending on an allowance clears the operator's expired ApproveAll
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("operator", &[]),
+            Cw1155ExecuteMsg::DefineToken { token_uri: None, max_supply: Uint128::from(10u128), transfer_policy: None, token_type: None, category: None, royalty: None },
+        )
+        .unwrap();
+        let token_id = res.attributes[2].value.clone();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("operator", &[]),
+            Cw1155ExecuteMsg::Mint { to: user1.clone(), token_id: token_id.clone(), amount: Uint128::one(), msg: None },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&user1, &[]),
+            Cw1155ExecuteMsg::Approve { operator: String::from("user5"), token_id: token_id.clone(), amount: Uint128::one(), expires: None },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("user5", &[]),
+            Cw1155ExecuteMsg::TransferFrom { from: user1.clone(), to: user2, token_id, amount: Uint128::one() },
+        )
+        .unwrap();
+        assert!(!APPROVES.has(&deps.storage, (&Addr::unchecked(&user1), &Addr::unchecked("user5"))));
+    }
+
+    #[test]
+    fn force_transfer_and_clawback_log() {
+        let mut deps = mock_dependencies();
+        let thief = String::from("thief");
+        let victim = String::from("victim");
+
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name : String::from("NFT TOKEN SIX"),
+            symbol : String::from("NFTSIX"),
+            transfer_policy : None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), operator.clone(),
+            Cw1155ExecuteMsg::DefineToken {
+                token_uri : Some(String::from("www.token1.com")),
+                max_supply : Uint128::from(10000u128),
+                transfer_policy : None,
+                token_type : None,
+                category : None,
+                royalty : None,
+            },
+        ).unwrap();
+        let token1 = res.attributes[2].value.clone();
+        execute(deps.as_mut(), env.clone(), operator.clone(),
+            Cw1155ExecuteMsg::Mint { to: thief.clone(), token_id: token1.clone(), amount: 10u64.into(), msg: None },
+        ).unwrap();
+
+        let force_msg = Cw1155ExecuteMsg::ForceTransfer {
+            from: thief.clone(),
+            to: victim.clone(),
+            token_id: token1.clone(),
+            amount: 4u64.into(),
+            reason: String::from("duped items"),
+        };
+
+        // only owner can claw back
+        assert_eq!(
+            execute(deps.as_mut(), env.clone(), mock_info(&thief, &[]), force_msg.clone()),
+            Err(ContractError::Unauthorized {})
+        );
+
+        // reason is required
+        assert_eq!(
+            execute(deps.as_mut(), env.clone(), operator.clone(), Cw1155ExecuteMsg::ForceTransfer {
+                from: thief.clone(),
+                to: victim.clone(),
+                token_id: token1.clone(),
+                amount: 4u64.into(),
+                reason: String::new(),
+            }),
+            Err(ContractError::EmptyClawbackReason {})
+        );
+
+        execute(deps.as_mut(), env.clone(), operator.clone(), force_msg.clone()).unwrap();
+        assert_eq!(
+            query_balance(deps.as_ref(), thief.clone(), token1.clone()).unwrap().balance,
+            Uint128::from(6u64)
+        );
+        assert_eq!(
+            query_balance(deps.as_ref(), victim.clone(), token1.clone()).unwrap().balance,
+            Uint128::from(4u64)
+        );
+
+        let log = query_clawback_log(deps.as_ref(), None, None).unwrap();
+        assert_eq!(
+            log,
+            ClawbackLogResponse {
+                renounced: false,
+                entries: vec![ClawbackLogEntry {
+                    id: 0,
+                    operator: String::from("operator"),
+                    from: thief.clone(),
+                    to: victim.clone(),
+                    token_id: token1.clone(),
+                    amount: 4u64.into(),
+                    reason: String::from("duped items"),
+                    height: env.block.height,
+                    time: env.block.time,
+                }],
+            }
+        );
+
+        execute(deps.as_mut(), env.clone(), operator.clone(), Cw1155ExecuteMsg::RenounceClawback {}).unwrap();
+        assert_eq!(
+            execute(deps.as_mut(), env, operator, force_msg),
+            Err(ContractError::ClawbackRenounced {})
+        );
+        assert!(query_clawback_log(deps.as_ref(), None, None).unwrap().renounced);
+    }
+
+    #[test]
+    fn transfer_policies() {
+        let mut deps = mock_dependencies();
+        let user1 = String::from("user1");
+        let user2 = String::from("user2");
+        let user3 = String::from("user3");
+
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name : String::from("NFT TOKEN SEVEN"),
+            symbol : String::from("NFTSEVEN"),
+            transfer_policy : Some(TransferPolicy::Soulbound),
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+
+        let mut define = |policy: Option<TransferPolicy>| -> String {
+            let res = execute(deps.as_mut(), env.clone(), operator.clone(),
+                Cw1155ExecuteMsg::DefineToken {
+                    token_uri : Some(String::from("www.token.com")),
+                    max_supply : Uint128::from(10000u128),
+                    transfer_policy : policy,
+                    token_type : None,
+                    category : None,
+                    royalty : None,
+                },
+            ).unwrap();
+            res.attributes[2].value.clone()
+        };
+        let achievement = define(None);
+        let gear = define(Some(TransferPolicy::BindOnTransfer));
+        let coop_only = define(Some(TransferPolicy::CooperativeOnly));
+
+        for token_id in [&achievement, &gear, &coop_only] {
+            execute(deps.as_mut(), env.clone(), operator.clone(),
+                Cw1155ExecuteMsg::Mint { to: user1.clone(), token_id: token_id.clone(), amount: 10u64.into(), msg: None },
+            ).unwrap();
+        }
+        assert_eq!(
+            query_transfer_policy(deps.as_ref(), Some(achievement.clone())).unwrap().policy,
+            TransferPolicy::Soulbound
+        );
+
+        // soulbound fails in every path, burning is fine
+        assert_eq!(
+            execute(deps.as_mut(), env.clone(), mock_info(&user1, &[]),
+                Cw1155ExecuteMsg::Transfer { to: user2.clone(), token_id: achievement.clone(), amount: 1u64.into() }),
+            Err(ContractError::TransferNotAllowed { token_id: achievement.clone() })
+        );
+        assert_eq!(
+            execute(deps.as_mut(), env.clone(), mock_info(&user1, &[]),
+                Cw1155ExecuteMsg::BatchTransfer { to: user2.clone(), batch: vec![(achievement.clone(), 1u64.into())] }),
+            Err(ContractError::TransferNotAllowed { token_id: achievement.clone() })
+        );
+        execute(deps.as_mut(), env.clone(), mock_info(&user1, &[]),
+            Cw1155ExecuteMsg::Burn { from: user1.clone(), token_id: achievement.clone(), amount: 1u64.into() },
+        ).unwrap();
+
+        // cooperative only
+        assert_eq!(
+            execute(deps.as_mut(), env.clone(), mock_info(&user1, &[]),
+                Cw1155ExecuteMsg::Transfer { to: user2.clone(), token_id: coop_only.clone(), amount: 1u64.into() }),
+            Err(ContractError::TransferNotAllowed { token_id: coop_only.clone() })
+        );
+        execute(deps.as_mut(), env.clone(), operator.clone(),
+            Cw1155ExecuteMsg::TransferFrom { from: user1.clone(), to: user2.clone(), token_id: coop_only.clone(), amount: 1u64.into() },
+        ).unwrap();
+
+        // received amounts stay with the receiver, own amounts can still move
+        execute(deps.as_mut(), env.clone(), mock_info(&user1, &[]),
+            Cw1155ExecuteMsg::Transfer { to: user2.clone(), token_id: gear.clone(), amount: 4u64.into() },
+        ).unwrap();
+        assert_eq!(
+            query_bound_balance(deps.as_ref(), user2.clone(), gear.clone()).unwrap().balance,
+            Uint128::from(4u64)
+        );
+        assert_eq!(
+            execute(deps.as_mut(), env.clone(), mock_info(&user2, &[]),
+                Cw1155ExecuteMsg::Transfer { to: user3.clone(), token_id: gear.clone(), amount: 1u64.into() }),
+            Err(ContractError::TransferNotAllowed { token_id: gear.clone() })
+        );
+        execute(deps.as_mut(), env.clone(), mock_info(&user1, &[]),
+            Cw1155ExecuteMsg::Transfer { to: user3.clone(), token_id: gear.clone(), amount: 6u64.into() },
+        ).unwrap();
+
+        // burning takes the bound part down with the balance
+        execute(deps.as_mut(), env.clone(), mock_info(&user2, &[]),
+            Cw1155ExecuteMsg::Burn { from: user2.clone(), token_id: gear.clone(), amount: 3u64.into() },
+        ).unwrap();
+        assert_eq!(
+            query_bound_balance(deps.as_ref(), user2.clone(), gear.clone()).unwrap().balance,
+            Uint128::from(1u64)
+        );
+
+        // messages written before transfer policies still parse
+        let msg: Cw1155ExecuteMsg = cosmwasm_std::from_slice(br#"{"define_token":{"token_uri":null,"max_supply":"10"}}"#).unwrap();
+        assert!(matches!(msg, Cw1155ExecuteMsg::DefineToken { transfer_policy: None, .. }));
+        let msg: InstantiateMsg = cosmwasm_std::from_slice(br#"{"name":"NFT TOKEN SEVEN","symbol":"NFTSEVEN"}"#).unwrap();
+        assert_eq!(msg.transfer_policy, None);
+    }
+
+    #[test]
+    fn token_stats() {
+        let mut deps = mock_dependencies();
+        let user1 = String::from("user1");
+        let user2 = String::from("user2");
+
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN SIX"),
+            symbol: String::from("NFTSIX"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::DefineToken {
+                token_uri: Some(String::from("www.token1.com")),
+                max_supply: Uint128::from(10000u128),
+                transfer_policy: None,
+                token_type: None,
+                category: None,
+                royalty: None,
+            },
+        )
+        .unwrap();
+        let token1 = res.attributes[2].value.clone();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::Mint {
+                to: user1.clone(),
+                token_id: token1.clone(),
+                amount: 10u64.into(),
+                msg: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&user1, &[]),
+            Cw1155ExecuteMsg::Transfer {
+                to: user2.clone(),
+                token_id: token1.clone(),
+                amount: 4u64.into(),
+            },
+        )
+        .unwrap();
+        // sending to yourself changes nothing about holders
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&user1, &[]),
+            Cw1155ExecuteMsg::Transfer {
+                to: user1.clone(),
+                token_id: token1.clone(),
+                amount: 6u64.into(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            query_stats(deps.as_ref(), token1.clone()).unwrap(),
+            StatsResponse {
+                minted: 10u64.into(),
+                burned: Uint128::zero(),
+                transfers: 2,
+                unique_holders: 2,
+            }
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&user2, &[]),
+            Cw1155ExecuteMsg::BatchTransfer {
+                to: user1.clone(),
+                batch: vec![(token1.clone(), 4u64.into())],
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env,
+            operator,
+            Cw1155ExecuteMsg::Burn {
+                from: user1,
+                token_id: token1.clone(),
+                amount: 3u64.into(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            query_stats(deps.as_ref(), token1).unwrap(),
+            StatsResponse {
+                minted: 10u64.into(),
+                burned: 3u64.into(),
+                transfers: 3,
+                unique_holders: 1,
+            }
+        );
+        assert!(query_stats(deps.as_ref(), String::from("unknown")).is_err());
+    }
+
+    #[test]
+    fn freezing_metadata() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN SEVEN"),
+            symbol: String::from("NFTSEVEN"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let mut token_ids = vec![];
+        for _ in 0..2 {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                operator.clone(),
+                Cw1155ExecuteMsg::DefineToken {
+                    token_uri: Some(String::from("www.before.com")),
+                    max_supply: Uint128::from(100u128),
+                    transfer_policy: None,
+                    token_type: None,
+                    category: None,
+                    royalty: None,
+                },
+            )
+            .unwrap();
+            token_ids.push(res.attributes[2].value.clone());
+        }
+
+        let freeze_msg = Cw1155ExecuteMsg::FreezeMetadata {
+            token_id: Some(token_ids[0].clone()),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("user1", &[]), freeze_msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), env.clone(), operator.clone(), freeze_msg).unwrap();
+        assert_eq!(res.events[0].ty, "metadata_frozen");
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::UpdateTokenUri {
+                token_id: token_ids[0].clone(),
+                token_uri: Some(String::from("www.after.com")),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::MetadataFrozen { token_id: token_ids[0].clone() });
+        assert!(query_metadata_frozen(deps.as_ref(), Some(token_ids[0].clone())).unwrap().frozen);
+        assert!(!query_metadata_frozen(deps.as_ref(), Some(token_ids[1].clone())).unwrap().frozen);
+
+        // the collection-wide freeze covers every token
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::FreezeMetadata { token_id: None },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env,
+            operator,
+            Cw1155ExecuteMsg::UpdateTokenUri {
+                token_id: token_ids[1].clone(),
+                token_uri: Some(String::from("www.after.com")),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::MetadataFrozen { token_id: token_ids[1].clone() });
+        assert!(query_metadata_frozen(deps.as_ref(), None).unwrap().frozen);
+    }
+
+    #[test]
+    fn resolving_base_uri() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN EIGHT"),
+            symbol: String::from("NFTEIGHT"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::UpdateBaseUri {
+                base_uri: Some(String::from("https://cdn.example.com/{id}.json")),
+            },
+        )
+        .unwrap();
+        let mut token_ids = vec![];
+        for token_uri in [None, Some(String::from("www.own.com"))] {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                operator.clone(),
+                Cw1155ExecuteMsg::DefineToken {
+                    token_uri,
+                    max_supply: Uint128::from(100u128),
+                    transfer_policy: None,
+                    token_type: None,
+                    category: None,
+                    royalty: None,
+                },
+            )
+            .unwrap();
+            token_ids.push(res.attributes[2].value.clone());
+        }
+
+        assert_eq!(
+            query_token_info(deps.as_ref(), token_ids[0].clone()).unwrap().url,
+            "https://cdn.example.com/0000000000000000000000000000000000000000000000000000000000000000.json"
+        );
+        assert_eq!(query_token_info(deps.as_ref(), token_ids[1].clone()).unwrap().url, "www.own.com");
+
+        // dropping the override falls back to the base uri
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::UpdateTokenUri { token_id: token_ids[1].clone(), token_uri: None },
+        )
+        .unwrap();
+        assert_eq!(
+            query_token_info(deps.as_ref(), token_ids[1].clone()).unwrap().url,
+            "https://cdn.example.com/0000000000000000000000000000000000000000000000000000000000000001.json"
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::UpdateBaseUri { base_uri: Some(String::from("https://new.example.com/")) },
+        )
+        .unwrap();
+        assert_eq!(
+            query_token_info(deps.as_ref(), token_ids[0].clone()).unwrap().url,
+            "https://new.example.com/0000000000000000000000000000000000000000000000000000000000000000"
+        );
+        assert_eq!(
+            query_contract_info(deps.as_ref()).unwrap().base_uri,
+            Some(String::from("https://new.example.com/"))
+        );
+
+        execute(deps.as_mut(), env.clone(), operator.clone(), Cw1155ExecuteMsg::FreezeMetadata { token_id: None }).unwrap();
+        let err = execute(deps.as_mut(), env, operator, Cw1155ExecuteMsg::UpdateBaseUri { base_uri: None }).unwrap_err();
+        assert_eq!(err, ContractError::CollectionMetadataFrozen {});
+    }
+
+    #[test]
+    fn redeeming_vouchers() {
+        use cw_utils::Expiration;
+        use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN NINE"),
+            symbol: String::from("NFTNINE"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::DefineToken {
+                token_uri: None,
+                max_supply: Uint128::from(100u128),
+                transfer_policy: None,
+                token_type: None,
+                category: None,
+                royalty: None,
+            },
+        )
+        .unwrap();
+        let token_id = res.attributes[2].value.clone();
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let sign = |voucher: &MintVoucher| {
+            let mut message = env.contract.address.as_bytes().to_vec();
+            message.extend(to_vec(voucher).unwrap());
+            let signature: Signature = signing_key.sign(&message);
+            Binary::from(signature.as_ref())
+        };
+        let voucher = |nonce: u64| MintVoucher {
+            signer: String::from("game"),
+            recipient: String::from("player"),
+            token_id: token_id.clone(),
+            amount: Uint128::from(5u128),
+            nonce,
+            expires: Expiration::AtHeight(env.block.height + 10),
+        };
+
+        // only minters can sign vouchers
+        let signer_msg = Cw1155ExecuteMsg::SetVoucherSigner {
+            pubkey: Some(Binary::from(signing_key.verifying_key().to_bytes().as_slice())),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("game", &[]), signer_msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator,
+            Cw1155ExecuteMsg::SetCooperative {
+                cooperative: String::from("game"),
+                can_mint_for: true,
+                can_burn_from: false,
+                scope: None,
+            },
+        )
+        .unwrap();
+        let bad_key_msg = Cw1155ExecuteMsg::SetVoucherSigner { pubkey: Some(Binary::from([4u8; 33].as_slice())) };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("game", &[]), bad_key_msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVoucherPubkey {});
+        execute(deps.as_mut(), env.clone(), mock_info("game", &[]), signer_msg).unwrap();
+
+        // anyone can submit a signed voucher, once
+        let redeem_msg = Cw1155ExecuteMsg::RedeemVoucher { voucher: voucher(1), signature: sign(&voucher(1)) };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), redeem_msg.clone()).unwrap();
+        assert!(res.attributes.contains(&attr("voucher_nonce", "1")));
+        assert_eq!(
+            query_balance(deps.as_ref(), String::from("player"), token_id.clone()).unwrap().balance,
+            Uint128::from(5u128)
+        );
+        assert!(query_voucher_redeemed(deps.as_ref(), String::from("game"), 1).unwrap().redeemed);
+        let err = execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), redeem_msg).unwrap_err();
+        assert_eq!(err, ContractError::VoucherRedeemed { nonce: 1 });
+
+        // a tampered voucher does not verify
+        let mut tampered = voucher(2);
+        tampered.amount = Uint128::from(50u128);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("relayer", &[]),
+            Cw1155ExecuteMsg::RedeemVoucher { voucher: tampered, signature: sign(&voucher(2)) },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidVoucherSignature {});
+
+        let mut late_env = env.clone();
+        late_env.block.height += 10;
+        let err = execute(
+            deps.as_mut(),
+            late_env,
+            mock_info("relayer", &[]),
+            Cw1155ExecuteMsg::RedeemVoucher { voucher: voucher(2), signature: sign(&voucher(2)) },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::VoucherExpired {});
+    }
+
+    #[test]
+    fn token_types() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN TEN"),
+            symbol: String::from("NFTTEN"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let define = |deps: DepsMut, max_supply: u128, token_type: TokenType| {
+            execute(
+                deps,
+                mock_env(),
+                mock_info("operator", &[]),
+                Cw1155ExecuteMsg::DefineToken {
+                    token_uri: None,
+                    max_supply: Uint128::from(max_supply),
+                    transfer_policy: None,
+                    token_type: Some(token_type),
+                    category: None,
+                    royalty: None,
+                },
+            )
+        };
+        let mint = |token_id: &str, amount: u128| Cw1155ExecuteMsg::Mint {
+            to: String::from("user1"),
+            token_id: token_id.to_string(),
+            amount: Uint128::from(amount),
+            msg: None,
+        };
+
+        let err = define(deps.as_mut(), 10, TokenType::NonFungible { serials: false }).unwrap_err();
+        assert_eq!(err, ContractError::NonFungibleSupply { token_id: String::from("0") });
+        let gold = define(deps.as_mut(), 1000, TokenType::Fungible { decimals: 2 }).unwrap().attributes[2].value.clone();
+        let crown = define(deps.as_mut(), 1, TokenType::NonFungible { serials: false }).unwrap().attributes[2].value.clone();
+        let sword = define(deps.as_mut(), 2, TokenType::NonFungible { serials: true }).unwrap().attributes[2].value.clone();
+        assert_eq!(query_token_info(deps.as_ref(), gold.clone()).unwrap().token_type, TokenType::Fungible { decimals: 2 });
+
+        // fungible ids mint any amount and have no single owner
+        execute(deps.as_mut(), env.clone(), operator.clone(), mint(&gold, 250)).unwrap();
+        assert!(query_owner_of(deps.as_ref(), gold).is_err());
+
+        let err = execute(deps.as_mut(), env.clone(), operator.clone(), mint(&crown, 2)).unwrap_err();
+        assert_eq!(err, ContractError::NonFungibleAmount {});
+        execute(deps.as_mut(), env.clone(), operator.clone(), mint(&crown, 1)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), operator.clone(), mint(&crown, 1)).unwrap_err();
+        assert_eq!(err, ContractError::ExceedMaxSupply {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            Cw1155ExecuteMsg::Transfer { to: String::from("user2"), token_id: crown.clone(), amount: Uint128::one() },
+        )
+        .unwrap();
+        assert_eq!(query_owner_of(deps.as_ref(), crown.clone()).unwrap().owner, "user2");
+
+        // every mint of a serial base type creates the next split id
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::BatchMint { to: String::from("user1"), batch: vec![(sword.clone(), Uint128::one())], msg: None },
+        )
+        .unwrap();
+        let first = format!("{}:1", sword);
+        assert_eq!(res.attributes[1], attr("token_id[0]", first.clone()));
+        let res = execute(deps.as_mut(), env.clone(), operator.clone(), mint(&sword, 1)).unwrap();
+        assert_eq!(res.attributes[1], attr("token_id", format!("{}:2", sword)));
+        let err = execute(deps.as_mut(), env.clone(), operator.clone(), mint(&sword, 1)).unwrap_err();
+        assert_eq!(err, ContractError::ExceedMaxSupply {});
+        assert_eq!(query_owner_of(deps.as_ref(), first.clone()).unwrap().owner, "user1");
+        assert_eq!(
+            query_token_info(deps.as_ref(), first.clone()).unwrap().token_type,
+            TokenType::NonFungible { serials: false }
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::UpdateBaseUri { base_uri: Some(String::from("ipfs://items/{id}.json")) },
+        )
+        .unwrap();
+        assert_eq!(
+            query_token_info(deps.as_ref(), first.clone()).unwrap().url,
+            format!("ipfs://items/{:032x}{:032x}.json", sword.parse::<u128>().unwrap(), 1)
+        );
+
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("user1", &[]),
+            Cw1155ExecuteMsg::Burn { from: String::from("user1"), token_id: first.clone(), amount: Uint128::one() },
+        )
+        .unwrap();
+        assert!(query_owner_of(deps.as_ref(), first).is_err());
+    }
+
+    #[test]
+    fn scoped_approvals() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN ELEVEN"),
+            symbol: String::from("NFTELEVEN"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        let user1 = mock_info("user1", &[]);
+        let market = mock_info("market", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let mut token_ids = vec![];
+        for _ in 0..2 {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                operator.clone(),
+                Cw1155ExecuteMsg::DefineToken {
+                    token_uri: None,
+                    max_supply: Uint128::from(100u128),
+                    transfer_policy: None,
+                    token_type: None,
+                    category: None,
+                    royalty: None,
+                },
+            )
+            .unwrap();
+            let token_id = res.attributes[2].value.clone();
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                operator.clone(),
+                Cw1155ExecuteMsg::Mint { to: String::from("user1"), token_id: token_id.clone(), amount: Uint128::from(10u128), msg: None },
+            )
+            .unwrap();
+            token_ids.push(token_id);
+        }
+        let (ore, gem) = (token_ids[0].clone(), token_ids[1].clone());
+        // market is a plain contract, the allowance alone lets it transfer
+        let take = |token_id: &str, amount: u128| Cw1155ExecuteMsg::TransferFrom {
+            from: String::from("user1"),
+            to: String::from("user2"),
+            token_id: token_id.to_string(),
+            amount: Uint128::from(amount),
+        };
+
+        let err = execute(deps.as_mut(), env.clone(), market.clone(), take(&ore, 3)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            user1.clone(),
+            Cw1155ExecuteMsg::Approve { operator: String::from("market"), token_id: ore.clone(), amount: Uint128::from(5u128), expires: None },
+        )
+        .unwrap();
+
+        // every transfer spends from the allowance, other ids are not covered
+        execute(deps.as_mut(), env.clone(), market.clone(), take(&ore, 3)).unwrap();
+        let allowance = query_token_allowance(deps.as_ref(), String::from("user1"), String::from("market"), ore.clone()).unwrap();
+        assert_eq!(allowance.amount, Uint128::from(2u128));
+        let err = execute(deps.as_mut(), env.clone(), market.clone(), take(&ore, 3)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientAllowance { token_id: ore.clone() });
+        let err = execute(deps.as_mut(), env.clone(), market.clone(), take(&gem, 1)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            market.clone(),
+            Cw1155ExecuteMsg::BatchTransferFrom {
+                from: String::from("user1"),
+                to: String::from("user2"),
+                batch: vec![(ore.clone(), Uint128::from(2u128))],
+            },
+        )
+        .unwrap();
+        let allowance = query_token_allowance(deps.as_ref(), String::from("user1"), String::from("market"), ore.clone()).unwrap();
+        assert_eq!(allowance.amount, Uint128::zero());
+
+        // burning for someone else still takes burn rights, and spends the allowance
+        let forge = mock_info("forge", &[]);
+        let approve_ore = |operator: &str| Cw1155ExecuteMsg::Approve {
+            operator: operator.to_string(),
+            token_id: ore.clone(),
+            amount: Uint128::from(2u128),
+            expires: None,
+        };
+        let burn_ore = Cw1155ExecuteMsg::Burn { from: String::from("user1"), token_id: ore.clone(), amount: Uint128::from(2u128) };
+        execute(deps.as_mut(), env.clone(), user1.clone(), approve_ore("market")).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), market.clone(), burn_ore.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            user1.clone(),
+            Cw1155ExecuteMsg::Approve { operator: String::from("market"), token_id: ore.clone(), amount: Uint128::zero(), expires: None },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::SetCooperative { cooperative: String::from("forge"), can_mint_for: false, can_burn_from: true, scope: None },
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), forge.clone(), burn_ore.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), user1.clone(), approve_ore("forge")).unwrap();
+        execute(deps.as_mut(), env.clone(), forge.clone(), burn_ore).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            forge,
+            Cw1155ExecuteMsg::BatchBurn { from: String::from("user1"), batch: vec![(ore.clone(), Uint128::one())] },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        assert_eq!(query_balance(deps.as_ref(), String::from("user1"), ore.clone()).unwrap().balance, Uint128::from(3u128));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            user1.clone(),
+            Cw1155ExecuteMsg::Approve {
+                operator: String::from("market"),
+                token_id: gem.clone(),
+                amount: Uint128::from(4u128),
+                expires: Some(Expiration::AtHeight(env.block.height + 1)),
+            },
+        )
+        .unwrap();
+        let allowances = query_token_allowances(deps.as_ref(), env.clone(), String::from("user1"), false, None, None).unwrap();
+        assert_eq!(allowances.allowances, vec![TokenAllowanceInfo {
+            operator: String::from("market"),
+            token_id: gem.clone(),
+            amount: Uint128::from(4u128),
+            expires: Expiration::AtHeight(env.block.height + 1),
+        }]);
+        let mut late_env = env.clone();
+        late_env.block.height += 1;
+        let allowances = query_token_allowances(deps.as_ref(), late_env, String::from("user1"), false, None, None).unwrap();
+        assert!(allowances.allowances.is_empty());
+
+        // ApproveAll alone does not let a plain contract send
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            user1,
+            Cw1155ExecuteMsg::ApproveAll { operator: String::from("market"), expires: None },
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), market.clone(), take(&ore, 1)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), env.clone(), market.clone(), take(&gem, 6)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientAllowance { token_id: gem.clone() });
+
+        // for a cooperative ApproveAll is used first and spends nothing
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator,
+            Cw1155ExecuteMsg::SetCooperative { cooperative: String::from("market"), can_mint_for: false, can_burn_from: false, scope: None },
+        )
+        .unwrap();
+        execute(deps.as_mut(), env.clone(), market, take(&gem, 6)).unwrap();
+        let allowance = query_token_allowance(deps.as_ref(), String::from("user1"), String::from("market"), gem).unwrap();
+        assert_eq!(allowance.amount, Uint128::from(4u128));
+    }
+
+    #[test]
+    fn cooperative_scopes() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN TWELVE"),
+            symbol: String::from("NFTTWELVE"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        let studio = mock_info("studio", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let mut token_ids = vec![];
+        for _ in 0..3 {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                operator.clone(),
+                Cw1155ExecuteMsg::DefineToken {
+                    token_uri: None,
+                    max_supply: Uint128::from(100u128),
+                    transfer_policy: None,
+                    token_type: None,
+                    category: None,
+                    royalty: None,
+                },
+            )
+            .unwrap();
+            token_ids.push(res.attributes[2].value.clone());
+        }
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            studio.clone(),
+            Cw1155ExecuteMsg::SetTokenCategory { token_id: token_ids[1].clone(), category: Some(String::from("studio-a")) },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::SetTokenCategory { token_id: token_ids[1].clone(), category: Some(String::from("studio-a")) },
+        )
+        .unwrap();
+        let set_scope = |scope: CooperativeScope| Cw1155ExecuteMsg::SetCooperative {
+            cooperative: String::from("studio"),
+            can_mint_for: true,
+            can_burn_from: true,
+            scope: Some(scope),
+        };
+        let mint = |token_id: &str| Cw1155ExecuteMsg::Mint {
+            to: String::from("user1"),
+            token_id: token_id.to_string(),
+            amount: Uint128::from(5u128),
+            msg: None,
+        };
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            set_scope(CooperativeScope::TokenIds { token_ids: vec![token_ids[0].clone()] }),
+        )
+        .unwrap();
+        execute(deps.as_mut(), env.clone(), studio.clone(), mint(&token_ids[0])).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), studio.clone(), mint(&token_ids[1])).unwrap_err();
+        assert_eq!(err, ContractError::OutOfCooperativeScope { token_id: token_ids[1].clone() });
+
+        let category_scope = CooperativeScope::Category { category: String::from("studio-a") };
+        execute(deps.as_mut(), env.clone(), operator.clone(), set_scope(category_scope.clone())).unwrap();
+        assert_eq!(
+            query_cooperative_scope(deps.as_ref(), String::from("studio")).unwrap(),
+            CooperativeScopeResponse { can_mint_for: true, can_burn_from: true, scope: category_scope }
+        );
+        execute(deps.as_mut(), env.clone(), studio.clone(), mint(&token_ids[1])).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            studio.clone(),
+            Cw1155ExecuteMsg::BatchMint {
+                to: String::from("user1"),
+                batch: vec![(token_ids[1].clone(), Uint128::one()), (token_ids[2].clone(), Uint128::one())],
+                msg: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OutOfCooperativeScope { token_id: token_ids[2].clone() });
+
+        // burning from a holder is scoped the same way
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            Cw1155ExecuteMsg::ApproveAll { operator: String::from("studio"), expires: None },
+        )
+        .unwrap();
+        let burn = |token_id: &str| Cw1155ExecuteMsg::Burn {
+            from: String::from("user1"),
+            token_id: token_id.to_string(),
+            amount: Uint128::one(),
+        };
+        execute(deps.as_mut(), env.clone(), studio.clone(), burn(&token_ids[1])).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), studio.clone(), burn(&token_ids[0])).unwrap_err();
+        assert_eq!(err, ContractError::OutOfCooperativeScope { token_id: token_ids[0].clone() });
+        let err = execute(
+            deps.as_mut(),
+            env,
+            studio,
+            Cw1155ExecuteMsg::BatchBurn {
+                from: String::from("user1"),
+                batch: vec![(token_ids[0].clone(), Uint128::one())],
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OutOfCooperativeScope { token_id: token_ids[0].clone() });
+    }
+
+    #[test]
+    fn defining_tokens() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN THIRTEEN"),
+            symbol: String::from("NFTTHIRTEEN"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let definition = |token_id: Option<&str>, category: Option<&str>| TokenDefinition {
+            token_id: token_id.map(String::from),
+            token_uri: None,
+            max_supply: Uint128::from(100u128),
+            transfer_policy: None,
+            token_type: None,
+            category: category.map(String::from),
+            royalty: None,
+        };
+        let royalty = Royalty { payment_address: String::from("artist"), share: Decimal::percent(5) };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            Cw1155ExecuteMsg::DefineTokens { tokens: vec![definition(None, None)] },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::DefineTokens {
+                tokens: vec![
+                    TokenDefinition { royalty: Some(royalty.clone()), ..definition(Some("sword:iron"), Some("weapons")) },
+                    definition(None, Some("weapons")),
+                    TokenDefinition {
+                        token_type: Some(TokenType::NonFungible { serials: true }),
+                        royalty: Some(royalty.clone()),
+                        ..definition(Some("helm"), Some("armor"))
+                    },
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("token_id[0]", "sword:iron"));
+        assert_eq!(res.attributes[3], attr("token_id[1]", "0"));
+        assert_eq!(res.attributes[4], attr("token_id[2]", "helm"));
+
+        // chosen ids cannot clash with running numbers, serials or each other
+        for (token_id, expected) in [
+            ("helm", ContractError::TokenAlreadyDefined { token_id: String::from("helm") }),
+            ("12", ContractError::InvalidTokenId { token_id: String::from("12") }),
+            ("sword:3", ContractError::InvalidTokenId { token_id: String::from("sword:3") }),
+            ("", ContractError::InvalidTokenId { token_id: String::new() }),
+        ] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                operator.clone(),
+                Cw1155ExecuteMsg::DefineTokens { tokens: vec![definition(Some(token_id), None)] },
+            )
+            .unwrap_err();
+            assert_eq!(err, expected);
+        }
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::DefineTokens {
+                tokens: vec![TokenDefinition {
+                    royalty: Some(Royalty { share: Decimal::percent(101), ..royalty.clone() }),
+                    ..definition(Some("shield"), None)
+                }],
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidRoyalty {});
+
+        assert_eq!(
+            query_tokens_by_category(deps.as_ref(), String::from("weapons"), None, None).unwrap().tokens,
+            vec!["0", "sword:iron"]
+        );
+        assert_eq!(
+            query_tokens_by_category(deps.as_ref(), String::from("weapons"), Some(String::from("0")), Some(1))
+                .unwrap()
+                .tokens,
+            vec!["sword:iron"]
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::SetTokenCategory { token_id: String::from("0"), category: Some(String::from("armor")) },
+        )
+        .unwrap();
+        assert_eq!(
+            query_tokens_by_category(deps.as_ref(), String::from("weapons"), None, None).unwrap().tokens,
+            vec!["sword:iron"]
+        );
+        assert_eq!(
+            query_tokens_by_category(deps.as_ref(), String::from("armor"), None, None).unwrap().tokens,
+            vec!["0", "helm"]
+        );
+
+        let definitions = query_token_definitions(deps.as_ref(), Some(String::from("0")), None).unwrap().definitions;
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions[1], TokenDefinitionInfo {
+            token_id: String::from("sword:iron"),
+            token_uri: None,
+            supply: TokenSupply { total_supply: Uint128::zero(), max_supply: Uint128::from(100u128) },
+            transfer_policy: TransferPolicy::Free,
+            token_type: TokenType::Fungible { decimals: 0 },
+            category: Some(String::from("weapons")),
+            royalty: Some(royalty.clone()),
+        });
+
+        // custom ids mint like any other, serials pay the royalty of their base type
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::Mint { to: String::from("user1"), token_id: String::from("sword:iron"), amount: Uint128::from(3u128), msg: None },
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::Mint { to: String::from("user1"), token_id: String::from("helm"), amount: Uint128::one(), msg: None },
+        )
+        .unwrap();
+        let serial = res.attributes[1].value.clone();
+        assert!(serial.starts_with("helm:"));
+        assert_eq!(
+            query_royalty_info(deps.as_ref(), serial.clone(), Uint128::from(1000u128)).unwrap(),
+            RoyaltyInfoResponse { address: String::from("artist"), royalty_amount: Uint128::from(50u128) }
+        );
+        assert_eq!(
+            query_royalty_info(deps.as_ref(), String::from("0"), Uint128::from(1000u128)).unwrap(),
+            RoyaltyInfoResponse { address: String::new(), royalty_amount: Uint128::zero() }
+        );
+
+        // the listing only holds definitions, not the serials minted from them
+        let definitions = query_token_definitions(deps.as_ref(), None, None).unwrap().definitions;
+        assert_eq!(
+            definitions.iter().map(|d| d.token_id.as_str()).collect::<Vec<_>>(),
+            vec!["0", "helm", "sword:iron"]
+        );
+
+        // chosen ids and serials count towards AllBalance like running numbers
+        let balances = query_all_balance(deps.as_ref(), String::from("user1")).unwrap();
+        assert_eq!(balances.tokenids, vec![serial.clone(), String::from("sword:iron")]);
+        assert_eq!(balances.amounts, vec![Uint128::one(), Uint128::from(3u128)]);
+
+        // a single DefineToken takes a category and royalty too
+        let res = execute(
+            deps.as_mut(),
+            env,
+            operator,
+            Cw1155ExecuteMsg::DefineToken {
+                token_uri: None,
+                max_supply: Uint128::from(10u128),
+                transfer_policy: None,
+                token_type: None,
+                category: Some(String::from("weapons")),
+                royalty: Some(royalty),
+            },
+        )
+        .unwrap();
+        let token_id = res.attributes[2].value.clone();
+        assert_eq!(
+            query_tokens_by_category(deps.as_ref(), String::from("weapons"), None, None).unwrap().tokens,
+            vec![token_id.clone(), String::from("sword:iron")]
+        );
+        assert_eq!(
+            query_royalty_info(deps.as_ref(), token_id, Uint128::from(1000u128)).unwrap(),
+            RoyaltyInfoResponse { address: String::from("artist"), royalty_amount: Uint128::from(50u128) }
+        );
+    }
+
+    #[test]
+    fn migrating_definitions() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN FOURTEEN"),
+            symbol: String::from("NFTFOURTEEN"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        for _ in 0..3 {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                operator.clone(),
+                Cw1155ExecuteMsg::DefineToken {
+                    token_uri: None,
+                    max_supply: Uint128::from(100u128),
+                    transfer_policy: None,
+                    token_type: None,
+                    category: None,
+                    royalty: None,
+                },
+            )
+            .unwrap();
+        }
+
+        // pretend the tokens were defined by a version without TOKEN_DEFINITIONS
+        for token_id in ["0", "1", "2"] {
+            TOKEN_DEFINITIONS.remove(deps.as_mut().storage, token_id);
+        }
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.16.0").unwrap();
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        assert_eq!(DEFINITION_MIGRATION.load(&deps.storage).unwrap(), 0);
+
+        let msg = Cw1155ExecuteMsg::MigrateDefinitions { limit: 2 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg.clone()).unwrap();
+        assert_eq!(res.attributes[2].value, "2");
+        assert_eq!(res.attributes[3].value, "false");
+        let definitions = query_token_definitions(deps.as_ref(), None, None).unwrap().definitions;
+        assert_eq!(definitions.iter().map(|d| d.token_id.as_str()).collect::<Vec<_>>(), vec!["0", "1"]);
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg.clone()).unwrap();
+        assert_eq!(res.attributes[2].value, "1");
+        assert_eq!(res.attributes[3].value, "true");
+        assert_eq!(query_token_definitions(deps.as_ref(), None, None).unwrap().definitions.len(), 3);
+        let err = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NoDefinitionMigration {});
+    }
+
+    #[test]
+    fn migrating_holders() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            name: String::from("NFT TOKEN FIFTEEN"),
+            symbol: String::from("NFTFIFTEEN"),
+            transfer_policy: None,
+        };
+        let operator = mock_info("operator", &[]);
+        instantiate(deps.as_mut(), env.clone(), operator.clone(), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            Cw1155ExecuteMsg::DefineToken {
+                token_uri: None,
+                max_supply: Uint128::from(100u128),
+                transfer_policy: None,
+                token_type: None,
+                category: None,
+                royalty: None,
+            },
+        )
+        .unwrap();
+        let ore = res.attributes[2].value.clone();
+        for (to, amount) in [("alice", 5u128), ("bob", 3), ("carol", 2)] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                operator.clone(),
+                Cw1155ExecuteMsg::Mint { to: String::from(to), token_id: ore.clone(), amount: Uint128::from(amount), msg: None },
+            )
+            .unwrap();
+        }
+
+        // pretend the tokens were minted by a version without stats
+        TOKEN_STATS.remove(deps.as_mut().storage, &ore);
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.16.0").unwrap();
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        assert!(HOLDER_MIGRATION.may_load(&deps.storage).unwrap().is_some());
+
+        // balances are keyed by address length first, bob is counted before alice
+        let msg = Cw1155ExecuteMsg::MigrateHolders { limit: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.attributes[2].value, "1");
+        assert_eq!(res.attributes[3].value, "false");
+        assert_eq!(query_stats(deps.as_ref(), ore.clone()).unwrap().unique_holders, 1);
+
+        // a counted balance moves right away, an uncounted one once the backfill gets to it
+        let transfer = |from: &str, to: &str, amount: u128| Cw1155ExecuteMsg::TransferFrom {
+            from: from.to_string(),
+            to: to.to_string(),
+            token_id: ore.clone(),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), transfer("bob", "dave", 3)).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), transfer("alice", "bob", 5)).unwrap();
+        assert_eq!(query_stats(deps.as_ref(), ore.clone()).unwrap().unique_holders, 1);
+
+        let msg = Cw1155ExecuteMsg::MigrateHolders { limit: 10 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg.clone()).unwrap();
+        assert_eq!(res.attributes[3].value, "true");
+        // bob, carol and dave
+        assert_eq!(query_stats(deps.as_ref(), ore).unwrap().unique_holders, 3);
+        let err = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NoHolderMigration {});
+    }
 }
@@ -12,15 +12,72 @@ pub enum ContractError {
     #[error("Expired")]
     Expired {},
 
+    #[error("Allowance of token id : {token_id} is too low")]
+    InsufficientAllowance{token_id : String},
+
+    #[error("Token id : {token_id} is outside the cooperative scope")]
+    OutOfCooperativeScope{token_id : String},
+
     #[error("Zero Amount")]
     InvalidZeroAmount{},
 
     #[error("Token id : {token_id} undefined")]
     TokenUndefined{token_id : String},
 
+    #[error("Token id : {token_id} already defined")]
+    TokenAlreadyDefined{token_id : String},
+
+    #[error("Token id : {token_id} is invalid, chosen ids cannot be numbers or end in :<number>")]
+    InvalidTokenId{token_id : String},
+
+    #[error("Royalty share must be at most 1")]
+    InvalidRoyalty{},
+
     #[error("Invalid addresses transfer")]
     InvalidTransferAddress{},
 
     #[error("Exceed max supply")]
     ExceedMaxSupply{},
+
+    #[error("Transfer policy does not allow to transfer token id : {token_id}")]
+    TransferNotAllowed{token_id : String},
+
+    #[error("Metadata is frozen, token id : {token_id}")]
+    MetadataFrozen{token_id : String},
+
+    #[error("Collection metadata is frozen")]
+    CollectionMetadataFrozen{},
+
+    #[error("Non-fungible tokens are minted one at a time, amount must be 1")]
+    NonFungibleAmount{},
+
+    #[error("Non-fungible token {token_id} can only have a max_supply of 1")]
+    NonFungibleSupply{token_id : String},
+
+    #[error("Signer has no voucher key registered")]
+    VoucherSignerNotSet{},
+
+    #[error("Voucher signature is invalid")]
+    InvalidVoucherSignature{},
+
+    #[error("Voucher signer key is not a secp256k1 public key")]
+    InvalidVoucherPubkey{},
+
+    #[error("Voucher expired")]
+    VoucherExpired{},
+
+    #[error("Voucher already redeemed, nonce : {nonce}")]
+    VoucherRedeemed{nonce : u64},
+
+    #[error("Clawback has been renounced")]
+    ClawbackRenounced{},
+
+    #[error("Clawback reason must not be empty")]
+    EmptyClawbackReason{},
+
+    #[error("No definition migration is pending")]
+    NoDefinitionMigration{},
+
+    #[error("No holder migration is pending")]
+    NoHolderMigration{},
 }
@@ -1,5 +1,6 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{StdError, StdResult};
+use cw1155::TransferPolicy;
 
 
 #[cw_serde]
@@ -7,8 +8,14 @@ use cosmwasm_std::{StdError, StdResult};
 pub struct InstantiateMsg {
     pub name: String,
     pub symbol: String,
+    /// Policy for tokens defined without one, Free when not set
+    #[serde(default)]
+    pub transfer_policy: Option<TransferPolicy>,
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 impl InstantiateMsg {
     pub fn validate(&self) -> StdResult<()> {
         // Check name, symbol, decimals
@@ -1,6 +1,6 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
-use cw1155::{Expiration,TokenSupply};
+use cosmwasm_std::{Addr, Binary, StdResult, Storage, Timestamp, Uint128};
+use cw1155::{CooperativeScope,Expiration,Royalty,TokenSupply,TokenType,TransferPolicy};
 use cw_storage_plus::{Item, Map};
 
 #[cw_serde]
@@ -14,6 +14,43 @@ pub struct ContractInfo {
 pub struct CooperativeData{
     pub can_mint_for : bool,
     pub can_burn_from : bool,
+    /// Missing in cooperatives saved before scopes existed
+    #[serde(default)]
+    pub scope : CooperativeScope,
+}
+
+#[cw_serde]
+pub struct ClawbackRecord{
+    pub operator : Addr,
+    pub from : Addr,
+    pub to : Addr,
+    pub token_id : String,
+    pub amount : Uint128,
+    pub reason : String,
+    pub height : u64,
+    pub time : Timestamp,
+}
+
+#[cw_serde]
+pub struct TokenApproval {
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct TokenStats {
+    pub minted: Uint128,
+    pub burned: Uint128,
+    pub transfers: u64,
+    pub holders: u64,
+}
+
+/// Progress of the holder backfill started by `migrate` and finished by `MigrateHolders`
+#[cw_serde]
+pub struct HolderMigration {
+    /// Last balance counted, the next page starts after it
+    pub cursor: Option<(Addr, String)>,
 }
 
 use std::fmt;
@@ -49,11 +86,124 @@ pub const COOPERATIVES: Map<&Addr, CooperativeData> = Map::new("cooperatives");
 pub const BALANCES: Map<(&Addr, &str), Uint128> = Map::new("balances");
 /// Store the approval status, `(owner, spender) -> expiration`
 pub const APPROVES: Map<(&Addr, &Addr), Expiration> = Map::new("approves");
+/// Per token approvals, `(owner, operator, token_id) -> approval`
+pub const TOKEN_APPROVES: Map<(&Addr, &Addr, &str), TokenApproval> = Map::new("token_approves");
+/// Last `(owner, spender)` looked at by an unscoped prune, the next one starts after it
+pub const APPROVES_PRUNE_CURSOR: Item<(Addr, Addr)> = Item::new("approves_prune_cursor");
+/// Last operator looked at by a prune scoped to the owner, `owner -> operator`
+pub const APPROVES_OWNER_PRUNE_CURSORS: Map<&Addr, Addr> = Map::new("approves_owner_prune_cursors");
 
 /// Store the tokens metadata url, also supports enumerating tokens,
 /// An entry for token_id must exist as long as there's tokens in circulation.
-pub const TOKENS: Map<&str, String> = Map::new("tokens");
+/// None when the token uses the collection base_uri
+pub const TOKENS: Map<&str, Option<String>> = Map::new("tokens");
+
+/// Ids set up by DefineToken, TOKENS also holds the serials minted from them
+pub const TOKEN_DEFINITIONS: Map<&str, bool> = Map::new("token_definitions");
+/// Next running number MigrateDefinitions marks, set by migrate while older definitions are unmarked
+pub const DEFINITION_MIGRATION: Item<u128> = Item::new("definition_migration");
 
 //Keep track of token supply
 //An entry for token_id => TokenSupply
 pub const TOKEN_SUPPLIES: Map<&str, TokenSupply> = Map::new("token_supplies");
+
+/// Types set at DefineToken, ids without one are Fungible with 0 decimals
+pub const TOKEN_TYPES: Map<&str, TokenType> = Map::new("token_types");
+/// Categories the owner put token ids in, `token_id -> category`
+pub const TOKEN_CATEGORIES: Map<&str, String> = Map::new("token_categories");
+/// Same as TOKEN_CATEGORIES, `(category, token_id)` to list a category
+pub const CATEGORY_TOKENS: Map<(&str, &str), bool> = Map::new("category_tokens");
+/// Royalties set at DefineTokens, `token_id -> royalty`
+pub const TOKEN_ROYALTIES: Map<&str, Royalty> = Map::new("token_royalties");
+/// Holder of every minted NonFungible token, `token_id -> owner`
+pub const NFT_OWNERS: Map<&str, Addr> = Map::new("nft_owners");
+
+/// Policy of tokens defined without their own one
+pub const TRANSFER_POLICY: Item<TransferPolicy> = Item::new("transfer_policy");
+/// Policies set at DefineToken, `token_id -> policy`
+pub const TOKEN_TRANSFER_POLICIES: Map<&str, TransferPolicy> = Map::new("token_transfer_policies");
+/// Part of the balance locked by BindOnTransfer, `(owner, token_id) -> amount`
+pub const BOUND_BALANCES: Map<(&Addr, &str), Uint128> = Map::new("bound_balances");
+
+/// Set once the owner renounced ForceTransfer
+pub const CLAWBACK_RENOUNCED: Item<bool> = Item::new("clawback_renounced");
+/// Every ForceTransfer ever executed, `id -> record`
+pub const CLAWBACK_LOG: Map<u64, ClawbackRecord> = Map::new("clawback_log");
+
+/// Mint, burn, transfer and holder counts, `token_id -> stats`
+pub const TOKEN_STATS: Map<&str, TokenStats> = Map::new("token_stats");
+/// Only set while balances from before TOKEN_STATS are being counted
+pub const HOLDER_MIGRATION: Item<HolderMigration> = Item::new("holder_migration");
+
+/// Whether the holder backfill already got to `(owner, token_id)`, every balance is counted without one
+pub fn is_holder_counted(storage: &dyn Storage, owner: &Addr, token_id: &str) -> StdResult<bool> {
+    Ok(match HOLDER_MIGRATION.may_load(storage)? {
+        None => true,
+        Some(HolderMigration { cursor: None }) => false,
+        // compare storage keys, composite keys do not sort like the tuple
+        Some(HolderMigration { cursor: Some((last_owner, last_token_id)) }) => {
+            *BALANCES.key((owner, token_id)) <= *BALANCES.key((&last_owner, &last_token_id))
+        }
+    })
+}
+
+/// Uri template of tokens without their own uri
+pub const BASE_URI: Item<String> = Item::new("base_uri");
+
+/// Set once the owner froze the uri of every token
+pub const METADATA_FROZEN: Item<bool> = Item::new("metadata_frozen");
+/// Tokens whose uri the owner froze one by one
+pub const FROZEN_TOKENS: Map<&str, bool> = Map::new("frozen_tokens");
+
+/// secp256k1 public keys of voucher signers
+pub const VOUCHER_SIGNERS: Map<&Addr, Binary> = Map::new("voucher_signers");
+/// `(signer, nonce)` of redeemed vouchers
+pub const REDEEMED_VOUCHERS: Map<(&Addr, u64), bool> = Map::new("redeemed_vouchers");
+
+pub fn load_token_type(storage: &dyn Storage, token_id: &str) -> StdResult<TokenType> {
+    Ok(TOKEN_TYPES.may_load(storage, token_id)?.unwrap_or_default())
+}
+
+/// `<base>:<serial>` id of a serial minted from a NonFungible base type
+pub fn serial_token_id(base_id: &str, serial: u128) -> String {
+    format!("{}:{}", base_id, serial)
+}
+
+/// Base type of a serial id, the id itself otherwise
+pub fn base_token_id(token_id: &str) -> &str {
+    match token_id.rsplit_once(':') {
+        Some((base_id, serial)) if serial.parse::<u128>().is_ok() => base_id,
+        _ => token_id,
+    }
+}
+
+pub fn is_metadata_frozen(storage: &dyn Storage, token_id: &str) -> StdResult<bool> {
+    Ok(METADATA_FROZEN.may_load(storage)?.unwrap_or_default() || FROZEN_TOKENS.has(storage, token_id))
+}
+
+/// The token's own uri, or else BASE_URI filled in with token_id
+pub fn resolve_token_uri(storage: &dyn Storage, token_id: &str, token_uri: Option<String>) -> StdResult<Option<String>> {
+    if token_uri.is_some() {
+        return Ok(token_uri);
+    }
+    Ok(BASE_URI.may_load(storage)?.map(|base_uri| expand_base_uri(&base_uri, token_id)))
+}
+
+/// Replaces every `{id}` in base_uri, without a placeholder the id is appended.
+/// Numeric ids become 64 lowercase hex digits as ERC-1155 metadata clients expect,
+/// serial ids put the base type in the upper and the serial in the lower 128 bits (split id).
+pub fn expand_base_uri(base_uri: &str, token_id: &str) -> String {
+    let split = token_id
+        .split_once(':')
+        .and_then(|(base, serial)| Some((base.parse::<u128>().ok()?, serial.parse::<u128>().ok()?)));
+    let id = match (token_id.parse::<u128>(), split) {
+        (Ok(id), _) => format!("{:064x}", id),
+        (_, Some((base, serial))) => format!("{:032x}{:032x}", base, serial),
+        _ => token_id.to_string(),
+    };
+    if base_uri.contains("{id}") {
+        base_uri.replace("{id}", &id)
+    } else {
+        format!("{}{}", base_uri, id)
+    }
+}
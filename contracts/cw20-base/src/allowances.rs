@@ -1,11 +1,20 @@
 use cosmwasm_std::{
-    attr, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, 
+    attr, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order,
     Response, StdError, StdResult,Storage, Uint128,
 };
 use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
+use cw_storage_plus::Bound;
 
 use crate::error::ContractError;
-use crate::state::{ALLOWANCES,COOPERATIVES, ALLOWANCES_SPENDER, BALANCES, TOKEN_INFO,TOKEN_SUPPLY};
+use crate::holds::ensure_not_held;
+use crate::state::{
+    ALLOWANCES,COOPERATIVES, ALLOWANCES_PRUNE_CURSOR, ALLOWANCES_OWNER_PRUNE_CURSORS, ALLOWANCES_SPENDER, add_balance, sub_balance, update_stats, TOKEN_INFO,TOKEN_SUPPLY,
+};
+
+// max entries a single prune may look at
+const MAX_PRUNE_LIMIT: u32 = 100;
+// allowances of the owner checked for expiry each time one of them is spent
+const TOUCH_PRUNE_LIMIT: usize = 5;
 
 pub fn execute_increase_allowance(
     deps: DepsMut,
@@ -21,7 +30,10 @@ pub fn execute_increase_allowance(
     }
 
     let update_fn = |allow: Option<AllowanceResponse>| -> Result<_, _> {
-        let mut val = allow.unwrap_or_default();
+        // an expired allowance is dead, start over instead of reviving it
+        let mut val = allow
+            .filter(|a| !a.expires.is_expired(&env.block))
+            .unwrap_or_default();
         if let Some(exp) = expires {
             if exp.is_expired(&env.block) {
                 return Err(ContractError::InvalidExpiration {});
@@ -62,9 +74,9 @@ pub fn execute_decrease_allowance(
         (t.1, t.0)
     }
 
-    // load value and delete if it hits 0, or update otherwise
+    // load value and delete if it hits 0 or is expired, or update otherwise
     let mut allowance = ALLOWANCES.load(deps.storage, key)?;
-    if amount < allowance.allowance {
+    if amount < allowance.allowance && !allowance.expires.is_expired(&env.block) {
         // update the new amount
         allowance.allowance = allowance
             .allowance
@@ -117,8 +129,90 @@ pub fn deduct_allowance(
             None => Err(ContractError::NoAllowance {}),
         }
     };
-    ALLOWANCES.update(storage, (owner, spender), update_fn)?;
-    ALLOWANCES_SPENDER.update(storage, (spender, owner), update_fn)
+    let allowance = ALLOWANCES.update(storage, (owner, spender), update_fn)?;
+    ALLOWANCES_SPENDER.update(storage, (spender, owner), update_fn)?;
+
+    // nothing left to spend, don't keep the entry around
+    if allowance.allowance.is_zero() {
+        ALLOWANCES.remove(storage, (owner, spender));
+        ALLOWANCES_SPENDER.remove(storage, (spender, owner));
+    }
+    // spending an expired allowance fails and reverts any delete, so clean up on success
+    prune_expired_allowances(storage, block, Some(owner), TOUCH_PRUNE_LIMIT)?;
+    Ok(allowance)
+}
+
+/// Deletes expired allowances from both `ALLOWANCES` and `ALLOWANCES_SPENDER`, looking at
+/// no more than limit of them. Sweeps of one owner and of all owners resume from their own cursor.
+pub fn prune_expired_allowances(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    owner: Option<&Addr>,
+    limit: usize,
+) -> StdResult<u32> {
+    let scanned: Vec<((Addr, Addr), AllowanceResponse)> = match owner {
+        Some(owner) => {
+            let cursor = ALLOWANCES_OWNER_PRUNE_CURSORS.may_load(storage, owner)?;
+            let start = cursor.as_ref().map(Bound::exclusive);
+            let scanned = ALLOWANCES
+                .prefix(owner)
+                .range(storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(spender, allow)| ((owner.clone(), spender), allow)))
+                .collect::<StdResult<Vec<_>>>()?;
+            match scanned.last() {
+                Some(((_, last), _)) if scanned.len() == limit => {
+                    ALLOWANCES_OWNER_PRUNE_CURSORS.save(storage, owner, last)?
+                }
+                _ => ALLOWANCES_OWNER_PRUNE_CURSORS.remove(storage, owner),
+            }
+            scanned
+        }
+        None => {
+            let cursor = ALLOWANCES_PRUNE_CURSOR.may_load(storage)?;
+            let start = cursor.as_ref().map(|(owner, spender)| Bound::exclusive((owner, spender)));
+            let scanned = ALLOWANCES
+                .range(storage, start, None, Order::Ascending)
+                .take(limit)
+                .collect::<StdResult<Vec<_>>>()?;
+            match scanned.last() {
+                Some((last, _)) if scanned.len() == limit => {
+                    ALLOWANCES_PRUNE_CURSOR.save(storage, last)?
+                }
+                _ => ALLOWANCES_PRUNE_CURSOR.remove(storage),
+            }
+            scanned
+        }
+    };
+
+    let mut pruned = 0u32;
+    for ((owner, spender), allow) in scanned {
+        if allow.expires.is_expired(block) {
+            ALLOWANCES.remove(storage, (&owner, &spender));
+            ALLOWANCES_SPENDER.remove(storage, (&spender, &owner));
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+pub fn execute_prune_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: Option<String>,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    let owner_addr = owner.map(|owner| deps.api.addr_validate(&owner)).transpose()?;
+    let limit = limit.min(MAX_PRUNE_LIMIT) as usize;
+    let pruned = prune_expired_allowances(deps.storage, &env.block, owner_addr.as_ref(), limit)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "prune_expired"),
+        attr("by", info.sender),
+        attr("pruned", pruned.to_string()),
+    ]);
+    Ok(res)
 }
 
 pub fn execute_transfer_from(
@@ -134,19 +228,11 @@ pub fn execute_transfer_from(
 
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    ensure_not_held(deps.storage, &owner_addr, &env.block, amount)?;
 
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    sub_balance(deps.storage, &owner_addr, amount)?;
+    add_balance(deps.storage, &rcpt_addr, amount)?;
+    update_stats(deps.storage, |stats| stats.transfers += 1)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "transfer_from"),
@@ -181,21 +267,17 @@ pub fn execute_burn_from(
 
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    ensure_not_held(deps.storage, &owner_addr, &env.block, amount)?;
 
     // lower balance
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
+    sub_balance(deps.storage, &owner_addr, amount)?;
     
     // reduce total_supply
     TOKEN_SUPPLY.update(deps.storage, |mut supply| -> StdResult<_> {
         supply.total_supply = supply.total_supply.checked_sub(amount)?;
         Ok(supply)
     })?;
+    update_stats(deps.storage, |stats| stats.burned += amount)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "burn_from"),
@@ -220,20 +302,12 @@ pub fn execute_send_from(
 
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    ensure_not_held(deps.storage, &owner_addr, &env.block, amount)?;
 
     // move the tokens to the contract
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    sub_balance(deps.storage, &owner_addr, amount)?;
+    add_balance(deps.storage, &rcpt_addr, amount)?;
+    update_stats(deps.storage, |stats| stats.transfers += 1)?;
 
     let attrs = vec![
         attr("action", "send_from"),
@@ -897,4 +971,105 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn prune_expired_allowances_works() {
+        let mut deps = mock_dependencies_with_balance(&[]);
+        let owner = String::from("addr0001");
+        let owner2 = String::from("addr0002");
+        let spender = String::from("addr0003");
+        let spender2 = String::from("addr0004");
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+
+        let env = mock_env();
+        let soon = Some(Expiration::AtHeight(env.block.height + 1));
+        for (granter, grantee, expires) in [
+            (&owner, &spender, soon),
+            (&owner, &spender2, None),
+            (&owner2, &spender, soon),
+        ] {
+            let msg = Cw20ExecuteMsg::IncreaseAllowance {
+                spender: grantee.clone(),
+                amount: Uint128::new(100),
+                expires,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(granter, &[]), msg).unwrap();
+        }
+
+        // nothing expired yet
+        let msg = Cw20ExecuteMsg::PruneExpired { owner: None, limit: 10 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("pruned", "0"));
+
+        let mut env = env;
+        env.block.height += 1;
+
+        // scoped to one owner
+        let msg = Cw20ExecuteMsg::PruneExpired { owner: Some(owner.clone()), limit: 10 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("pruned", "1"));
+        assert!(!ALLOWANCES.has(&deps.storage, (&Addr::unchecked(&owner), &Addr::unchecked(&spender))));
+        assert!(!ALLOWANCES_SPENDER.has(&deps.storage, (&Addr::unchecked(&spender), &Addr::unchecked(&owner))));
+        assert!(ALLOWANCES.has(&deps.storage, (&Addr::unchecked(&owner), &Addr::unchecked(&spender2))));
+
+        // unscoped sweep looks at one entry per call and picks up where it stopped
+        let msg = Cw20ExecuteMsg::PruneExpired { owner: None, limit: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg.clone()).unwrap();
+        assert_eq!(res.attributes[2], attr("pruned", "0"));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("pruned", "1"));
+        assert!(!ALLOWANCES.has(&deps.storage, (&Addr::unchecked(&owner2), &Addr::unchecked(&spender))));
+
+        // one owner's sweep resumes too, so live allowances first cannot hide expired ones
+        let soon = Some(Expiration::AtHeight(env.block.height + 1));
+        for grantee in ["addr0005", "addr0006"] {
+            let msg = Cw20ExecuteMsg::IncreaseAllowance {
+                spender: grantee.to_string(),
+                amount: Uint128::new(100),
+                expires: soon,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(&owner, &[]), msg).unwrap();
+        }
+        env.block.height += 1;
+        let msg = Cw20ExecuteMsg::PruneExpired { owner: Some(owner.clone()), limit: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg.clone()).unwrap();
+        assert_eq!(res.attributes[2], attr("pruned", "0"));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("pruned", "1"));
+        assert!(!ALLOWANCES.has(&deps.storage, (&Addr::unchecked(&owner), &Addr::unchecked("addr0005"))));
+
+        // spending an allowance clears the owner's other expired ones
+        let msg = Cw20ExecuteMsg::TransferFrom {
+            from: owner.clone(),
+            to: spender.clone(),
+            amount: Uint128::new(10),
+        };
+        execute(deps.as_mut(), env, mock_info(&spender2, &[]), msg).unwrap();
+        assert!(!ALLOWANCES.has(&deps.storage, (&Addr::unchecked(&owner), &Addr::unchecked("addr0006"))));
+        assert!(!ALLOWANCES_SPENDER.has(&deps.storage, (&Addr::unchecked("addr0006"), &Addr::unchecked(&owner))));
+    }
+
+    #[test]
+    fn spent_allowance_is_removed() {
+        let mut deps = mock_dependencies_with_balance(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+
+        let msg = Cw20ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(100),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(owner.as_ref(), &[]), msg).unwrap();
+
+        let msg = Cw20ExecuteMsg::TransferFrom {
+            from: owner.clone(),
+            to: spender.clone(),
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(spender.as_ref(), &[]), msg).unwrap();
+        assert!(!ALLOWANCES.has(&deps.storage, (&Addr::unchecked(&owner), &Addr::unchecked(&spender))));
+        assert!(!ALLOWANCES_SPENDER.has(&deps.storage, (&Addr::unchecked(&spender), &Addr::unchecked(&owner))));
+    }
 }
@@ -0,0 +1,323 @@
+use cosmwasm_std::{attr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Uint128};
+use cw20::{ClawbackLogEntry, ClawbackLogResponse};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::holds::release_uncovered_holds;
+use crate::state::{
+    add_balance, sub_balance, update_stats, ClawbackRecord, CLAWBACK_LOG, CLAWBACK_RENOUNCED,
+    TOKEN_INFO,
+};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn execute_force_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    amount: Uint128,
+    reason: String,
+) -> Result<Response, ContractError> {
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let config = TOKEN_INFO.load(deps.storage)?;
+    //Only contract owner can force transfer.
+    if config.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if CLAWBACK_RENOUNCED.may_load(deps.storage)?.unwrap_or(false) {
+        return Err(ContractError::ClawbackRenounced {});
+    }
+    if reason.trim().is_empty() {
+        return Err(ContractError::EmptyClawbackReason {});
+    }
+
+    let from_addr = deps.api.addr_validate(&from)?;
+    let rcpt_addr = deps.api.addr_validate(&to)?;
+
+    // approvals and holds are bypassed on purpose, holds the rest no longer covers are released
+    sub_balance(deps.storage, &from_addr, amount)?;
+    add_balance(deps.storage, &rcpt_addr, amount)?;
+    update_stats(deps.storage, |stats| stats.transfers += 1)?;
+    let released = release_uncovered_holds(deps.storage, &from_addr, &env.block)?;
+
+    let id = CLAWBACK_LOG
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(0, |last| last + 1);
+    let record = ClawbackRecord {
+        operator: info.sender.clone(),
+        from: from_addr,
+        to: rcpt_addr,
+        amount,
+        reason: reason.clone(),
+        height: env.block.height,
+        time: env.block.time,
+    };
+    CLAWBACK_LOG.save(deps.storage, id, &record)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "force_transfer"),
+        attr("by", info.sender),
+        attr("from", from),
+        attr("to", to),
+        attr("amount", amount),
+        attr("reason", reason),
+        attr("clawback_id", id.to_string()),
+        attr("released_holds", released.join(",")),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_renounce_clawback(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = TOKEN_INFO.load(deps.storage)?;
+    if config.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    CLAWBACK_RENOUNCED.save(deps.storage, &true)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "renounce_clawback"),
+        attr("by", info.sender),
+    ]);
+    Ok(res)
+}
+
+pub fn query_clawback_log(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ClawbackLogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let entries = CLAWBACK_LOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, record)| ClawbackLogEntry {
+                id,
+                operator: record.operator.into(),
+                from: record.from.into(),
+                to: record.to.into(),
+                amount: record.amount,
+                reason: record.reason,
+                height: record.height,
+                time: record.time,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    let renounced = CLAWBACK_RENOUNCED.may_load(deps.storage)?.unwrap_or(false);
+    Ok(ClawbackLogResponse { renounced, entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{Addr, StdError};
+    use cw20::Cw20ExecuteMsg;
+
+    use crate::contract::{execute, instantiate, query_balance};
+    use crate::msg::InstantiateMsg;
+    use crate::state::HOLDS;
+
+    fn do_instantiate(mut deps: DepsMut, players: &[(&str, u128)]) {
+        let instantiate_msg = InstantiateMsg {
+            name: "Auto Gen".to_string(),
+            symbol: "AUTO".to_string(),
+            decimals: 3,
+            max_supply: Uint128::from(10000000000u128),
+            marketing: None,
+        };
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate(deps.branch(), env.clone(), info.clone(), instantiate_msg).unwrap();
+        for (player, amount) in players {
+            execute(deps.branch(), env.clone(), info.clone(), Cw20ExecuteMsg::Mint {
+                to: player.to_string(),
+                amount: Uint128::new(*amount),
+            })
+            .unwrap();
+        }
+    }
+
+    fn force_transfer(from: &str, to: &str, amount: u128, reason: &str) -> Cw20ExecuteMsg {
+        Cw20ExecuteMsg::ForceTransfer {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: Uint128::new(amount),
+            reason: reason.to_string(),
+        }
+    }
+
+    #[test]
+    fn force_transfer_is_logged() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), &[("thief", 1000)]);
+        let env = mock_env();
+
+        // only owner can claw back
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("thief", &[]),
+            force_transfer("thief", "victim", 400, "exploit"),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // reason is required
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            force_transfer("thief", "victim", 400, " "),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::EmptyClawbackReason {});
+
+        // no allowance needed
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            force_transfer("thief", "victim", 400, "stolen in raid #12"),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            force_transfer("thief", "victim", 100, "exploit refund"),
+        )
+        .unwrap();
+        assert_eq!(query_balance(deps.as_ref(), "thief".to_string()).unwrap().balance, Uint128::new(500));
+        assert_eq!(query_balance(deps.as_ref(), "victim".to_string()).unwrap().balance, Uint128::new(500));
+
+        // cannot take more than the account has
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            force_transfer("thief", "victim", 501, "too much"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        let log = query_clawback_log(deps.as_ref(), None, None).unwrap();
+        assert!(!log.renounced);
+        assert_eq!(
+            log.entries,
+            vec![
+                ClawbackLogEntry {
+                    id: 0,
+                    operator: "creator".to_string(),
+                    from: "thief".to_string(),
+                    to: "victim".to_string(),
+                    amount: Uint128::new(400),
+                    reason: "stolen in raid #12".to_string(),
+                    height: env.block.height,
+                    time: env.block.time,
+                },
+                ClawbackLogEntry {
+                    id: 1,
+                    operator: "creator".to_string(),
+                    from: "thief".to_string(),
+                    to: "victim".to_string(),
+                    amount: Uint128::new(100),
+                    reason: "exploit refund".to_string(),
+                    height: env.block.height,
+                    time: env.block.time,
+                },
+            ]
+        );
+        let log = query_clawback_log(deps.as_ref(), Some(0), None).unwrap();
+        assert_eq!(log.entries.len(), 1);
+        assert_eq!(log.entries[0].id, 1);
+    }
+
+    #[test]
+    fn force_transfer_ignores_holds() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), &[("thief", 1000)]);
+        let env = mock_env();
+
+        for (hold_id, amount) in [("a", 300u128), ("b", 500), ("c", 100)] {
+            execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), Cw20ExecuteMsg::Hold {
+                account: "thief".to_string(),
+                amount: Uint128::new(amount),
+                hold_id: hold_id.to_string(),
+                expires: None,
+            })
+            .unwrap();
+        }
+
+        // 900 of 1000 are held, the clawback takes 600 anyway
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            force_transfer("thief", "victim", 600, "stolen in raid #12"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[7].value, "b");
+        assert_eq!(query_balance(deps.as_ref(), "thief".to_string()).unwrap().balance, Uint128::new(400));
+        assert_eq!(query_balance(deps.as_ref(), "victim".to_string()).unwrap().balance, Uint128::new(600));
+
+        // the 400 left cover holds a and c, b was released
+        let holds: Vec<_> = HOLDS
+            .prefix(&Addr::unchecked("thief"))
+            .keys(&deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(holds, vec!["a".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn renounce_clawback_is_permanent() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), &[("thief", 1000)]);
+        let env = mock_env();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("thief", &[]),
+            Cw20ExecuteMsg::RenounceClawback {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            Cw20ExecuteMsg::RenounceClawback {},
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("creator", &[]),
+            force_transfer("thief", "victim", 400, "exploit"),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ClawbackRenounced {});
+        let log = query_clawback_log(deps.as_ref(), None, None).unwrap();
+        assert!(log.renounced);
+        assert!(log.entries.is_empty());
+    }
+}
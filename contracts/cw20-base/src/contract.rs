@@ -7,31 +7,46 @@ use cosmwasm_std::{
 
 use cw2::set_contract_version;
 use cw20::{
-    BalanceResponse,Cw20ReceiveMsg, DownloadLogoResponse, EmbeddedLogo, Logo, LogoInfo,
-    MarketingInfoResponse, TokenInfoResponse,Cw20QueryMsg,TokenSupplyResponse,
+    BalanceResponse, BalancesResponse, Cw20ReceiveMsg, DownloadLogoResponse, EmbeddedLogo, Logo, LogoInfo,
+    MarketingInfoResponse, TokenInfoResponse,Cw20QueryMsg,TokenSupplyResponse,StatsResponse,
 };
+use cw_storage_plus::Bound;
 use cw_utils::ensure_from_older_version;
 
 use crate::allowances::{
-    execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
-    execute_transfer_from, query_allowance,
+    execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_prune_expired,
+    execute_send_from, execute_transfer_from, query_allowance,
 };
-use crate::enumerable::{query_all_accounts, query_owner_allowances, query_spender_allowances};
+use crate::enumerable::{
+    query_all_accounts, query_all_accounts_with_balances, query_holder_count, query_holds,
+    query_top_holders, query_owner_allowances, query_spender_allowances};
 use crate::error::ContractError;
+use crate::clawback::{execute_force_transfer, execute_renounce_clawback, query_clawback_log};
+use crate::holds::{
+    ensure_not_held, execute_hold, execute_release_hold, execute_settle_hold,
+    query_available_balance,
+};
 use crate::msg::{InstantiateMsg, MigrateMsg};
 use cw20::Cw20ExecuteMsg;
 
 use crate::state::{
-    TokenInfo,CooperativeData, ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, 
-    LOGO, MARKETING_INFO,TOKEN_INFO,TOKEN_SUPPLY,COOPERATIVES,TokenSupply,
+    TokenInfo,CooperativeData, ALLOWANCES, ALLOWANCES_SPENDER, add_balance, balances, sub_balance, update_stats, HOLDER_COUNT,
+    BalanceMigration, BALANCE_MIGRATION,
+    LOGO, MARKETING_INFO,TOKEN_INFO,TOKEN_SUPPLY,COOPERATIVES,TokenSupply,STATS,
 };
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw20-base";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// max addresses a single Balances query may ask for
+const MAX_BALANCES_ADDRESSES: usize = 100;
+
 const LOGO_SIZE_CAP: usize = 5 * 1024;
 
+// max balances a single MigrateBalances call may re-index
+const MAX_MIGRATE_LIMIT: u32 = 100;
+
 /// Checks if data starts with XML preamble
 fn verify_xml_preamble(data: &[u8]) -> Result<(), ContractError> {
     // The easiest way to perform this check would be just match on regex, however regex
@@ -121,6 +136,7 @@ pub fn instantiate(
         max_supply : msg.max_supply,
     };
     TOKEN_SUPPLY.save(deps.storage, &supply)?;
+    HOLDER_COUNT.save(deps.storage, &0)?;
 
     if let Some(marketing) = msg.marketing {
         let logo = if let Some(logo) = marketing.logo {
@@ -178,6 +194,10 @@ pub fn execute(
         Cw20ExecuteMsg::BurnFrom { from, amount } => execute_burn_from(deps, env, info, from, amount),
         
         Cw20ExecuteMsg::SendFrom {from, contract,amount,msg} => execute_send_from(deps, env, info, from, contract, amount, msg),
+
+        Cw20ExecuteMsg::PruneExpired {owner,limit} => execute_prune_expired(deps, env, info, owner, limit),
+
+        Cw20ExecuteMsg::MigrateBalances { limit } => execute_migrate_balances(deps, info, limit),
         
         Cw20ExecuteMsg::UpdateMarketing {
             project,
@@ -201,6 +221,16 @@ pub fn execute(
         Cw20ExecuteMsg::SetMaxSupply {
             max_supply,
        } => execute_set_max_supply(deps, env, info,max_supply),
+
+        Cw20ExecuteMsg::Hold {account,amount,hold_id,expires} => execute_hold(deps, env, info, account, amount, hold_id, expires),
+
+        Cw20ExecuteMsg::ReleaseHold {account,hold_id} => execute_release_hold(deps, env, info, account, hold_id),
+
+        Cw20ExecuteMsg::SettleHold {account,hold_id,to} => execute_settle_hold(deps, env, info, account, hold_id, to),
+
+        Cw20ExecuteMsg::ForceTransfer {from,to,amount,reason} => execute_force_transfer(deps, env, info, from, to, amount, reason),
+
+        Cw20ExecuteMsg::RenounceClawback {} => execute_renounce_clawback(deps, info),
     }
 }
 
@@ -227,7 +257,7 @@ pub fn execute_change_owner(
 
 pub fn execute_transfer(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
@@ -237,19 +267,11 @@ pub fn execute_transfer(
     }
 
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    ensure_not_held(deps.storage, &info.sender, &env.block, amount)?;
 
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    sub_balance(deps.storage, &info.sender, amount)?;
+    add_balance(deps.storage, &rcpt_addr, amount)?;
+    update_stats(deps.storage, |stats| stats.transfers += 1)?;
 
     let res = Response::new()
         .add_attribute("action", "transfer")
@@ -261,27 +283,23 @@ pub fn execute_transfer(
 
 pub fn execute_burn(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
+    ensure_not_held(deps.storage, &info.sender, &env.block, amount)?;
 
     // lower balance
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
+    sub_balance(deps.storage, &info.sender, amount)?;
     // reduce total_supply
     TOKEN_SUPPLY.update(deps.storage, |mut supply| -> StdResult<_> {
         supply.total_supply = supply.total_supply.checked_sub(amount)?;
         Ok(supply)
     })?;
+    update_stats(deps.storage, |stats| stats.burned += amount)?;
 
     let res = Response::new()
         .add_attribute("action", "burn")
@@ -403,14 +421,11 @@ pub fn execute_mint_for(
         return Err(ContractError::CannotExceedCap {});
     }
     TOKEN_SUPPLY.save(deps.storage, &supply)?;
+    update_stats(deps.storage, |stats| stats.minted += amount)?;
 
     // add amount to recipient balance
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    add_balance(deps.storage, &rcpt_addr, amount)?;
 
     let res = Response::new()
         .add_attribute("action", "mint")
@@ -421,7 +436,7 @@ pub fn execute_mint_for(
 
 pub fn execute_send(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     contract: String,
     amount: Uint128,
@@ -432,20 +447,12 @@ pub fn execute_send(
     }
 
     let rcpt_addr = deps.api.addr_validate(&contract)?;
+    ensure_not_held(deps.storage, &info.sender, &env.block, amount)?;
 
     // move the tokens to the contract
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    sub_balance(deps.storage, &info.sender, amount)?;
+    add_balance(deps.storage, &rcpt_addr, amount)?;
+    update_stats(deps.storage, |stats| stats.transfers += 1)?;
 
     let res = Response::new()
         .add_attribute("action", "send")
@@ -553,10 +560,18 @@ pub fn execute_upload_logo(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
     match msg {
         
         Cw20QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+
+        Cw20QueryMsg::Balances { addresses } => to_binary(&query_balances(deps, addresses)?),
+
+        Cw20QueryMsg::AvailableBalance { address } => to_binary(&query_available_balance(deps, env, address)?),
+
+        Cw20QueryMsg::Holds {account,start_after,limit} => to_binary(&query_holds(deps, env, account, start_after, limit)?),
+
+        Cw20QueryMsg::ClawbackLog {start_after,limit} => to_binary(&query_clawback_log(deps, start_after, limit)?),
         
         Cw20QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
         
@@ -567,8 +582,15 @@ pub fn query(deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
         Cw20QueryMsg::AllAllowances {owner,start_after,limit} => to_binary(&query_owner_allowances(deps, owner, start_after, limit)?),
         
         Cw20QueryMsg::AllSpenderAllowances {spender,start_after,limit} => to_binary(&query_spender_allowances(deps,spender,start_after,limit,)?),
+        
+        Cw20QueryMsg::Stats {} => to_binary(&query_stats(deps)?),
 
         Cw20QueryMsg::AllAccounts { start_after, limit } => to_binary(&query_all_accounts(deps, start_after, limit)?),
+        Cw20QueryMsg::AllAccountsWithBalances { start_after, limit } => {
+            to_binary(&query_all_accounts_with_balances(deps, start_after, limit)?)
+        }
+        Cw20QueryMsg::TopHolders { limit } => to_binary(&query_top_holders(deps, limit)?),
+        Cw20QueryMsg::HolderCount {} => to_binary(&query_holder_count(deps)?),
         
         Cw20QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(deps)?),
  
@@ -578,12 +600,26 @@ pub fn query(deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
 
 pub fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse> {
     let address = deps.api.addr_validate(&address)?;
-    let balance = BALANCES
+    let balance = balances()
         .may_load(deps.storage, &address)?
         .unwrap_or_default();
     Ok(BalanceResponse { balance })
 }
 
+pub fn query_balances(deps: Deps, addresses: Vec<String>) -> StdResult<BalancesResponse> {
+    if addresses.len() > MAX_BALANCES_ADDRESSES {
+        return Err(StdError::generic_err(format!(
+            "Cannot query more than {} addresses at once",
+            MAX_BALANCES_ADDRESSES
+        )));
+    }
+    let balances = addresses
+        .into_iter()
+        .map(|address| query_balance(deps, address).map(|res| res.balance))
+        .collect::<StdResult<_>>()?;
+    Ok(BalancesResponse { balances })
+}
+
 pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
     let info = TOKEN_INFO.load(deps.storage)?;
     let res = TokenInfoResponse {
@@ -603,6 +639,16 @@ pub fn query_token_supply(deps: Deps) -> StdResult<TokenSupplyResponse> {
     Ok(res)
 }
 
+pub fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let stats = STATS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(StatsResponse {
+        minted: stats.minted,
+        burned: stats.burned,
+        transfers: stats.transfers,
+        unique_holders: HOLDER_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
 pub fn query_marketing_info(deps: Deps) -> StdResult<MarketingInfoResponse> {
     Ok(MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default())
 }
@@ -636,9 +682,58 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
             ALLOWANCES_SPENDER.save(deps.storage, (&spender, &owner), &allowance)?;
         }
     }
+    if HOLDER_COUNT.may_load(deps.storage)?.is_none() && BALANCE_MIGRATION.may_load(deps.storage)?.is_none() {
+        // Balances used to be a plain map, MigrateBalances indexes them page by page
+        BALANCE_MIGRATION.save(deps.storage, &BalanceMigration { cursor: None, holders: 0 })?;
+    }
     Ok(Response::default())
 }
 
+/// Re-indexes the next page of legacy balances and drops zero entries.
+/// The last page saves HOLDER_COUNT and ends the migration.
+pub fn execute_migrate_balances(
+    deps: DepsMut,
+    info: MessageInfo,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    let mut migration = BALANCE_MIGRATION
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoBalanceMigration {})?;
+    let limit = limit.clamp(1, MAX_MIGRATE_LIMIT) as usize;
+
+    // Legacy entries share the primary namespace, ranging it sees indexed and unindexed ones alike
+    let start = migration.cursor.as_ref().map(Bound::exclusive);
+    let page = balances()
+        .range(deps.storage, start, None, Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (account, balance) in page.iter() {
+        if balance.is_zero() {
+            balances().remove(deps.storage, account)?;
+        } else {
+            balances().save(deps.storage, account, balance)?;
+            migration.holders += 1;
+        }
+    }
+
+    let done = page.len() < limit;
+    if done {
+        HOLDER_COUNT.save(deps.storage, &migration.holders)?;
+        BALANCE_MIGRATION.remove(deps.storage);
+    } else {
+        migration.cursor = page.last().map(|(account, _)| account.clone());
+        BALANCE_MIGRATION.save(deps.storage, &migration)?;
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "migrate_balances")
+        .add_attribute("by", info.sender)
+        .add_attribute("migrated", page.len().to_string())
+        .add_attribute("done", done.to_string());
+    Ok(res)
+}
+
 ///////////////////////////////////////////////////////
 // Test Sections
 ///////////////////////////////////////////////////////
@@ -999,7 +1094,7 @@ mod tests {
         // check balance query (empty)
         let data = query(
             deps.as_ref(),
-            env,
+            env.clone(),
             Cw20QueryMsg::Balance {
                 address: String::from("addr0002"),
             },
@@ -1007,6 +1102,23 @@ mod tests {
         .unwrap();
         let loaded: BalanceResponse = from_binary(&data).unwrap();
         assert_eq!(loaded.balance, Uint128::zero());
+
+        // check batch balance query keeps the order of addresses
+        let data = query(
+            deps.as_ref(),
+            env.clone(),
+            Cw20QueryMsg::Balances {
+                addresses: vec![String::from("addr0002"), String::from("addr0001")],
+            },
+        )
+        .unwrap();
+        let loaded: BalancesResponse = from_binary(&data).unwrap();
+        assert_eq!(loaded.balances, vec![Uint128::zero(), amount1]);
+
+        // too many addresses at once
+        let addresses = vec![String::from("addr0001"); MAX_BALANCES_ADDRESSES + 1];
+        let err = query(deps.as_ref(), env, Cw20QueryMsg::Balances { addresses }).unwrap_err();
+        assert!(matches!(err, StdError::GenericErr { .. }));
     }
 
     #[test]
@@ -1118,6 +1230,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stats_work() {
+        let mut deps = mock_dependencies();
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let amount1 = Uint128::from(1000u128);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = Cw20ExecuteMsg::Transfer {
+            to: addr2,
+            amount: Uint128::from(300u128),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = Cw20ExecuteMsg::Burn {
+            amount: Uint128::from(700u128),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(
+            query_stats(deps.as_ref()).unwrap(),
+            StatsResponse {
+                minted: amount1,
+                burned: Uint128::from(700u128),
+                transfers: 1,
+                unique_holders: 1,
+            }
+        );
+    }
+
     #[test]
     fn send() {
         let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
@@ -1319,6 +1462,59 @@ mod tests {
                 }]
             );
         }
+
+        #[test]
+        fn migrate_balances_in_pages() {
+            let mut deps = mock_dependencies();
+            do_instantiate(deps.as_mut(), "addr0000", Uint128::new(1000));
+
+            // Pretend the balances were written by a version without the amount index
+            let legacy: cw_storage_plus::Map<&Addr, Uint128> = cw_storage_plus::Map::new("balance");
+            legacy.save(deps.as_mut().storage, &Addr::unchecked("addr0001"), &Uint128::new(50)).unwrap();
+            legacy.save(deps.as_mut().storage, &Addr::unchecked("addr0002"), &Uint128::zero()).unwrap();
+            legacy.save(deps.as_mut().storage, &Addr::unchecked("addr0003"), &Uint128::new(30)).unwrap();
+            HOLDER_COUNT.remove(deps.as_mut().storage);
+            cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.16.0").unwrap();
+
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+            assert!(BALANCE_MIGRATION.may_load(&deps.storage).unwrap().is_some());
+            // Running the migration again does not restart the re-index
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+            let msg = Cw20ExecuteMsg::MigrateBalances { limit: 2 };
+            let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg.clone()).unwrap();
+            assert_eq!(res.attributes[2].value, "2");
+            assert_eq!(res.attributes[3].value, "false");
+
+            // addr0001 was already counted and leaves, addr0004 is counted when the migration reaches it
+            let transfer = Cw20ExecuteMsg::Transfer { to: "addr0004".into(), amount: Uint128::new(50) };
+            execute(deps.as_mut(), mock_env(), mock_info("addr0001", &[]), transfer).unwrap();
+            assert_eq!(query_holder_count(deps.as_ref()).unwrap().count, 0);
+
+            let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg.clone()).unwrap();
+            assert_eq!(res.attributes[3].value, "false");
+            let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg.clone()).unwrap();
+            assert_eq!(res.attributes[2].value, "1");
+            assert_eq!(res.attributes[3].value, "true");
+
+            assert_eq!(query_holder_count(deps.as_ref()).unwrap().count, 3);
+            assert!(legacy.may_load(&deps.storage, &Addr::unchecked("addr0002")).unwrap().is_none());
+            let holders = query_top_holders(deps.as_ref(), None).unwrap().holders;
+            let holders: Vec<_> = holders.into_iter().map(|h| (h.address, h.balance.u128())).collect();
+            assert_eq!(
+                holders,
+                vec![("addr0000".to_string(), 1000), ("addr0004".to_string(), 50), ("addr0003".to_string(), 30)]
+            );
+
+            // Nothing left to migrate
+            let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+            assert_eq!(err, ContractError::NoBalanceMigration {});
+
+            // Counting is back on HOLDER_COUNT
+            let transfer = Cw20ExecuteMsg::Transfer { to: "addr0005".into(), amount: Uint128::new(10) };
+            execute(deps.as_mut(), mock_env(), mock_info("addr0003", &[]), transfer).unwrap();
+            assert_eq!(query_holder_count(deps.as_ref()).unwrap().count, 4);
+        }
     }
 
     mod marketing {
@@ -1,10 +1,10 @@
-use cosmwasm_std::{Deps, Order, StdResult};
+use cosmwasm_std::{Deps, Env, Order, StdResult};
 use cw20::{
-    AllAccountsResponse, AllAllowancesResponse, AllSpenderAllowancesResponse, AllowanceInfo,
-    SpenderAllowanceInfo,
+    AccountBalance, AllAccountsResponse, AllAccountsWithBalancesResponse, AllAllowancesResponse, AllSpenderAllowancesResponse, AllowanceInfo,
+    HolderCountResponse, HoldInfo, HoldsResponse, SpenderAllowanceInfo, TopHoldersResponse,
 };
 
-use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES};
+use crate::state::{balances, ALLOWANCES, ALLOWANCES_SPENDER, HOLDER_COUNT, HOLDS};
 use cw_storage_plus::Bound;
 
 // settings for pagination
@@ -71,7 +71,7 @@ pub fn query_all_accounts(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
 
-    let accounts = BALANCES
+    let accounts = balances()
         .keys(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| item.map(Into::into))
@@ -80,6 +80,82 @@ pub fn query_all_accounts(
     Ok(AllAccountsResponse { accounts })
 }
 
+pub fn query_all_accounts_with_balances(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAccountsWithBalancesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let accounts = balances()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(addr, balance)| AccountBalance {
+                address: addr.into(),
+                balance,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(AllAccountsWithBalancesResponse { accounts })
+}
+
+//Get accounts ordered by balance, richest first.
+pub fn query_top_holders(deps: Deps, limit: Option<u32>) -> StdResult<TopHoldersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let holders = balances()
+        .idx
+        .amount
+        .range(deps.storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(addr, balance)| AccountBalance {
+                address: addr.into(),
+                balance,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(TopHoldersResponse { holders })
+}
+
+pub fn query_holder_count(deps: Deps) -> StdResult<HolderCountResponse> {
+    let count = HOLDER_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    Ok(HolderCountResponse { count })
+}
+
+//Get all holds on account that are not expired yet.
+pub fn query_holds(
+    deps: Deps,
+    env: Env,
+    account: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<HoldsResponse> {
+    let account_addr = deps.api.addr_validate(&account)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+
+    let holds = HOLDS
+        .prefix(&account_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|r| r.is_err() || !r.as_ref().unwrap().1.expires.is_expired(&env.block))
+        .take(limit)
+        .map(|item| {
+            item.map(|(hold_id, hold)| HoldInfo {
+                hold_id,
+                holder: hold.holder.into(),
+                amount: hold.amount,
+                expires: hold.expires,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(HoldsResponse { holds })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,4 +391,90 @@ mod tests {
                 .unwrap();
         assert_eq!(accounts.accounts, expected_order[3..].to_vec());
     }
+
+    #[test]
+    fn top_holders_and_holder_count_work() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+
+        let acct1 = String::from("acct01");
+        let acct2 = String::from("zebra");
+        let acct3 = String::from("nice");
+
+        do_instantiate(deps.as_mut(), &acct1, Uint128::new(1000));
+        let count = query_holder_count(deps.as_ref()).unwrap();
+        assert_eq!(count.count, 1);
+
+        let info = mock_info(acct1.as_ref(), &[]);
+        let env = mock_env();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            Cw20ExecuteMsg::Transfer {
+                to: acct2.clone(),
+                amount: Uint128::new(600),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            Cw20ExecuteMsg::Transfer {
+                to: acct3.clone(),
+                amount: Uint128::new(150),
+            },
+        )
+        .unwrap();
+
+        let count = query_holder_count(deps.as_ref()).unwrap();
+        assert_eq!(count.count, 3);
+
+        let top = query_top_holders(deps.as_ref(), None).unwrap();
+        assert_eq!(
+            top.holders,
+            vec![
+                AccountBalance { address: acct2.clone(), balance: Uint128::new(600) },
+                AccountBalance { address: acct1.clone(), balance: Uint128::new(250) },
+                AccountBalance { address: acct3.clone(), balance: Uint128::new(150) },
+            ]
+        );
+        let top = query_top_holders(deps.as_ref(), Some(1)).unwrap();
+        assert_eq!(top.holders.len(), 1);
+        assert_eq!(top.holders[0].address, acct2);
+
+        let all = query_all_accounts_with_balances(deps.as_ref(), None, None).unwrap();
+        assert_eq!(
+            all.accounts,
+            vec![
+                AccountBalance { address: acct1.clone(), balance: Uint128::new(250) },
+                AccountBalance { address: acct3.clone(), balance: Uint128::new(150) },
+                AccountBalance { address: acct2.clone(), balance: Uint128::new(600) },
+            ]
+        );
+
+        // emptying an account removes it from the listings
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            Cw20ExecuteMsg::Transfer {
+                to: acct2.clone(),
+                amount: Uint128::new(250),
+            },
+        )
+        .unwrap();
+        let count = query_holder_count(deps.as_ref()).unwrap();
+        assert_eq!(count.count, 2);
+        let accounts = query_all_accounts(deps.as_ref(), None, None).unwrap();
+        assert_eq!(accounts.accounts, vec![acct3.clone(), acct2.clone()]);
+        let top = query_top_holders(deps.as_ref(), None).unwrap();
+        assert_eq!(top.holders[0].balance, Uint128::new(850));
+        assert_eq!(top.holders[1].address, acct3);
+
+        let res: HolderCountResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), Cw20QueryMsg::HolderCount {}).unwrap())
+                .unwrap();
+        assert_eq!(res.count, 2);
+    }
 }
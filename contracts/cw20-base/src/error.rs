@@ -40,6 +40,30 @@ pub enum ContractError {
     #[error("Duplicate initial balance addresses")]
     DuplicateInitialBalanceAddresses {},
 
+    #[error("Hold {hold_id} already exists")]
+    HoldAlreadyExists {hold_id : String},
+
+    #[error("Hold {hold_id} not found")]
+    HoldNotFound {hold_id : String},
+
+    #[error("Hold {hold_id} is expired")]
+    HoldExpired {hold_id : String},
+
+    #[error("Account already has {max} holds")]
+    TooManyHolds {max : u32},
+
+    #[error("Insufficient available balance [Available : {available} / Required : {required}]")]
+    InsufficientAvailableBalance {available : Uint128,required : Uint128},
+
+    #[error("No balance migration is pending")]
+    NoBalanceMigration {},
+
+    #[error("Clawback has been renounced")]
+    ClawbackRenounced {},
+
+    #[error("Clawback reason must not be empty")]
+    EmptyClawbackReason {},
+
     #[error("Max Supply change can not lower than total supply [Max Supply : {max_supply} / Total Supply : {total_supply}")]
     LowerThanTotalSupply {max_supply : Uint128,total_supply : Uint128},
 }
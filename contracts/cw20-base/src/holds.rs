@@ -0,0 +1,483 @@
+use cosmwasm_std::{
+    attr, Addr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage,
+    Uint128,
+};
+use cw20::{AvailableBalanceResponse, Expiration};
+
+use crate::error::ContractError;
+use crate::state::{
+    add_balance, balances, sub_balance, update_stats, HoldData, COOPERATIVES, HOLDS, TOKEN_INFO,
+};
+
+/// Live holds an account can have, every spend from it sums them up
+pub const MAX_HOLDS_PER_ACCOUNT: u32 = 20;
+
+/// Returns sum of all holds on account that are not expired yet.
+pub fn held_amount(
+    storage: &dyn Storage,
+    account: &Addr,
+    block: &BlockInfo,
+) -> StdResult<Uint128> {
+    HOLDS
+        .prefix(account)
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |held, item| {
+            let (_, hold) = item?;
+            if hold.expires.is_expired(block) {
+                Ok(held)
+            } else {
+                Ok(held + hold.amount)
+            }
+        })
+}
+
+/// Errors if taking amount out of account would eat into its held tokens.
+/// When account has less than amount at all, this passes and leaves it to the
+/// balance update to fail with overflow as before.
+pub fn ensure_not_held(
+    storage: &dyn Storage,
+    account: &Addr,
+    block: &BlockInfo,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let balance = balances().may_load(storage, account)?.unwrap_or_default();
+    if amount > balance {
+        return Ok(());
+    }
+    let held = held_amount(storage, account, block)?;
+    if balance - amount < held {
+        return Err(ContractError::InsufficientAvailableBalance {
+            available: balance.saturating_sub(held),
+            required: amount,
+        });
+    }
+    Ok(())
+}
+
+// Expired holds no longer lock anything, drop them so they don't pile up on the account.
+fn remove_expired_holds(
+    storage: &mut dyn Storage,
+    account: &Addr,
+    block: &BlockInfo,
+) -> StdResult<()> {
+    let expired = HOLDS
+        .prefix(account)
+        .range(storage, None, None, Order::Ascending)
+        .filter(|r| r.is_err() || r.as_ref().unwrap().1.expires.is_expired(block))
+        .map(|item| item.map(|(hold_id, _)| hold_id))
+        .collect::<StdResult<Vec<_>>>()?;
+    for hold_id in expired {
+        HOLDS.remove(storage, (account, &hold_id));
+    }
+    Ok(())
+}
+
+/// Drops the holds the balance of account no longer covers, keeping them in hold_id order
+/// while they fit. For balances that went down without ensure_not_held, e.g. a clawback.
+/// Returns the ids of the released holds.
+pub fn release_uncovered_holds(
+    storage: &mut dyn Storage,
+    account: &Addr,
+    block: &BlockInfo,
+) -> StdResult<Vec<String>> {
+    remove_expired_holds(storage, account, block)?;
+    let balance = balances().may_load(storage, account)?.unwrap_or_default();
+    let holds = HOLDS
+        .prefix(account)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut covered = Uint128::zero();
+    let mut released = vec![];
+    for (hold_id, hold) in holds {
+        if covered + hold.amount <= balance {
+            covered += hold.amount;
+        } else {
+            HOLDS.remove(storage, (account, &hold_id));
+            released.push(hold_id);
+        }
+    }
+    Ok(released)
+}
+
+pub fn execute_hold(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account: String,
+    amount: Uint128,
+    hold_id: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let config = TOKEN_INFO.load(deps.storage)?;
+    //Check is token contract owner account
+    if config.owner != info.sender {
+        //If not owner check for cooperative account.
+        COOPERATIVES.may_load(deps.storage,&info.sender)?.ok_or(ContractError::Unauthorized {})?;
+    }
+
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+
+    let account_addr = deps.api.addr_validate(&account)?;
+    remove_expired_holds(deps.storage, &account_addr, &env.block)?;
+    if HOLDS.has(deps.storage, (&account_addr, &hold_id)) {
+        return Err(ContractError::HoldAlreadyExists { hold_id });
+    }
+    let holds = HOLDS
+        .prefix(&account_addr)
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .count();
+    if holds >= MAX_HOLDS_PER_ACCOUNT as usize {
+        return Err(ContractError::TooManyHolds { max: MAX_HOLDS_PER_ACCOUNT });
+    }
+
+    let balance = balances().may_load(deps.storage, &account_addr)?.unwrap_or_default();
+    let available = balance.saturating_sub(held_amount(deps.storage, &account_addr, &env.block)?);
+    if available < amount {
+        return Err(ContractError::InsufficientAvailableBalance {
+            available,
+            required: amount,
+        });
+    }
+
+    let hold = HoldData {
+        holder: info.sender.clone(),
+        amount,
+        expires,
+    };
+    HOLDS.save(deps.storage, (&account_addr, &hold_id), &hold)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "hold"),
+        attr("account", account),
+        attr("hold_id", hold_id),
+        attr("by", info.sender),
+        attr("amount", amount),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_release_hold(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account: String,
+    hold_id: String,
+) -> Result<Response, ContractError> {
+    let account_addr = deps.api.addr_validate(&account)?;
+    let hold = HOLDS
+        .may_load(deps.storage, (&account_addr, &hold_id))?
+        .ok_or_else(|| ContractError::HoldNotFound { hold_id: hold_id.clone() })?;
+
+    // anyone can clean up an expired hold, it doesn't lock anything anymore
+    if !hold.expires.is_expired(&env.block) && hold.holder != info.sender {
+        let config = TOKEN_INFO.load(deps.storage)?;
+        if config.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+
+    HOLDS.remove(deps.storage, (&account_addr, &hold_id));
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "release_hold"),
+        attr("account", account),
+        attr("hold_id", hold_id),
+        attr("by", info.sender),
+        attr("amount", hold.amount),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_settle_hold(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    account: String,
+    hold_id: String,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let account_addr = deps.api.addr_validate(&account)?;
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    let hold = HOLDS
+        .may_load(deps.storage, (&account_addr, &hold_id))?
+        .ok_or_else(|| ContractError::HoldNotFound { hold_id: hold_id.clone() })?;
+
+    if hold.holder != info.sender {
+        let config = TOKEN_INFO.load(deps.storage)?;
+        if config.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+    if hold.expires.is_expired(&env.block) {
+        return Err(ContractError::HoldExpired { hold_id });
+    }
+
+    // release first, the held tokens are exactly what gets paid out
+    HOLDS.remove(deps.storage, (&account_addr, &hold_id));
+
+    sub_balance(deps.storage, &account_addr, hold.amount)?;
+    add_balance(deps.storage, &rcpt_addr, hold.amount)?;
+    update_stats(deps.storage, |stats| stats.transfers += 1)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "settle_hold"),
+        attr("account", account),
+        attr("hold_id", hold_id),
+        attr("to", recipient),
+        attr("by", info.sender),
+        attr("amount", hold.amount),
+    ]);
+    Ok(res)
+}
+
+pub fn query_available_balance(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<AvailableBalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let balance = balances()
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let held = held_amount(deps.storage, &address, &env.block)?;
+    Ok(AvailableBalanceResponse {
+        balance,
+        held,
+        available: balance.saturating_sub(held),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{StdError, Uint128};
+    use cw20::{Cw20ExecuteMsg, HoldInfo};
+
+    use crate::contract::{execute, instantiate, query_balance};
+    use crate::enumerable::query_holds;
+    use crate::msg::InstantiateMsg;
+
+    const GAME: &str = "game";
+
+    fn do_instantiate(mut deps: DepsMut, players: &[(&str, u128)]) {
+        let instantiate_msg = InstantiateMsg {
+            name: "Auto Gen".to_string(),
+            symbol: "AUTO".to_string(),
+            decimals: 3,
+            max_supply: Uint128::from(10000000000u128),
+            marketing: None,
+        };
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate(deps.branch(), env.clone(), info.clone(), instantiate_msg).unwrap();
+        for (player, amount) in players {
+            execute(deps.branch(), env.clone(), info.clone(), Cw20ExecuteMsg::Mint {
+                to: player.to_string(),
+                amount: Uint128::new(*amount),
+            })
+            .unwrap();
+        }
+        execute(deps.branch(), env, info, Cw20ExecuteMsg::SetCooperative {
+            cooperative: GAME.to_string(),
+            can_mint_for: false,
+            can_burn_from: false,
+        })
+        .unwrap();
+    }
+
+    fn hold(account: &str, amount: u128, hold_id: &str, expires: Option<Expiration>) -> Cw20ExecuteMsg {
+        Cw20ExecuteMsg::Hold {
+            account: account.to_string(),
+            amount: Uint128::new(amount),
+            hold_id: hold_id.to_string(),
+            expires,
+        }
+    }
+
+    #[test]
+    fn only_owner_or_cooperative_can_hold() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), &[("alice", 1000)]);
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), hold("alice", 100, "match1", None))
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(deps.as_mut(), mock_env(), mock_info(GAME, &[]), hold("alice", 100, "match1", None)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), hold("alice", 100, "match2", None)).unwrap();
+
+        // same id twice is rejected
+        let err = execute(deps.as_mut(), mock_env(), mock_info(GAME, &[]), hold("alice", 100, "match1", None))
+            .unwrap_err();
+        assert_eq!(err, ContractError::HoldAlreadyExists { hold_id: "match1".to_string() });
+
+        // cannot hold more than what is still available
+        let err = execute(deps.as_mut(), mock_env(), mock_info(GAME, &[]), hold("alice", 801, "match3", None))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientAvailableBalance {
+                available: Uint128::new(800),
+                required: Uint128::new(801),
+            }
+        );
+
+        let available = query_available_balance(deps.as_ref(), mock_env(), "alice".to_string()).unwrap();
+        assert_eq!(
+            available,
+            AvailableBalanceResponse {
+                balance: Uint128::new(1000),
+                held: Uint128::new(200),
+                available: Uint128::new(800),
+            }
+        );
+
+        // every spend sums up the holds, so an account only takes so many
+        for i in 2..MAX_HOLDS_PER_ACCOUNT {
+            execute(deps.as_mut(), mock_env(), mock_info(GAME, &[]), hold("alice", 1, &format!("round{}", i), None))
+                .unwrap();
+        }
+        let err = execute(deps.as_mut(), mock_env(), mock_info(GAME, &[]), hold("alice", 1, "one_more", None))
+            .unwrap_err();
+        assert_eq!(err, ContractError::TooManyHolds { max: MAX_HOLDS_PER_ACCOUNT });
+    }
+
+    #[test]
+    fn held_tokens_cannot_move() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), &[("alice", 1000)]);
+        execute(deps.as_mut(), mock_env(), mock_info(GAME, &[]), hold("alice", 600, "match1", None)).unwrap();
+
+        let alice = mock_info("alice", &[]);
+        let err = execute(deps.as_mut(), mock_env(), alice.clone(), Cw20ExecuteMsg::Transfer {
+            to: "bob".to_string(),
+            amount: Uint128::new(401),
+        })
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientAvailableBalance { .. }));
+
+        let err = execute(deps.as_mut(), mock_env(), alice.clone(), Cw20ExecuteMsg::Burn {
+            amount: Uint128::new(500),
+        })
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientAvailableBalance { .. }));
+
+        // more than the whole balance still fails as overflow
+        let err = execute(deps.as_mut(), mock_env(), alice.clone(), Cw20ExecuteMsg::Transfer {
+            to: "bob".to_string(),
+            amount: Uint128::new(1001),
+        })
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        // the free part moves fine
+        execute(deps.as_mut(), mock_env(), alice, Cw20ExecuteMsg::Transfer {
+            to: "bob".to_string(),
+            amount: Uint128::new(400),
+        })
+        .unwrap();
+        assert_eq!(query_balance(deps.as_ref(), "bob".to_string()).unwrap().balance, Uint128::new(400));
+    }
+
+    #[test]
+    fn release_and_settle() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), &[("alice", 1000), ("bob", 1000)]);
+        let game = mock_info(GAME, &[]);
+        execute(deps.as_mut(), mock_env(), game.clone(), hold("alice", 300, "match1", None)).unwrap();
+        execute(deps.as_mut(), mock_env(), game.clone(), hold("bob", 300, "match1", None)).unwrap();
+
+        // player cannot free their own stake
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), Cw20ExecuteMsg::ReleaseHold {
+            account: "alice".to_string(),
+            hold_id: "match1".to_string(),
+        })
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // bob wins, alice pays out and bob gets his stake back
+        execute(deps.as_mut(), mock_env(), game.clone(), Cw20ExecuteMsg::SettleHold {
+            account: "alice".to_string(),
+            hold_id: "match1".to_string(),
+            to: "bob".to_string(),
+        })
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), game.clone(), Cw20ExecuteMsg::ReleaseHold {
+            account: "bob".to_string(),
+            hold_id: "match1".to_string(),
+        })
+        .unwrap();
+
+        assert_eq!(query_balance(deps.as_ref(), "alice".to_string()).unwrap().balance, Uint128::new(700));
+        assert_eq!(query_balance(deps.as_ref(), "bob".to_string()).unwrap().balance, Uint128::new(1300));
+        assert_eq!(held_amount(&deps.storage, &Addr::unchecked("alice"), &mock_env().block).unwrap(), Uint128::zero());
+        assert_eq!(held_amount(&deps.storage, &Addr::unchecked("bob"), &mock_env().block).unwrap(), Uint128::zero());
+
+        let err = execute(deps.as_mut(), mock_env(), game, Cw20ExecuteMsg::SettleHold {
+            account: "alice".to_string(),
+            hold_id: "match1".to_string(),
+            to: "bob".to_string(),
+        })
+        .unwrap_err();
+        assert_eq!(err, ContractError::HoldNotFound { hold_id: "match1".to_string() });
+    }
+
+    #[test]
+    fn expired_hold_is_released() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut(), &[("alice", 1000)]);
+        let env = mock_env();
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        execute(deps.as_mut(), env.clone(), mock_info(GAME, &[]), hold("alice", 1000, "match1", Some(expires)))
+            .unwrap();
+
+        let holds = query_holds(deps.as_ref(), env.clone(), "alice".to_string(), None, None).unwrap();
+        assert_eq!(
+            holds.holds,
+            vec![HoldInfo {
+                hold_id: "match1".to_string(),
+                holder: GAME.to_string(),
+                amount: Uint128::new(1000),
+                expires,
+            }]
+        );
+
+        let mut later = env;
+        later.block.height += 10;
+
+        // nothing is locked any more and the hold cannot be paid out
+        let holds = query_holds(deps.as_ref(), later.clone(), "alice".to_string(), None, None).unwrap();
+        assert_eq!(holds.holds, vec![]);
+        let err = execute(deps.as_mut(), later.clone(), mock_info(GAME, &[]), Cw20ExecuteMsg::SettleHold {
+            account: "alice".to_string(),
+            hold_id: "match1".to_string(),
+            to: "bob".to_string(),
+        })
+        .unwrap_err();
+        assert_eq!(err, ContractError::HoldExpired { hold_id: "match1".to_string() });
+
+        execute(deps.as_mut(), later.clone(), mock_info("alice", &[]), Cw20ExecuteMsg::Transfer {
+            to: "bob".to_string(),
+            amount: Uint128::new(1000),
+        })
+        .unwrap();
+
+        // anyone may clean the expired entry up
+        execute(deps.as_mut(), later, mock_info("anyone", &[]), Cw20ExecuteMsg::ReleaseHold {
+            account: "alice".to_string(),
+            hold_id: "match1".to_string(),
+        })
+        .unwrap();
+        assert!(!HOLDS.has(&deps.storage, (&Addr::unchecked("alice"), "match1")));
+    }
+}
@@ -9,15 +9,19 @@ Implements:
 - [x] CW20 Base
 - [x] Mintable extension
 - [x] Allowances extension
+- [x] Holds extension
+- [x] Clawback extension
 
 For more information on this contract, please check out the
 [README](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-base/README.md).
 */
 
 pub mod allowances;
+pub mod clawback;
 pub mod contract;
 pub mod enumerable;
 mod error;
+pub mod holds;
 pub mod msg;
 pub mod state;
 
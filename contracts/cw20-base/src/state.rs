@@ -1,8 +1,8 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
-use cw20::{AllowanceResponse, Logo, MarketingInfoResponse};
+use cw20::{AllowanceResponse, Expiration, Logo, MarketingInfoResponse};
 
 #[cw_serde]
 pub struct TokenInfo {
@@ -18,19 +18,142 @@ pub struct TokenSupply {
     pub max_supply: Uint128,
 }
 
+#[cw_serde]
+#[derive(Default)]
+pub struct Stats {
+    pub minted: Uint128,
+    pub burned: Uint128,
+    pub transfers: u64,
+}
+
 #[cw_serde]
 pub struct CooperativeData{
     pub can_mint_for : bool,
     pub can_burn_from : bool,
 }
 
+#[cw_serde]
+pub struct HoldData{
+    pub holder : Addr,
+    pub amount : Uint128,
+    pub expires : Expiration,
+}
+
+#[cw_serde]
+pub struct ClawbackRecord{
+    pub operator : Addr,
+    pub from : Addr,
+    pub to : Addr,
+    pub amount : Uint128,
+    pub reason : String,
+    pub height : u64,
+    pub time : Timestamp,
+}
+
+/// Progress of the balance re-index started by `migrate` and finished by `MigrateBalances`
+#[cw_serde]
+pub struct BalanceMigration{
+    /// Last account re-indexed, the next page starts after it
+    pub cursor : Option<Addr>,
+    /// Non-zero balances seen up to and including the cursor
+    pub holders : u64,
+}
+
 pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
 pub const TOKEN_SUPPLY: Item<TokenSupply> = Item::new("token_supply");
 pub const MARKETING_INFO: Item<MarketingInfoResponse> = Item::new("marketing_info");
 pub const LOGO: Item<Logo> = Item::new("logo");
 pub const COOPERATIVES: Map<&Addr, CooperativeData> = Map::new("cooperatives");
-pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+/// Number of accounts holding a non-zero balance
+pub const HOLDER_COUNT: Item<u64> = Item::new("holder_count");
+/// Set while legacy balances are being re-indexed, HOLDER_COUNT is written once it is done
+pub const BALANCE_MIGRATION: Item<BalanceMigration> = Item::new("balance_migration");
+/// Totals of mints, burns and transfers, change it with `update_stats`
+pub const STATS: Item<Stats> = Item::new("stats");
 pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
 // TODO: After https://github.com/CosmWasm/cw-plus/issues/670 is implemented, replace this with a `MultiIndex` over `ALLOWANCES`
 pub const ALLOWANCES_SPENDER: Map<(&Addr, &Addr), AllowanceResponse> =
     Map::new("allowance_spender");
+/// Last `(owner, spender)` looked at by an unscoped `PruneExpired`, the next one starts after it
+pub const ALLOWANCES_PRUNE_CURSOR: Item<(Addr, Addr)> = Item::new("allowance_prune_cursor");
+/// Last spender looked at by a `PruneExpired` scoped to the owner, `owner -> spender`
+pub const ALLOWANCES_OWNER_PRUNE_CURSORS: Map<&Addr, Addr> = Map::new("allowance_owner_prune_cursors");
+
+/// Locked balances, `(account, hold_id) -> hold`
+pub const HOLDS: Map<(&Addr, &str), HoldData> = Map::new("holds");
+
+/// Set once the owner renounced ForceTransfer
+pub const CLAWBACK_RENOUNCED: Item<bool> = Item::new("clawback_renounced");
+/// Every ForceTransfer ever executed, `id -> record`
+pub const CLAWBACK_LOG: Map<u64, ClawbackRecord> = Map::new("clawback_log");
+
+pub struct BalanceIndexes<'a> {
+    pub amount: MultiIndex<'a, u128, Uint128, Addr>,
+}
+
+impl<'a> IndexList<Uint128> for BalanceIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Uint128>> + '_> {
+        let v: Vec<&dyn Index<Uint128>> = vec![&self.amount];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Account balances, secondary indexed by amount so holders can be listed richest first.
+/// Only non-zero balances are stored, use `add_balance`/`sub_balance` to change them.
+pub fn balances<'a>() -> IndexedMap<'a, &'a Addr, Uint128, BalanceIndexes<'a>> {
+    let indexes = BalanceIndexes {
+        amount: MultiIndex::new(|_pk, balance| balance.u128(), "balance", "balance__amount"),
+    };
+    IndexedMap::new("balance", indexes)
+}
+
+pub fn add_balance(storage: &mut dyn Storage, account: &Addr, amount: Uint128) -> StdResult<Uint128> {
+    let old = balances().may_load(storage, account)?.unwrap_or_default();
+    let new = old + amount;
+    if !new.is_zero() {
+        balances().save(storage, account, &new)?;
+        if old.is_zero() {
+            update_holder_count(storage, account, true)?;
+        }
+    }
+    Ok(new)
+}
+
+pub fn sub_balance(storage: &mut dyn Storage, account: &Addr, amount: Uint128) -> StdResult<Uint128> {
+    let old = balances().may_load(storage, account)?.unwrap_or_default();
+    let new = old.checked_sub(amount)?;
+    if new.is_zero() {
+        if !old.is_zero() {
+            balances().remove(storage, account)?;
+            update_holder_count(storage, account, false)?;
+        }
+    } else {
+        balances().save(storage, account, &new)?;
+    }
+    Ok(new)
+}
+
+fn update_holder_count(storage: &mut dyn Storage, account: &Addr, joined: bool) -> StdResult<()> {
+    let change = |count: u64| if joined { count + 1 } else { count.saturating_sub(1) };
+    match BALANCE_MIGRATION.may_load(storage)? {
+        Some(mut migration) => {
+            // Accounts past the cursor are counted once the migration reaches them
+            if migration.cursor.as_ref().is_some_and(|cursor| account <= cursor) {
+                migration.holders = change(migration.holders);
+                BALANCE_MIGRATION.save(storage, &migration)?;
+            }
+            Ok(())
+        }
+        None => HOLDER_COUNT.update(storage, |count| -> StdResult<_> { Ok(change(count)) }).map(|_| ()),
+    }
+}
+
+/// Applies action to the contract statistics
+pub fn update_stats<A>(storage: &mut dyn Storage, action: A) -> StdResult<()>
+where
+    A: FnOnce(&mut Stats),
+{
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    action(&mut stats);
+    STATS.save(storage, &stats)
+}
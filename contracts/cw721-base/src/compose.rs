@@ -0,0 +1,351 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, CustomMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response,
+    StdResult, Storage, Uint128, WasmMsg,
+};
+use cw1155::{Cw1155BatchReceiveMsg, Cw1155ExecuteMsg, Cw1155ReceiveMsg};
+use cw721::{
+    ChildCollectionsResponse, ChildToken, ChildrenResponse, ComposeReceiveMsg, Cw721BatchReceiveMsg,
+    Cw721ExecuteMsg, Cw721ReceiveMsg,
+};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::query::{DEFAULT_LIMIT, MAX_LIMIT};
+use crate::state::Cw721Contract;
+
+// Children are keyed by the parent token id, so whoever owns the parent controls them
+// and they follow it on every transfer without moving themselves.
+// The from of a receive is only as honest as the collection sending it,
+// so only collections the owner allowed can equip.
+impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    C: CustomMsg,
+    E: CustomMsg,
+    Q: CustomMsg,
+{
+    /// cw1155 Send into this contract, info.sender is the cw1155 collection
+    pub fn receive_cw1155(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        wrapper: Cw1155ReceiveMsg,
+    ) -> Result<Response<C>, ContractError> {
+        let ComposeReceiveMsg::Equip { token_id } = from_binary(&wrapper.msg)?;
+        if wrapper.amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        // minted straight into the contract when from is None
+        let sender = deps.api.addr_validate(&wrapper.from.unwrap_or(wrapper.operator))?;
+        self.check_can_equip(deps.as_ref(), &env, &info.sender, &sender, &token_id)?;
+        self.add_cw1155_child(deps.storage, &token_id, &info.sender, &wrapper.token_id, wrapper.amount)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "equip")
+            .add_attribute("token_id", token_id)
+            .add_attribute("child_contract", info.sender)
+            .add_attribute("child_token_id", wrapper.token_id)
+            .add_attribute("amount", wrapper.amount))
+    }
+
+    /// cw721 Send into this contract, info.sender is the cw721 collection, possibly this one
+    pub fn receive_cw721(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        wrapper: Cw721ReceiveMsg,
+    ) -> Result<Response<C>, ContractError> {
+        let ComposeReceiveMsg::Equip { token_id } = from_binary(&wrapper.msg)?;
+        if info.sender == env.contract.address && wrapper.token_id == token_id {
+            return Err(ContractError::CannotEquip { token_id, reason: "a token cannot hold itself".to_string() });
+        }
+        let sender = deps.api.addr_validate(&wrapper.from)?;
+        self.check_can_equip(deps.as_ref(), &env, &info.sender, &sender, &token_id)?;
+
+        self.children.save(
+            deps.storage,
+            (token_id.as_str(), &info.sender, wrapper.token_id.as_str()),
+            &ChildToken::Cw721 { contract: info.sender.to_string(), token_id: wrapper.token_id.clone() },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "equip")
+            .add_attribute("token_id", token_id)
+            .add_attribute("child_contract", info.sender)
+            .add_attribute("child_token_id", wrapper.token_id))
+    }
+
+    /// cw1155 BatchSend into this contract, every token of the batch goes into the same parent
+    pub fn batch_receive_cw1155(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        wrapper: Cw1155BatchReceiveMsg,
+    ) -> Result<Response<C>, ContractError> {
+        let ComposeReceiveMsg::Equip { token_id } = from_binary(&wrapper.msg)?;
+        if wrapper.batch.is_empty() || wrapper.batch.iter().any(|(_, amount)| amount.is_zero()) {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        let sender = deps.api.addr_validate(&wrapper.from.unwrap_or(wrapper.operator))?;
+        self.check_can_equip(deps.as_ref(), &env, &info.sender, &sender, &token_id)?;
+
+        let mut rsp = Response::new()
+            .add_attribute("action", "equip")
+            .add_attribute("token_id", &token_id)
+            .add_attribute("child_contract", info.sender.to_string());
+        for (index, (child_id, amount)) in wrapper.batch.into_iter().enumerate() {
+            self.add_cw1155_child(deps.storage, &token_id, &info.sender, &child_id, amount)?;
+            rsp = rsp
+                .add_attribute(format!("child_token_id[{}]", index), child_id)
+                .add_attribute(format!("amount[{}]", index), amount);
+        }
+        Ok(rsp)
+    }
+
+    /// cw721 BatchSend into this contract, every token of the batch goes into the same parent
+    pub fn batch_receive_cw721(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        wrapper: Cw721BatchReceiveMsg,
+    ) -> Result<Response<C>, ContractError> {
+        let ComposeReceiveMsg::Equip { token_id } = from_binary(&wrapper.msg)?;
+        if info.sender == env.contract.address && wrapper.token_ids.contains(&token_id) {
+            return Err(ContractError::CannotEquip { token_id, reason: "a token cannot hold itself".to_string() });
+        }
+        let sender = deps.api.addr_validate(&wrapper.from)?;
+        self.check_can_equip(deps.as_ref(), &env, &info.sender, &sender, &token_id)?;
+
+        let mut rsp = Response::new()
+            .add_attribute("action", "equip")
+            .add_attribute("token_id", &token_id)
+            .add_attribute("child_contract", info.sender.to_string());
+        for (index, child_id) in wrapper.token_ids.into_iter().enumerate() {
+            self.children.save(
+                deps.storage,
+                (token_id.as_str(), &info.sender, child_id.as_str()),
+                &ChildToken::Cw721 { contract: info.sender.to_string(), token_id: child_id.clone() },
+            )?;
+            rsp = rsp.add_attribute(format!("child_token_id[{}]", index), child_id);
+        }
+        Ok(rsp)
+    }
+
+    pub fn unequip(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        child: ChildToken,
+        recipient: Option<String>,
+    ) -> Result<Response<C>, ContractError> {
+
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        self.check_not_locked(&token_id, &token)?;
+        self.check_can_send(deps.as_ref(), &env, &info, &token.owner, &token)?;
+        let recipient = deps.api.addr_validate(&recipient.unwrap_or_else(|| info.sender.to_string()))?;
+
+        let (contract, child_id) = match &child {
+            ChildToken::Cw1155 { contract, token_id, .. } | ChildToken::Cw721 { contract, token_id } => {
+                (deps.api.addr_validate(contract)?, token_id.clone())
+            }
+        };
+        let key = (token_id.as_str(), &contract, child_id.as_str());
+        let held = self.children.may_load(deps.storage, key)?.ok_or(ContractError::ChildNotEquipped {})?;
+        let (msg, amount) = match (held, child) {
+            (ChildToken::Cw1155 { contract: held_contract, amount: held, .. }, ChildToken::Cw1155 { amount, .. }) => {
+                if amount.is_zero() {
+                    return Err(ContractError::InvalidZeroAmount {});
+                }
+                let left = held.checked_sub(amount).map_err(|_| ContractError::ChildNotEquipped {})?;
+                if left.is_zero() {
+                    self.children.remove(deps.storage, key);
+                } else {
+                    self.children.save(deps.storage, key, &ChildToken::Cw1155 {
+                        contract: held_contract,
+                        token_id: child_id.clone(),
+                        amount: left,
+                    })?;
+                }
+                let msg = Cw1155ExecuteMsg::Transfer { to: recipient.to_string(), token_id: child_id.clone(), amount };
+                (to_binary(&msg)?, amount.to_string())
+            }
+            (ChildToken::Cw721 { .. }, ChildToken::Cw721 { .. }) => {
+                self.children.remove(deps.storage, key);
+                let msg = Cw721ExecuteMsg::<Empty, Empty>::Transfer { to: recipient.to_string(), token_id: child_id.clone() };
+                (to_binary(&msg)?, "1".to_string())
+            }
+            _ => return Err(ContractError::ChildNotEquipped {}),
+        };
+
+        Ok(Response::new()
+            .add_message(WasmMsg::Execute { contract_addr: contract.to_string(), msg, funds: vec![] })
+            .add_attribute("action", "unequip")
+            .add_attribute("token_id", token_id)
+            .add_attribute("child_contract", contract)
+            .add_attribute("child_token_id", child_id)
+            .add_attribute("amount", amount)
+            .add_attribute("recipient", recipient))
+    }
+
+    pub fn set_child_collection(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        allowed: bool,
+    ) -> Result<Response<C>, ContractError> {
+        if !self.is_contract_owner(deps.as_ref(), &info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        let contract_addr = deps.api.addr_validate(&contract)?;
+        if allowed {
+            self.child_collections.save(deps.storage, &contract_addr, &true)?;
+        } else {
+            self.child_collections.remove(deps.storage, &contract_addr);
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_child_collection")
+            .add_attribute("contract", contract)
+            .add_attribute("allowed", allowed.to_string()))
+    }
+
+    /// Removes the child record only, whatever the collection still holds for this contract stays there
+    pub fn drop_child(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        contract: String,
+        child_token_id: String,
+    ) -> Result<Response<C>, ContractError> {
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        self.check_not_locked(&token_id, &token)?;
+        self.check_can_send(deps.as_ref(), &env, &info, &token.owner, &token)?;
+
+        let contract_addr = deps.api.addr_validate(&contract)?;
+        let key = (token_id.as_str(), &contract_addr, child_token_id.as_str());
+        if !self.children.has(deps.storage, key) {
+            return Err(ContractError::ChildNotEquipped {});
+        }
+        self.children.remove(deps.storage, key);
+
+        Ok(Response::new()
+            .add_attribute("action", "drop_child")
+            .add_attribute("token_id", token_id)
+            .add_attribute("child_contract", contract)
+            .add_attribute("child_token_id", child_token_id))
+    }
+
+    pub fn child_collections(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ChildCollectionsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_addr = start_after.map(Addr::unchecked);
+        let start = start_addr.as_ref().map(Bound::exclusive);
+
+        let collections = self
+            .child_collections
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(String::from))
+            .collect::<StdResult<_>>()?;
+        Ok(ChildCollectionsResponse { collections })
+    }
+
+    pub fn children_of(
+        &self,
+        deps: Deps,
+        token_id: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<ChildrenResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = start_after.map(|(contract, child_id)| (Addr::unchecked(contract), child_id));
+        let start = start_after.as_ref().map(|(contract, child_id)| Bound::exclusive((contract, child_id.as_str())));
+
+        let children = self
+            .children
+            .sub_prefix(&token_id)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, child)| child))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(ChildrenResponse { children })
+    }
+
+    /// Burning a parent would strand its children, they must be unequipped first
+    pub fn check_no_children(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+    ) -> Result<(), ContractError> {
+        let has_children = self
+            .children
+            .sub_prefix(token_id)
+            .keys_raw(storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        if has_children {
+            return Err(ContractError::HasChildren { token_id: token_id.to_string() });
+        }
+        Ok(())
+    }
+
+    fn add_cw1155_child(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+        collection: &Addr,
+        child_id: &str,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        let key = (token_id, collection, child_id);
+        let amount = match self.children.may_load(storage, key)? {
+            Some(ChildToken::Cw1155 { amount: held, .. }) => held + amount,
+            _ => amount,
+        };
+        self.children.save(storage, key, &ChildToken::Cw1155 {
+            contract: collection.to_string(),
+            token_id: child_id.to_string(),
+            amount,
+        })
+    }
+
+    /// collection must be allowed and sender allowed to send the parent. An equipped parent
+    /// is owned by this contract and cannot take children, which also rules out cycles.
+    fn check_can_equip(
+        &self,
+        deps: Deps,
+        env: &Env,
+        collection: &Addr,
+        sender: &Addr,
+        token_id: &str,
+    ) -> Result<(), ContractError> {
+        if *collection != env.contract.address && !self.child_collections.has(deps.storage, collection) {
+            return Err(ContractError::ChildCollectionNotAllowed { contract: collection.to_string() });
+        }
+        let token = self.tokens.load(deps.storage, token_id)?;
+        if token.owner == env.contract.address {
+            return Err(ContractError::CannotEquip {
+                token_id: token_id.to_string(),
+                reason: "it is equipped itself".to_string(),
+            });
+        }
+        let info = MessageInfo { sender: sender.clone(), funds: vec![] };
+        self.check_can_send(deps, env, &info, &token.owner, &token)
+    }
+}
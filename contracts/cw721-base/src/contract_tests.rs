@@ -1,14 +1,17 @@
 #![cfg(test)]
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_binary, to_binary, CosmosMsg, DepsMut, Empty,Uint128, Response, WasmMsg};
+use cosmwasm_std::{coins, Addr, from_binary, from_slice, to_binary, to_vec, BankMsg, Binary, CosmosMsg, DepsMut, Empty, Order, StdResult, Uint128, Response, WasmMsg};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use sha2::{Digest, Sha256};
 
 use cw721::{
-    ContractInfoResponse, Cw721ReceiveMsg, Expiration,
+    ContractInfoResponse, Cw721BatchReceiveMsg, Cw721ReceiveMsg, HistoryAction, MintInfo, MintManyResponse, MintedTokens, Expiration, TransferPolicy,
+    MintVoucher, SaleAsset, SaleConfig, SalePhase, SaleReceiveMsg, ChildToken, ComposeReceiveMsg, ReceiveMsg,
     TokenInfoResponse, OperatorsResponse, OwnerOfResponse,Cw721Query
 };
 
 use crate::{
-    ContractError, Cw721Contract, Extension, InstantiateMsg,
+    ContractError, Cw721Contract, Extension, InstantiateMsg, MigrateMsg, RevealConfig,
 };
 
 pub type ExecuteMsg = cw721::Cw721ExecuteMsg<Extension, Empty>;
@@ -24,6 +27,8 @@ fn setup_contract(deps: DepsMut<'_>) -> Cw721Contract<'static, Extension, Empty,
         name: CONTRACT_NAME.to_string(),
         symbol: SYMBOL.to_string(),
         max_supply : Uint128::from(10000u128),
+        transfer_policy : None,
+        reveal : None,
     };
     let info = mock_info("creator", &[]);
     let res = contract.instantiate(deps, mock_env(), info, msg).unwrap();
@@ -40,6 +45,8 @@ fn proper_instantiation() {
         name: CONTRACT_NAME.to_string(),
         symbol: SYMBOL.to_string(),
         max_supply : Uint128::from(10000u128),
+        transfer_policy : None,
+        reveal : None,
     };
     let info = mock_info("creator", &[]);
     let creator_addr = info.sender.clone();
@@ -57,6 +64,7 @@ fn proper_instantiation() {
             name: CONTRACT_NAME.to_string(),
             symbol: SYMBOL.to_string(),
             owner : creator_addr.to_string(),
+            base_uri : None,
         }
     );
 
@@ -78,6 +86,7 @@ fn minting() {
         token_owner: String::from("medusa"),
         token_uri: Some(token_uri.clone()),
         extension: None,
+        transfer_policy: None,
     };
 
     // random cannot mint
@@ -111,6 +120,7 @@ fn minting() {
         TokenInfoResponse::<Extension> {
             token_uri: Some(token_uri),
             extension: None,
+            lock: None,
         }
     );
 
@@ -137,6 +147,7 @@ fn minting() {
         TokenInfoResponse::<Extension> {
             token_uri: Some(token_uri_2),
             extension: None,
+            lock: None,
         }
     );
 
@@ -167,6 +178,7 @@ fn burning() {
         token_owner: MINTER.to_string(),
         token_uri: Some(token_uri),
         extension: None,
+        transfer_policy: None,
     };
 
     // mint some NFT
@@ -214,6 +226,7 @@ fn transferring_nft() {
         token_owner: String::from("venus"),
         token_uri: Some(token_uri),
         extension: None,
+        transfer_policy: None,
     };
 
     let creator = mock_info("creator", &[]);
@@ -267,6 +280,1426 @@ fn transferring_nft() {
     );
 }
 
+#[test]
+fn force_transfer_and_clawback_log() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let mint_msg = ExecuteMsg::Mint{
+        token_owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+        transfer_policy: None,
+    };
+    let creator = mock_info("creator", &[]);
+    let res = contract.execute(deps.as_mut(), mock_env(), creator.clone(), mint_msg).unwrap();
+    let token_id = res.attributes[3].value.clone(); //Get return token id.
+
+    let force_msg = ExecuteMsg::ForceTransfer {
+        from: String::from("venus"),
+        to: String::from("mars"),
+        token_id: token_id.clone(),
+        reason: String::from("stolen sword"),
+    };
+
+    // only owner can claw back
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), force_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // reason is required
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), creator.clone(), ExecuteMsg::ForceTransfer {
+            from: String::from("venus"),
+            to: String::from("mars"),
+            token_id: token_id.clone(),
+            reason: String::new(),
+        })
+        .unwrap_err();
+    assert_eq!(err, ContractError::EmptyClawbackReason {});
+
+    // from must own the token
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), creator.clone(), ExecuteMsg::ForceTransfer {
+            from: String::from("mars"),
+            to: String::from("venus"),
+            token_id: token_id.clone(),
+            reason: String::from("wrong owner"),
+        })
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotTokenOwner {});
+
+    contract.execute(deps.as_mut(), mock_env(), creator.clone(), force_msg.clone()).unwrap();
+    let owner = contract.owner_of(deps.as_ref(), token_id.clone()).unwrap();
+    assert_eq!(owner.owner, "mars");
+
+    let log = contract.clawback_log(deps.as_ref(), None, None).unwrap();
+    assert!(!log.renounced);
+    assert_eq!(log.entries.len(), 1);
+    assert_eq!(log.entries[0].id, 0);
+    assert_eq!(log.entries[0].operator, "creator");
+    assert_eq!(log.entries[0].from, "venus");
+    assert_eq!(log.entries[0].to, "mars");
+    assert_eq!(log.entries[0].token_id, token_id);
+    assert_eq!(log.entries[0].reason, "stolen sword");
+    assert_eq!(log.entries[0].height, mock_env().block.height);
+
+    // locks and binding do not stop a clawback
+    let res = contract.execute(deps.as_mut(), mock_env(), creator.clone(), ExecuteMsg::Mint {
+        token_owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+        transfer_policy: Some(TransferPolicy::BindOnTransfer),
+    }).unwrap();
+    let bound_id = res.attributes[3].value.clone();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::Transfer {
+        to: String::from("mars"),
+        token_id: bound_id.clone(),
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), creator.clone(), ExecuteMsg::SetCooperative {
+        cooperative: String::from("game"),
+        can_mint_for: false,
+        can_burn_from: false,
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), ExecuteMsg::ApproveAll {
+        spender: String::from("game"),
+        expires: None,
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("game", &[]), ExecuteMsg::Lock {
+        token_id: bound_id.clone(),
+        lock_id: String::from("arena"),
+    }).unwrap();
+    let res = contract.execute(deps.as_mut(), mock_env(), creator.clone(), ExecuteMsg::ForceTransfer {
+        from: String::from("mars"),
+        to: String::from("venus"),
+        token_id: bound_id.clone(),
+        reason: String::from("chargeback"),
+    }).unwrap();
+    assert!(res.attributes.iter().any(|a| a.key == "released_lock_id" && a.value == "arena"));
+    let token = contract.tokens.load(&deps.storage, &bound_id).unwrap();
+    assert_eq!(token.owner, "venus");
+    assert_eq!(token.lock, None);
+    assert!(!token.bound);
+    // the token can move once more, like before the undone transfer
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::Transfer {
+        to: String::from("jupiter"),
+        token_id: bound_id,
+    }).unwrap();
+
+    // once renounced it is gone for good
+    contract
+        .execute(deps.as_mut(), mock_env(), creator.clone(), ExecuteMsg::RenounceClawback {})
+        .unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), creator, ExecuteMsg::ForceTransfer {
+            from: String::from("mars"),
+            to: String::from("venus"),
+            token_id,
+            reason: String::from("give it back"),
+        })
+        .unwrap_err();
+    assert_eq!(err, ContractError::ClawbackRenounced {});
+    let log = contract.clawback_log(deps.as_ref(), Some(0), None).unwrap();
+    assert!(log.renounced);
+    assert_eq!(log.entries.len(), 1);
+    assert_eq!(log.entries[0].reason, "chargeback");
+}
+
+#[test]
+fn transfer_policies() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let creator = mock_info("creator", &[]);
+
+    let mut mint = |policy: Option<TransferPolicy>| -> String {
+        let mint_msg = ExecuteMsg::Mint{
+            token_owner: String::from("venus"),
+            token_uri: None,
+            extension: None,
+            transfer_policy: policy,
+        };
+        let res = contract.execute(deps.as_mut(), mock_env(), creator.clone(), mint_msg).unwrap();
+        res.attributes[3].value.clone()
+    };
+    let free = mint(None);
+    let soulbound = mint(Some(TransferPolicy::Soulbound));
+    let coop_only = mint(Some(TransferPolicy::CooperativeOnly));
+    let bind = mint(Some(TransferPolicy::BindOnTransfer));
+
+    let transfer = |to: &str, token_id: &String| ExecuteMsg::Transfer {
+        to: String::from(to),
+        token_id: token_id.clone(),
+    };
+    let venus = mock_info("venus", &[]);
+
+    // collection policy applies when token has none
+    let res: cw721::TransferPolicyResponse = from_binary(
+        &contract.query(deps.as_ref(), mock_env(), QueryMsg::TransferPolicy { token_id: Some(free.clone()) }).unwrap()
+    ).unwrap();
+    assert_eq!(res.policy, TransferPolicy::Free);
+    contract.execute(deps.as_mut(), mock_env(), venus.clone(), transfer("mars", &free)).unwrap();
+
+    // soulbound never moves, but can still be burned
+    let err = contract.execute(deps.as_mut(), mock_env(), venus.clone(), transfer("mars", &soulbound)).unwrap_err();
+    assert_eq!(err, ContractError::TransferNotAllowed { token_id: soulbound.clone() });
+    contract.execute(deps.as_mut(), mock_env(), venus.clone(), ExecuteMsg::Burn { token_id: soulbound, from_address: None }).unwrap();
+
+    // cooperative only needs a cooperative as sender
+    let err = contract.execute(deps.as_mut(), mock_env(), venus.clone(), transfer("mars", &coop_only)).unwrap_err();
+    assert_eq!(err, ContractError::TransferNotAllowed { token_id: coop_only.clone() });
+    contract.execute(deps.as_mut(), mock_env(), creator.clone(), ExecuteMsg::SetCooperative {
+        cooperative: String::from("market"),
+        can_mint_for: false,
+        can_burn_from: true,
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), venus.clone(), ExecuteMsg::ApproveAll {
+        spender: String::from("market"),
+        expires: None,
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("market", &[]), ExecuteMsg::TransferFrom {
+        from: String::from("venus"),
+        to: String::from("mars"),
+        token_id: coop_only,
+    }).unwrap();
+
+    // bind on transfer moves once, then stays with the receiver
+    contract.execute(deps.as_mut(), mock_env(), venus, transfer("mars", &bind)).unwrap();
+    let policy = contract.query_transfer_policy(deps.as_ref(), Some(bind.clone())).unwrap();
+    assert_eq!(policy.policy, TransferPolicy::BindOnTransfer);
+    assert!(policy.bound);
+    let err = contract.execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), transfer("venus", &bind)).unwrap_err();
+    assert_eq!(err, ContractError::TransferNotAllowed { token_id: bind });
+
+    // messages written before transfer policies still parse
+    let msg: ExecuteMsg = from_slice(br#"{"mint":{"token_owner":"venus","token_uri":null,"extension":null}}"#).unwrap();
+    assert!(matches!(msg, ExecuteMsg::Mint { transfer_policy: None, .. }));
+    let msg: InstantiateMsg = from_slice(br#"{"name":"Magic Power","symbol":"MGK","max_supply":"10000"}"#).unwrap();
+    assert_eq!(msg.transfer_policy, None);
+}
+
+#[test]
+fn renting_to_user() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let mint_msg = ExecuteMsg::Mint{
+        token_owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+        transfer_policy: None,
+    };
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_msg).unwrap();
+    let token_id = res.attributes[3].value.clone(); //Get return token id.
+
+    let expires = Expiration::AtHeight(mock_env().block.height + 100);
+    let set_user = ExecuteMsg::SetUser {
+        token_id: token_id.clone(),
+        user: Some(String::from("cupid")),
+        expires: Some(expires),
+    };
+
+    // random cannot lend somebody else's token
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), set_user.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), set_user).unwrap();
+    let user = contract.user_of(deps.as_ref(), mock_env(), token_id.clone()).unwrap();
+    assert_eq!(user.user, Some(String::from("cupid")));
+    assert_eq!(user.expires, Some(expires));
+    let info = contract.all_token_info(deps.as_ref(), mock_env(), token_id.clone()).unwrap();
+    assert_eq!(info.owner, "venus");
+    assert_eq!(info.user, Some(String::from("cupid")));
+
+    // gone once expired
+    let mut env = mock_env();
+    env.block.height += 100;
+    let user = contract.user_of(deps.as_ref(), env.clone(), token_id.clone()).unwrap();
+    assert_eq!(user.user, None);
+    let info = contract.all_token_info(deps.as_ref(), env, token_id.clone()).unwrap();
+    assert_eq!(info.user, None);
+
+    // and on transfer
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::SetUser {
+        token_id: token_id.clone(),
+        user: Some(String::from("cupid")),
+        expires: None,
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::Transfer {
+        to: String::from("mars"),
+        token_id: token_id.clone(),
+    }).unwrap();
+    let user = contract.user_of(deps.as_ref(), mock_env(), token_id).unwrap();
+    assert_eq!(user.user, None);
+}
+
+#[test]
+fn locking_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let mint_msg = ExecuteMsg::Mint{
+        token_owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+        transfer_policy: None,
+    };
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_msg).unwrap();
+    let token_id = res.attributes[3].value.clone(); //Get return token id.
+
+    let lock_msg = ExecuteMsg::Lock {
+        token_id: token_id.clone(),
+        lock_id: String::from("dungeon-1"),
+    };
+
+    // only cooperatives can lock
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), lock_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let set_cooperative = ExecuteMsg::SetCooperative {
+        cooperative: String::from("game"),
+        can_mint_for: false,
+        can_burn_from: false,
+    };
+    contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), set_cooperative).unwrap();
+    // not without the holder's consent
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("game", &[]), lock_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::ApproveAll {
+        spender: String::from("game"),
+        expires: None,
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("game", &[]), lock_msg.clone()).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("game", &[]), lock_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::TokenLocked { token_id: token_id.clone() });
+
+    let info = contract.token_info(deps.as_ref(), token_id.clone()).unwrap();
+    let lock = info.lock.unwrap();
+    assert_eq!(lock.locker, "game");
+    assert_eq!(lock.lock_id, "dungeon-1");
+    let info = contract.all_token_info(deps.as_ref(), mock_env(), token_id.clone()).unwrap();
+    assert!(info.info.lock.is_some());
+
+    // locked token can not be transferred or burned
+    let err = contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::Transfer {
+        to: String::from("mars"),
+        token_id: token_id.clone(),
+    }).unwrap_err();
+    assert_eq!(err, ContractError::TokenLocked { token_id: token_id.clone() });
+    let err = contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::Burn {
+        token_id: token_id.clone(),
+        from_address: None,
+    }).unwrap_err();
+    assert_eq!(err, ContractError::TokenLocked { token_id: token_id.clone() });
+    let err = contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::BurnBatch {
+        token_ids: vec![token_id.clone()],
+        from_address: None,
+    }).unwrap_err();
+    assert_eq!(err, ContractError::TokenLocked { token_id: token_id.clone() });
+
+    // owner can not unlock before the timeout
+    let emergency_unlock = ExecuteMsg::EmergencyUnlock { token_id: token_id.clone() };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), emergency_unlock.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::LockTimeoutNotReached {});
+
+    // unlock needs the same lock id
+    let err = contract.execute(deps.as_mut(), mock_env(), mock_info("game", &[]), ExecuteMsg::Unlock {
+        token_id: token_id.clone(),
+        lock_id: String::from("dungeon-2"),
+    }).unwrap_err();
+    assert_eq!(err, ContractError::LockIdMismatch { lock_id: String::from("dungeon-2") });
+    contract.execute(deps.as_mut(), mock_env(), mock_info("game", &[]), ExecuteMsg::Unlock {
+        token_id: token_id.clone(),
+        lock_id: String::from("dungeon-1"),
+    }).unwrap();
+    let info = contract.token_info(deps.as_ref(), token_id.clone()).unwrap();
+    assert_eq!(info.lock, None);
+
+    // lock again, owner shortens the timeout and the token owner gets it back
+    contract.execute(deps.as_mut(), mock_env(), mock_info("game", &[]), ExecuteMsg::Lock {
+        token_id: token_id.clone(),
+        lock_id: String::from("dungeon-3"),
+    }).unwrap();
+    let update_timeout = ExecuteMsg::UpdateLockTimeout { lock_timeout: 3600 };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), update_timeout.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), update_timeout).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(3600);
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("mars", &[]), emergency_unlock.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotTokenOwner {});
+    contract.execute(deps.as_mut(), env.clone(), mock_info("venus", &[]), emergency_unlock).unwrap();
+
+    contract.execute(deps.as_mut(), env, mock_info("venus", &[]), ExecuteMsg::Transfer {
+        to: String::from("mars"),
+        token_id: token_id.clone(),
+    }).unwrap();
+    let owner = contract.owner_of(deps.as_ref(), token_id).unwrap();
+    assert_eq!(owner.owner, "mars");
+}
+
+#[test]
+fn tombstones_and_stats() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let mut token_ids = vec![];
+    for owner in ["venus", "venus", "mars"] {
+        let mint_msg = ExecuteMsg::Mint {
+            token_owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+            transfer_policy: None,
+        };
+        let res = contract
+            .execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("creator", &[]),
+                mint_msg,
+            )
+            .unwrap();
+        token_ids.push(res.attributes[3].value.clone()); //Get return token id.
+    }
+    let stats = contract.stats(deps.as_ref()).unwrap();
+    assert_eq!(stats.minted, Uint128::from(3u128));
+    assert_eq!(stats.unique_holders, 2);
+
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mars", &[]),
+            ExecuteMsg::Transfer {
+                to: String::from("venus"),
+                token_id: token_ids[2].clone(),
+            },
+        )
+        .unwrap();
+    let stats = contract.stats(deps.as_ref()).unwrap();
+    assert_eq!(stats.transfers, 1);
+    assert_eq!(stats.unique_holders, 1);
+
+    let mut env = mock_env();
+    env.block.height += 10;
+    contract
+        .execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("venus", &[]),
+            ExecuteMsg::Burn {
+                token_id: token_ids[0].clone(),
+                from_address: None,
+            },
+        )
+        .unwrap();
+    contract
+        .execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::BurnBatch {
+                token_ids: vec![token_ids[1].clone()],
+                from_address: None,
+            },
+        )
+        .unwrap();
+
+    // burned ids are told apart from unknown ones
+    let burned = contract
+        .is_burned(deps.as_ref(), token_ids[0].clone())
+        .unwrap();
+    assert!(burned.burned);
+    let tombstone = burned.tombstone.unwrap();
+    assert_eq!(tombstone.burner, "venus");
+    assert_eq!(tombstone.owner, "venus");
+    assert_eq!(tombstone.height, env.block.height);
+    let burned = contract
+        .is_burned(deps.as_ref(), token_ids[1].clone())
+        .unwrap();
+    assert_eq!(burned.tombstone.unwrap().burner, "creator");
+    let burned = contract
+        .is_burned(deps.as_ref(), token_ids[2].clone())
+        .unwrap();
+    assert!(!burned.burned);
+    let burned = contract
+        .is_burned(deps.as_ref(), String::from("unknown"))
+        .unwrap();
+    assert!(!burned.burned);
+
+    let stats = contract.stats(deps.as_ref()).unwrap();
+    assert_eq!(stats.minted, Uint128::from(3u128));
+    assert_eq!(stats.burned, Uint128::from(2u128));
+    assert_eq!(stats.transfers, 1);
+    assert_eq!(stats.unique_holders, 1);
+}
+
+#[test]
+fn migrating_holders() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let mut token_ids = vec![];
+    for owner in ["venus", "venus", "mars", "jupiter"] {
+        let mint_msg = ExecuteMsg::Mint {
+            token_owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+            transfer_policy: None,
+        };
+        let res = contract
+            .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_msg)
+            .unwrap();
+        token_ids.push(res.attributes[3].value.clone());
+    }
+
+    // pretend the tokens were minted before the holder index existed
+    let holders = &contract.tokens.idx.holders;
+    for owner in ["venus", "mars", "jupiter"] {
+        holders.counts.remove(deps.as_mut().storage, &Addr::unchecked(owner));
+    }
+    holders.holders.remove(deps.as_mut().storage);
+    contract.migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert!(holders.migration.may_load(&deps.storage).unwrap().is_some());
+
+    let msg = ExecuteMsg::MigrateHolders { limit: 2 };
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg.clone()).unwrap();
+    assert_eq!(res.attributes[2].value, "2");
+    assert_eq!(res.attributes[3].value, "false");
+
+    // a counted token moves right away, an uncounted one once the backfill gets to it
+    for (owner, to, token_id) in [("venus", "mars", &token_ids[0]), ("jupiter", "venus", &token_ids[3])] {
+        let transfer = ExecuteMsg::Transfer { to: String::from(to), token_id: token_id.clone() };
+        contract.execute(deps.as_mut(), mock_env(), mock_info(owner, &[]), transfer).unwrap();
+    }
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg.clone()).unwrap();
+    assert_eq!(res.attributes[3].value, "false");
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg.clone()).unwrap();
+    assert_eq!(res.attributes[2].value, "0");
+    assert_eq!(res.attributes[3].value, "true");
+
+    let stats = contract.stats(deps.as_ref()).unwrap();
+    assert_eq!(stats.unique_holders, 2);
+    assert_eq!(stats.transfers, 2);
+    assert_eq!(holders.counts.load(&deps.storage, &Addr::unchecked("venus")).unwrap(), 2);
+    assert_eq!(holders.counts.load(&deps.storage, &Addr::unchecked("mars")).unwrap(), 2);
+    assert!(!holders.counts.has(&deps.storage, &Addr::unchecked("jupiter")));
+
+    let err = contract.execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::NoHolderMigration {});
+    // a contract that already has the index has nothing to backfill
+    contract.migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert!(holders.migration.may_load(&deps.storage).unwrap().is_none());
+}
+
+#[test]
+fn freezing_metadata() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let mut token_ids = vec![];
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            token_owner: String::from("venus"),
+            token_uri: Some(String::from("ipfs://before")),
+            extension: None,
+            transfer_policy: None,
+        };
+        let res = contract
+            .execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("creator", &[]),
+                mint_msg,
+            )
+            .unwrap();
+        token_ids.push(res.attributes[3].value.clone()); //Get return token id.
+    }
+
+    // only the contract owner can freeze
+    let freeze_msg = ExecuteMsg::FreezeMetadata {
+        token_id: Some(token_ids[0].clone()),
+    };
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("venus", &[]),
+            freeze_msg.clone(),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            freeze_msg,
+        )
+        .unwrap();
+    assert_eq!(res.events[0].ty, "metadata_frozen");
+    assert_eq!(res.events[0].attributes[0].value, token_ids[0]);
+
+    let update_msg = ExecuteMsg::UpdateTokenURI {
+        token_id: token_ids[0].clone(),
+        token_uri: Some(String::from("ipfs://after")),
+    };
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            update_msg,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MetadataFrozen {
+            token_id: token_ids[0].clone()
+        }
+    );
+    assert!(contract
+        .query_metadata_frozen(deps.as_ref(), Some(token_ids[0].clone()))
+        .unwrap()
+        .frozen);
+    assert!(!contract
+        .query_metadata_frozen(deps.as_ref(), Some(token_ids[1].clone()))
+        .unwrap()
+        .frozen);
+
+    // the other token can still be updated until the whole collection is frozen
+    let update_msg = ExecuteMsg::UpdateTokenExtension {
+        token_id: token_ids[1].clone(),
+        extension: None,
+    };
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            update_msg.clone(),
+        )
+        .unwrap();
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::FreezeMetadata { token_id: None },
+        )
+        .unwrap();
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            update_msg,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MetadataFrozen {
+            token_id: token_ids[1].clone()
+        }
+    );
+    assert!(contract
+        .query_metadata_frozen(deps.as_ref(), None)
+        .unwrap()
+        .frozen);
+}
+
+#[test]
+fn resolving_base_uri() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let base_msg = ExecuteMsg::UpdateBaseUri {
+        base_uri: Some(String::from("https://cdn.example.com/{id}.json")),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), base_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), base_msg)
+        .unwrap();
+
+    let mut token_ids = vec![];
+    for token_uri in [None, Some(String::from("ipfs://own"))] {
+        let mint_msg = ExecuteMsg::Mint {
+            token_owner: String::from("venus"),
+            token_uri,
+            extension: None,
+            transfer_policy: None,
+        };
+        let res = contract
+            .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_msg)
+            .unwrap();
+        token_ids.push(res.attributes[3].value.clone()); //Get return token id.
+    }
+
+    let info = contract.token_info(deps.as_ref(), token_ids[0].clone()).unwrap();
+    assert_eq!(
+        info.token_uri,
+        Some(format!("https://cdn.example.com/{}.json", token_ids[0]))
+    );
+    // the token's own uri overrides the base
+    let info = contract.token_info(deps.as_ref(), token_ids[1].clone()).unwrap();
+    assert_eq!(info.token_uri, Some(String::from("ipfs://own")));
+
+    // a frozen token keeps the uri it had when frozen
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::FreezeMetadata { token_id: Some(token_ids[0].clone()) },
+        )
+        .unwrap();
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateBaseUri { base_uri: Some(String::from("https://new.example.com/")) },
+        )
+        .unwrap();
+    let info = contract.token_info(deps.as_ref(), token_ids[0].clone()).unwrap();
+    assert_eq!(
+        info.token_uri,
+        Some(format!("https://cdn.example.com/{}.json", token_ids[0]))
+    );
+    let info = contract.contract_info(deps.as_ref()).unwrap();
+    assert_eq!(info.base_uri, Some(String::from("https://new.example.com/")));
+
+    contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::FreezeMetadata { token_id: None },
+        )
+        .unwrap();
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateBaseUri { base_uri: None },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::CollectionMetadataFrozen {});
+}
+
+#[test]
+fn delayed_reveal() {
+    let mut deps = mock_dependencies();
+    let contract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+    let msg = InstantiateMsg {
+        name: CONTRACT_NAME.to_string(),
+        symbol: SYMBOL.to_string(),
+        max_supply : Uint128::from(10000u128),
+        transfer_policy : None,
+        reveal : Some(RevealConfig {
+            placeholder_uri: String::from("ipfs://placeholder.json"),
+            provenance_hash: String::from("c0ffee"),
+        }),
+    };
+    contract
+        .instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg)
+        .unwrap();
+
+    let mint_msg = ExecuteMsg::Mint {
+        token_owner: String::from("venus"),
+        token_uri: Some(String::from("ipfs://own")),
+        extension: None,
+        transfer_policy: None,
+    };
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_msg)
+        .unwrap();
+    let token_id = res.attributes[3].value.clone(); //Get return token id.
+
+    // every token shows the placeholder until the reveal
+    let info = contract.token_info(deps.as_ref(), token_id.clone()).unwrap();
+    assert_eq!(info.token_uri, Some(String::from("ipfs://placeholder.json")));
+    let info = contract.all_token_info(deps.as_ref(), mock_env(), token_id.clone()).unwrap();
+    assert_eq!(info.info.token_uri, Some(String::from("ipfs://placeholder.json")));
+    let provenance = contract.provenance(deps.as_ref()).unwrap();
+    assert_eq!(provenance.provenance_hash, Some(String::from("c0ffee")));
+    assert!(!provenance.revealed);
+
+    // freezing first would leave the placeholder in place forever
+    let freeze_msg = ExecuteMsg::FreezeMetadata { token_id: None };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), freeze_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotRevealed {});
+
+    // the real location stays private until the reveal
+    let base_uri_msg = ExecuteMsg::UpdateBaseUri { base_uri: Some(String::from("ipfs://revealed/{id}.json")) };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), base_uri_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotRevealed {});
+
+    let reveal_msg = ExecuteMsg::Reveal {
+        base_uri: String::from("ipfs://revealed/{id}.json"),
+    };
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), reveal_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), reveal_msg.clone())
+        .unwrap();
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), base_uri_msg)
+        .unwrap();
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), freeze_msg)
+        .unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), reveal_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::AlreadyRevealed {});
+
+    let info = contract.token_info(deps.as_ref(), token_id).unwrap();
+    assert_eq!(info.token_uri, Some(String::from("ipfs://own")));
+    assert!(contract.provenance(deps.as_ref()).unwrap().revealed);
+
+    // collections without a reveal config have nothing to reveal
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let err = contract
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Reveal { base_uri: String::from("ipfs://revealed/") },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::RevealNotConfigured {});
+    assert_eq!(contract.provenance(deps.as_ref()).unwrap().provenance_hash, None);
+}
+
+#[test]
+fn selling_in_phases() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    // whitelist of alice and bob, the proof of each is the other leaf
+    let alice_leaf = Sha256::new().chain_update([0u8]).chain_update(b"alice").finalize();
+    let bob_leaf = Sha256::new().chain_update([0u8]).chain_update(b"bob").finalize();
+    let (first, second) = if alice_leaf <= bob_leaf { (alice_leaf, bob_leaf) } else { (bob_leaf, alice_leaf) };
+    let merkle_root = hex::encode(Sha256::new().chain_update([1u8]).chain_update(first).chain_update(second).finalize());
+
+    let mut env = mock_env();
+    let whitelist_end = env.block.time.plus_seconds(100);
+    let sale = SaleConfig {
+        phases: vec![
+            SalePhase {
+                name: String::from("whitelist"),
+                merkle_root: Some(merkle_root),
+                price: SaleAsset::Native { denom: String::from("ustars"), amount: Uint128::from(10u128) },
+                per_wallet_limit: Some(2),
+                start_time: env.block.time,
+                end_time: Some(whitelist_end),
+            },
+            SalePhase {
+                name: String::from("public"),
+                merkle_root: None,
+                price: SaleAsset::Cw20 { token: String::from("token"), amount: Uint128::from(15u128) },
+                per_wallet_limit: None,
+                start_time: whitelist_end,
+                end_time: None,
+            },
+        ],
+        extension: None,
+    };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), ExecuteMsg::UpdateSale { sale: Some(sale.clone()) })
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), ExecuteMsg::UpdateSale { sale: Some(sale) })
+        .unwrap();
+
+    let buy_msg = |proof: &[u8]| ExecuteMsg::Buy { count: 2, proof: Some(vec![hex::encode(proof)]) };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("carol", &coins(20, "ustars")), buy_msg(&alice_leaf))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotWhitelisted {});
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(10, "ustars")), buy_msg(&bob_leaf))
+        .unwrap_err();
+    assert_eq!(err, ContractError::WrongPayment { expected: String::from("20ustars") });
+    let res = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(20, "ustars")), buy_msg(&bob_leaf))
+        .unwrap();
+    let token_id = res.attributes[4].value.clone();
+    assert_eq!(contract.owner_of(deps.as_ref(), token_id).unwrap().owner, "alice");
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(20, "ustars")), buy_msg(&bob_leaf))
+        .unwrap_err();
+    assert_eq!(err, ContractError::WalletLimitReached { limit: 2 });
+
+    // the public phase takes the cw20 token only
+    env.block.time = whitelist_end;
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("carol", &coins(15, "ustars")), ExecuteMsg::Buy { count: 1, proof: None })
+        .unwrap_err();
+    assert_eq!(err, ContractError::WrongPayment { expected: String::from("15token") });
+    let receive_msg = ExecuteMsg::Receive(ReceiveMsg::Cw20(cw20::Cw20ReceiveMsg {
+        sender: String::from("carol"),
+        amount: Uint128::from(15u128),
+        msg: to_binary(&SaleReceiveMsg::Buy { count: 1, proof: None }).unwrap(),
+    }));
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("fake-token", &[]), receive_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::WrongPayment { expected: String::from("15token") });
+    contract
+        .execute(deps.as_mut(), env.clone(), mock_info("token", &[]), receive_msg)
+        .unwrap();
+
+    let status = contract.sale_status(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(status.active_phase, Some(1));
+    assert_eq!(status.sold, vec![2, 1]);
+    assert_eq!(status.total_supply, Uint128::from(3u128));
+    assert_eq!(
+        status.proceeds,
+        vec![
+            SaleAsset::Native { denom: String::from("ustars"), amount: Uint128::from(20u128) },
+            SaleAsset::Cw20 { token: String::from("token"), amount: Uint128::from(15u128) },
+        ]
+    );
+
+    let res = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), ExecuteMsg::WithdrawProceeds {})
+        .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send { to_address: String::from("creator"), amount: coins(20, "ustars") })
+    );
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("token"),
+            msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer { to: String::from("creator"), amount: Uint128::from(15u128) }).unwrap(),
+            funds: vec![],
+        })
+    );
+    assert!(contract.sale_status(deps.as_ref(), env).unwrap().proceeds.is_empty());
+}
+
+#[test]
+fn redeeming_vouchers() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let env = mock_env();
+
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+    let sign = |voucher: &MintVoucher<Extension>| {
+        let mut message = env.contract.address.as_bytes().to_vec();
+        message.extend(to_vec(voucher).unwrap());
+        let signature: Signature = signing_key.sign(&message);
+        Binary::from(signature.as_ref())
+    };
+    let voucher = |nonce: u64| MintVoucher {
+        signer: String::from("game"),
+        recipient: String::from("player"),
+        token_uri: Some(String::from("ipfs://sword.json")),
+        extension: None,
+        nonce,
+        expires: Expiration::AtHeight(env.block.height + 10),
+    };
+
+    // only minters can sign vouchers
+    let signer_msg = ExecuteMsg::SetVoucherSigner {
+        pubkey: Some(Binary::from(signing_key.verifying_key().to_bytes().as_slice())),
+    };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("game", &[]), signer_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let cooperative_msg = ExecuteMsg::SetCooperative {
+        cooperative: String::from("game"),
+        can_mint_for: true,
+        can_burn_from: false,
+    };
+    contract
+        .execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), cooperative_msg)
+        .unwrap();
+    let bad_key_msg = ExecuteMsg::SetVoucherSigner { pubkey: Some(Binary::from(b"not a key".as_slice())) };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("game", &[]), bad_key_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidVoucherPubkey {});
+    contract
+        .execute(deps.as_mut(), env.clone(), mock_info("game", &[]), signer_msg)
+        .unwrap();
+
+    let redeem_msg = ExecuteMsg::RedeemVoucher { voucher: voucher(1), signature: sign(&voucher(2)) };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("player", &[]), redeem_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidVoucherSignature {});
+
+    let redeem_msg = ExecuteMsg::RedeemVoucher { voucher: voucher(1), signature: sign(&voucher(1)) };
+    let res = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("player", &[]), redeem_msg.clone())
+        .unwrap();
+    let token_id = res.attributes[3].value.clone();
+    assert_eq!(contract.owner_of(deps.as_ref(), token_id).unwrap().owner, "player");
+    assert!(contract.is_voucher_redeemed(deps.as_ref(), String::from("game"), 1).unwrap().redeemed);
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("player", &[]), redeem_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::VoucherRedeemed { nonce: 1 });
+
+    let mut expired = voucher(2);
+    expired.expires = Expiration::AtHeight(env.block.height);
+    let redeem_msg = ExecuteMsg::RedeemVoucher { signature: sign(&expired), voucher: expired };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("player", &[]), redeem_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::VoucherExpired {});
+
+    // signed vouchers stop working with the signer's mint rights
+    contract
+        .execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UnsetCooperative { cooperative: String::from("game") },
+        )
+        .unwrap();
+    let redeem_msg = ExecuteMsg::RedeemVoucher { voucher: voucher(3), signature: sign(&voucher(3)) };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), mock_info("player", &[]), redeem_msg)
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn composing_tokens() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+    let env = mock_env();
+
+    let mut token_ids = vec![];
+    for _ in 0..2 {
+        let mint_msg = ExecuteMsg::Mint {
+            token_owner: String::from("alice"),
+            token_uri: None,
+            extension: None,
+            transfer_policy: None,
+        };
+        let res = contract.execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), mint_msg).unwrap();
+        token_ids.push(res.attributes[3].value.clone());
+    }
+    let (hero, sword) = (token_ids[0].clone(), token_ids[1].clone());
+    let equip = |token_id: &str| to_binary(&ComposeReceiveMsg::Equip { token_id: token_id.to_string() }).unwrap();
+
+    // cw1155 and cw721 payloads both arrive under receive
+    let gems = |from: &str, amount: u128| -> ExecuteMsg {
+        let payload = cw1155::Cw1155ReceiveMsg {
+            operator: from.to_string(),
+            from: Some(from.to_string()),
+            token_id: String::from("gem"),
+            amount: Uint128::from(amount),
+            msg: equip(&hero),
+        };
+        from_binary(&payload.into_binary().unwrap()).unwrap()
+    };
+    // anyone can call receive, so only allowed collections can equip
+    let err = contract.execute(deps.as_mut(), env.clone(), mock_info("items", &[]), gems("alice", 3)).unwrap_err();
+    assert_eq!(err, ContractError::ChildCollectionNotAllowed { contract: String::from("items") });
+    let allow_items = ExecuteMsg::SetChildCollection { contract: String::from("items"), allowed: true };
+    let err = contract.execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), allow_items.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract.execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), allow_items).unwrap();
+    assert_eq!(
+        contract.child_collections(deps.as_ref(), None, None).unwrap().collections,
+        vec![String::from("items")]
+    );
+    let err = contract.execute(deps.as_mut(), env.clone(), mock_info("items", &[]), gems("bob", 3)).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract.execute(deps.as_mut(), env.clone(), mock_info("items", &[]), gems("alice", 3)).unwrap();
+
+    // a token of this collection is equipped with a Send to the contract itself
+    contract.execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), ExecuteMsg::Send {
+        contract: env.contract.address.to_string(),
+        token_id: sword.clone(),
+        msg: equip(&hero),
+    }).unwrap();
+    let payload = Cw721ReceiveMsg {
+        operator: String::from("alice"),
+        from: String::from("alice"),
+        token_id: sword.clone(),
+        msg: equip(&hero),
+    };
+    let receive_msg: ExecuteMsg = from_binary(&payload.into_binary().unwrap()).unwrap();
+    let sender = mock_info(env.contract.address.as_str(), &[]);
+    contract.execute(deps.as_mut(), env.clone(), sender.clone(), receive_msg).unwrap();
+    let children = contract.children_of(deps.as_ref(), hero.clone(), None, None).unwrap().children;
+    assert_eq!(children, vec![
+        ChildToken::Cw1155 { contract: String::from("items"), token_id: String::from("gem"), amount: Uint128::from(3u128) },
+        ChildToken::Cw721 { contract: env.contract.address.to_string(), token_id: sword.clone() },
+    ]);
+
+    // an equipped token cannot take children, so no cycles
+    let payload = Cw721ReceiveMsg {
+        operator: String::from("alice"),
+        from: String::from("alice"),
+        token_id: hero.clone(),
+        msg: equip(&sword),
+    };
+    let err = contract
+        .execute(deps.as_mut(), env.clone(), sender, ExecuteMsg::Receive(ReceiveMsg::Cw721(payload)))
+        .unwrap_err();
+    assert_eq!(err, ContractError::CannotEquip { token_id: sword.clone(), reason: String::from("it is equipped itself") });
+
+    let burn_msg = ExecuteMsg::Burn { token_id: hero.clone(), from_address: None };
+    let err = contract.execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), burn_msg).unwrap_err();
+    assert_eq!(err, ContractError::HasChildren { token_id: hero.clone() });
+
+    // children follow the parent to its new owner
+    contract.execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), ExecuteMsg::Transfer {
+        to: String::from("bob"),
+        token_id: hero.clone(),
+    }).unwrap();
+    let unequip_gems = ExecuteMsg::Unequip {
+        token_id: hero.clone(),
+        child: ChildToken::Cw1155 { contract: String::from("items"), token_id: String::from("gem"), amount: Uint128::from(2u128) },
+        recipient: None,
+    };
+    let err = contract.execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), unequip_gems.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = contract.execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), unequip_gems).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: String::from("items"),
+        msg: to_binary(&cw1155::Cw1155ExecuteMsg::Transfer {
+            to: String::from("bob"),
+            token_id: String::from("gem"),
+            amount: Uint128::from(2u128),
+        }).unwrap(),
+        funds: vec![],
+    }));
+
+    let unequip_sword = ExecuteMsg::Unequip {
+        token_id: hero.clone(),
+        child: ChildToken::Cw721 { contract: env.contract.address.to_string(), token_id: sword.clone() },
+        recipient: Some(String::from("carol")),
+    };
+    let res = contract.execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), unequip_sword.clone()).unwrap();
+    assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::Transfer { to: String::from("carol"), token_id: sword }).unwrap(),
+        funds: vec![],
+    }));
+    let err = contract.execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), unequip_sword).unwrap_err();
+    assert_eq!(err, ContractError::ChildNotEquipped {});
+    let children = contract.children_of(deps.as_ref(), hero.clone(), None, None).unwrap().children;
+    assert_eq!(children, vec![
+        ChildToken::Cw1155 { contract: String::from("items"), token_id: String::from("gem"), amount: Uint128::from(1u128) },
+    ]);
+
+    // a cw1155 batch lands in one parent
+    let payload = cw1155::Cw1155BatchReceiveMsg {
+        operator: String::from("bob"),
+        from: Some(String::from("bob")),
+        batch: vec![(String::from("gem"), Uint128::from(2u128)), (String::from("rune"), Uint128::one())],
+        msg: equip(&hero),
+    };
+    let batch_msg: ExecuteMsg = from_binary(&payload.into_binary().unwrap()).unwrap();
+    contract.execute(deps.as_mut(), env.clone(), mock_info("items", &[]), batch_msg.clone()).unwrap();
+    let children = contract.children_of(deps.as_ref(), hero.clone(), None, None).unwrap().children;
+    assert_eq!(children, vec![
+        ChildToken::Cw1155 { contract: String::from("items"), token_id: String::from("gem"), amount: Uint128::from(3u128) },
+        ChildToken::Cw1155 { contract: String::from("items"), token_id: String::from("rune"), amount: Uint128::one() },
+    ]);
+    let page = contract
+        .children_of(deps.as_ref(), hero.clone(), Some((String::from("items"), String::from("gem"))), Some(1))
+        .unwrap()
+        .children;
+    assert_eq!(page, vec![
+        ChildToken::Cw1155 { contract: String::from("items"), token_id: String::from("rune"), amount: Uint128::one() },
+    ]);
+    contract.execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), ExecuteMsg::SetChildCollection {
+        contract: String::from("items"),
+        allowed: false,
+    }).unwrap();
+    let err = contract.execute(deps.as_mut(), env.clone(), mock_info("items", &[]), batch_msg).unwrap_err();
+    assert_eq!(err, ContractError::ChildCollectionNotAllowed { contract: String::from("items") });
+
+    // children a collection will not give back can be dropped so the parent can burn
+    let drop_rune = ExecuteMsg::DropChild {
+        token_id: hero.clone(),
+        contract: String::from("items"),
+        child_token_id: String::from("rune"),
+    };
+    let err = contract.execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), drop_rune.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = contract.execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), drop_rune).unwrap();
+    assert!(res.messages.is_empty());
+    contract.execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), ExecuteMsg::DropChild {
+        token_id: hero.clone(),
+        contract: String::from("items"),
+        child_token_id: String::from("gem"),
+    }).unwrap();
+    assert!(contract.children_of(deps.as_ref(), hero.clone(), None, None).unwrap().children.is_empty());
+    contract.execute(deps.as_mut(), env, mock_info("bob", &[]), ExecuteMsg::Burn { token_id: hero, from_address: None }).unwrap();
+}
+
+#[test]
+fn token_history() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    // nothing recorded until enabled
+    let mint_msg = ExecuteMsg::Mint{
+        token_owner: String::from("venus"),
+        token_uri: None,
+        extension: None,
+        transfer_policy: None,
+    };
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_msg.clone()).unwrap();
+    let token_id = res.attributes[3].value.clone(); //Get return token id.
+    let history = contract.token_history(deps.as_ref(), token_id, None, None).unwrap();
+    assert!(history.entries.is_empty());
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::UpdateHistoryCap { history_cap: 3 })
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::UpdateHistoryCap { history_cap: 3 })
+        .unwrap();
+
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_msg).unwrap();
+    let token_id = res.attributes[3].value.clone(); //Get return token id.
+    let mut env = mock_env();
+    env.block.height += 1;
+    contract.execute(deps.as_mut(), env.clone(), mock_info("venus", &[]), ExecuteMsg::Transfer {
+        to: String::from("mars"),
+        token_id: token_id.clone(),
+    }).unwrap();
+    contract.execute(deps.as_mut(), env.clone(), mock_info("mars", &[]), ExecuteMsg::Send {
+        contract: String::from("market"),
+        token_id: token_id.clone(),
+        msg: to_binary("list").unwrap(),
+    }).unwrap();
+
+    let history = contract.token_history(deps.as_ref(), token_id.clone(), None, None).unwrap();
+    let summary: Vec<_> = history.entries.iter().map(|e| (e.id, e.actor.as_str(), e.owner.as_str(), e.action.clone())).collect();
+    assert_eq!(summary, vec![
+        (0, "creator", "venus", HistoryAction::Mint),
+        (1, "venus", "mars", HistoryAction::Transfer),
+        (2, "mars", "market", HistoryAction::Send),
+    ]);
+    assert_eq!(history.entries[1].height, env.block.height);
+
+    // the cap drops the oldest entry
+    contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::ForceTransfer {
+        from: String::from("market"),
+        to: String::from("mars"),
+        token_id: token_id.clone(),
+        reason: String::from("listing scam"),
+    }).unwrap();
+    let history = contract.token_history(deps.as_ref(), token_id.clone(), None, None).unwrap();
+    let ids: Vec<_> = history.entries.iter().map(|e| e.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+    assert_eq!(history.entries[2].action, HistoryAction::Force);
+
+    let history = contract.token_history(deps.as_ref(), token_id.clone(), Some(1), Some(1)).unwrap();
+    assert_eq!(history.entries.len(), 1);
+    assert_eq!(history.entries[0].id, 2);
+
+    // a lower cap hides the surplus right away and trims it on the next write
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::UpdateHistoryCap { history_cap: 1 })
+        .unwrap();
+    let history = contract.token_history(deps.as_ref(), token_id.clone(), None, None).unwrap();
+    let ids: Vec<_> = history.entries.iter().map(|e| e.id).collect();
+    assert_eq!(ids, vec![3]);
+    let history = contract.token_history(deps.as_ref(), token_id.clone(), Some(0), None).unwrap();
+    assert_eq!(history.entries.len(), 1);
+    assert!(contract.token_history.has(&deps.storage, (&token_id, 1)));
+    contract.execute(deps.as_mut(), mock_env(), mock_info("mars", &[]), ExecuteMsg::Transfer {
+        to: String::from("venus"),
+        token_id: token_id.clone(),
+    }).unwrap();
+    let stored = contract.token_history.prefix(&token_id).keys(&deps.storage, None, None, Order::Ascending).collect::<StdResult<Vec<_>>>().unwrap();
+    assert_eq!(stored, vec![4]);
+
+    // turning history off drops the rest on the next write
+    contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::UpdateHistoryCap { history_cap: 0 })
+        .unwrap();
+    let history = contract.token_history(deps.as_ref(), token_id.clone(), None, None).unwrap();
+    assert!(history.entries.is_empty());
+    contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::Transfer {
+        to: String::from("mars"),
+        token_id: token_id.clone(),
+    }).unwrap();
+    assert!(contract.token_history.prefix(&token_id).keys(&deps.storage, None, None, Order::Ascending).next().is_none());
+}
+
+#[test]
+fn minting_many() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let mint_info = |owner: &str| MintInfo::<Extension> {
+        owner: String::from(owner),
+        token_uri: None,
+        extension: None,
+    };
+    let mint_many = ExecuteMsg::MintMany {
+        mints: vec![mint_info("venus"), mint_info("mars"), mint_info("venus")],
+        transfer_policy: None,
+    };
+
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("random", &[]), mint_many.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // whole drop must fit in max supply
+    contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::UpdateMaxSupply {
+        max_supply: Uint128::from(2u128),
+    }).unwrap();
+    let err = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_many.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::ExceedMaxSupply {});
+    contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::UpdateMaxSupply {
+        max_supply: Uint128::from(3u128),
+    }).unwrap();
+
+    let res = contract
+        .execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_many)
+        .unwrap();
+    let data: MintManyResponse = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        data.minted,
+        vec![
+            MintedTokens { owner: String::from("venus"), token_ids: vec![String::from("0"), String::from("2")] },
+            MintedTokens { owner: String::from("mars"), token_ids: vec![String::from("1")] },
+        ]
+    );
+    assert_eq!(res.attributes[2].value, "venus");
+    assert_eq!(res.attributes[3].value, "0");
+    assert_eq!(res.attributes[5].value, "1");
+
+    let tokens = contract.tokens(deps.as_ref(), String::from("venus"), None, None).unwrap();
+    assert_eq!(tokens.tokens, vec![String::from("0"), String::from("2")]);
+    let supply = contract.token_supply(deps.as_ref()).unwrap();
+    assert_eq!(Uint128::from(3u128), supply.supply.total_supply);
+}
+
+#[test]
+fn batch_transferring_and_sending() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let mut token_ids = vec![];
+    for owner in ["venus", "venus", "venus", "mars"] {
+        let mint_msg = ExecuteMsg::Mint{
+            token_owner: String::from(owner),
+            token_uri: None,
+            extension: None,
+            transfer_policy: None,
+        };
+        let res = contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), mint_msg).unwrap();
+        token_ids.push(res.attributes[3].value.clone()); //Get return token id.
+    }
+
+    // one foreign token fails the whole batch
+    let err = contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::BatchTransfer {
+        to: String::from("jupiter"),
+        token_ids: vec![token_ids[3].clone(), token_ids[0].clone()],
+    }).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::BatchTransfer {
+        to: String::from("jupiter"),
+        token_ids: vec![token_ids[0].clone(), token_ids[1].clone()],
+    }).unwrap();
+    assert_eq!(
+        res,
+        Response::new()
+            .add_attribute("action", "batch_transfer")
+            .add_attribute("from", "venus")
+            .add_attribute("recipient", "jupiter")
+            .add_attribute("token_id[0]", token_ids[0].clone())
+            .add_attribute("token_id[1]", token_ids[1].clone())
+    );
+    let tokens = contract.tokens(deps.as_ref(), String::from("jupiter"), None, None).unwrap();
+    assert_eq!(tokens.tokens, vec![token_ids[0].clone(), token_ids[1].clone()]);
+
+    // operator moves them on
+    contract.execute(deps.as_mut(), mock_env(), mock_info("jupiter", &[]), ExecuteMsg::ApproveAll {
+        spender: String::from("game"),
+        expires: None,
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::SetCooperative {
+        cooperative: String::from("game"),
+        can_mint_for: false,
+        can_burn_from: true,
+    }).unwrap();
+    contract.execute(deps.as_mut(), mock_env(), mock_info("game", &[]), ExecuteMsg::BatchTransferFrom {
+        from: String::from("jupiter"),
+        to: String::from("venus"),
+        token_ids: vec![token_ids[0].clone(), token_ids[1].clone()],
+    }).unwrap();
+    let tokens = contract.tokens(deps.as_ref(), String::from("venus"), None, None).unwrap();
+    assert_eq!(3, tokens.tokens.len());
+
+    let msg = to_binary("You now have the melting power").unwrap();
+    let res = contract.execute(deps.as_mut(), mock_env(), mock_info("venus", &[]), ExecuteMsg::BatchSend {
+        contract: String::from("another_contract"),
+        token_ids: vec![token_ids[2].clone(), token_ids[1].clone()],
+        msg: msg.clone(),
+    }).unwrap();
+    let payload = Cw721BatchReceiveMsg {
+        operator: String::from("venus"),
+        from: String::from("venus"),
+        token_ids: vec![token_ids[2].clone(), token_ids[1].clone()],
+        msg,
+    };
+    assert_eq!(
+        res,
+        Response::new()
+            .add_message(payload.into_cosmos_msg("another_contract").unwrap())
+            .add_attribute("action", "batch_send")
+            .add_attribute("from", "venus")
+            .add_attribute("recipient", "another_contract")
+            .add_attribute("token_id[0]", token_ids[2].clone())
+            .add_attribute("token_id[1]", token_ids[1].clone())
+    );
+    let owner = contract.owner_of(deps.as_ref(), token_ids[2].clone()).unwrap();
+    assert_eq!(owner.owner, "another_contract");
+}
+
 #[test]
 fn sending_nft() {
     let mut deps = mock_dependencies();
@@ -279,6 +1712,7 @@ fn sending_nft() {
         token_owner: String::from("venus"),
         token_uri: Some(token_uri),
         extension: None,
+        transfer_policy: None,
     };
 
     let creator = mock_info("creator", &[]);
@@ -339,6 +1773,7 @@ fn approving_revoking() {
         token_owner: String::from("demeter"),
         token_uri: Some(token_uri),
         extension: None,
+        transfer_policy: None,
     };
 
     let creator = mock_info("creator", &[]);
@@ -434,6 +1869,7 @@ fn approving_all_revoking_all() {
         token_owner: String::from("demeter"),
         token_uri: Some(token_uri1),
         extension: None,
+        transfer_policy: None,
     };
 
     let creator = mock_info("creator", &[]);
@@ -444,6 +1880,7 @@ fn approving_all_revoking_all() {
         token_owner: String::from("demeter"),
         token_uri: Some(token_uri2),
         extension: None,
+        transfer_policy: None,
     };
 
     let res2 = contract.execute(deps.as_mut(), mock_env(), creator, mint_msg2).unwrap();
@@ -629,6 +2066,109 @@ fn approving_all_revoking_all() {
     assert_eq!(0, res.operators.len());
 }
 
+#[test]
+fn pruning_expired_operators() {
+    let mut deps = mock_dependencies();
+    let contract = setup_contract(deps.as_mut());
+
+    let env = mock_env();
+    let expires = Expiration::AtHeight(env.block.height + 10);
+    for (owner, operator, expires) in [
+        ("demeter", "buddy", Some(expires)),
+        ("demeter", "random", None),
+        ("persephone", "buddy", Some(expires)),
+    ] {
+        let approve_all_msg = ExecuteMsg::ApproveAll {
+            spender: operator.to_string(),
+            expires,
+        };
+        contract
+            .execute(deps.as_mut(), env.clone(), mock_info(owner, &[]), approve_all_msg)
+            .unwrap();
+    }
+
+    let mut late_env = env;
+    late_env.block.height += 10;
+
+    // only demeter's expired operator goes
+    let prune_msg = ExecuteMsg::PruneExpired {
+        owner: Some(String::from("demeter")),
+        limit: 10,
+    };
+    let res = contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("anyone", &[]), prune_msg)
+        .unwrap();
+    assert_eq!(res.attributes[2].value, "1");
+    let res = contract
+        .operators(deps.as_ref(), late_env.clone(), String::from("demeter"), true, None, None)
+        .unwrap();
+    assert_eq!(
+        res.operators,
+        vec![cw721::Approval {
+            spender: String::from("random"),
+            expires: Expiration::Never {},
+        }]
+    );
+
+    // a sweep over everyone resumes where the last call stopped
+    let prune_msg = ExecuteMsg::PruneExpired { owner: None, limit: 1 };
+    let res = contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("anyone", &[]), prune_msg.clone())
+        .unwrap();
+    assert_eq!(res.attributes[2].value, "0");
+    let res = contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("anyone", &[]), prune_msg)
+        .unwrap();
+    assert_eq!(res.attributes[2].value, "1");
+    let res = contract
+        .operators(deps.as_ref(), late_env.clone(), String::from("persephone"), true, None, None)
+        .unwrap();
+    assert_eq!(0, res.operators.len());
+
+    // a sweep of one owner resumes as well, so live operators cannot hide expired ones
+    let soon = Some(Expiration::AtHeight(late_env.block.height + 1));
+    for operator in ["stranger", "tourist"] {
+        let approve_all_msg = ExecuteMsg::ApproveAll { spender: operator.to_string(), expires: soon };
+        contract
+            .execute(deps.as_mut(), late_env.clone(), mock_info("demeter", &[]), approve_all_msg)
+            .unwrap();
+    }
+    late_env.block.height += 1;
+    let prune_msg = ExecuteMsg::PruneExpired { owner: Some(String::from("demeter")), limit: 1 };
+    let res = contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("anyone", &[]), prune_msg.clone())
+        .unwrap();
+    assert_eq!(res.attributes[2].value, "0");
+    let res = contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("anyone", &[]), prune_msg)
+        .unwrap();
+    assert_eq!(res.attributes[2].value, "1");
+
+    // an operator transferring for demeter clears demeter's other expired operators
+    let mint_msg = ExecuteMsg::Mint {
+        token_owner: String::from("demeter"),
+        token_uri: None,
+        extension: None,
+        transfer_policy: None,
+    };
+    let res = contract.execute(deps.as_mut(), late_env.clone(), mock_info("creator", &[]), mint_msg).unwrap();
+    let token_id = res.attributes[3].value.clone();
+    let transfer_msg = ExecuteMsg::Transfer { to: String::from("random"), token_id };
+    contract
+        .execute(deps.as_mut(), late_env.clone(), mock_info("random", &[]), transfer_msg)
+        .unwrap();
+    let res = contract
+        .operators(deps.as_ref(), late_env, String::from("demeter"), true, None, None)
+        .unwrap();
+    assert_eq!(
+        res.operators,
+        vec![cw721::Approval {
+            spender: String::from("random"),
+            expires: Expiration::Never {},
+        }]
+    );
+}
+
 #[test]
 fn query_tokens_by_owner() {
     let mut deps = mock_dependencies();
@@ -643,6 +2183,7 @@ fn query_tokens_by_owner() {
         token_owner: demeter.clone(),
         token_uri: None,
         extension: None,
+        transfer_policy: None,
     };
     let res1 = contract.execute(deps.as_mut(), mock_env(), creator.clone(), mint_msg).unwrap();
     let token_id1 = res1.attributes[3].value.clone(); //Get return token id.
@@ -651,6 +2192,7 @@ fn query_tokens_by_owner() {
         token_owner: ceres.clone(),
         token_uri: None,
         extension: None,
+        transfer_policy: None,
     };
     let res2 = contract.execute(deps.as_mut(), mock_env(), creator.clone(), mint_msg).unwrap();
     let token_id2 = res2.attributes[3].value.clone(); //Get return token id.
@@ -659,6 +2201,7 @@ fn query_tokens_by_owner() {
         token_owner: demeter.clone(),
         token_uri: None,
         extension: None,
+        transfer_policy: None,
     };
     let res3 = contract.execute(deps.as_mut(), mock_env(), creator, mint_msg).unwrap();
     let token_id3 = res3.attributes[3].value.clone(); //Get return token id.
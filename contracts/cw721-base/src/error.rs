@@ -29,4 +29,88 @@ pub enum ContractError {
 
     #[error("Batch dimension mismatch")]
     BatchDimensionMismatch { },
+
+    #[error("Transfer policy does not allow to transfer token id : {token_id}")]
+    TransferNotAllowed { token_id : String },
+
+    #[error("Token is locked, token id : {token_id}")]
+    TokenLocked { token_id : String },
+
+    #[error("Token is not locked, token id : {token_id}")]
+    TokenNotLocked { token_id : String },
+
+    #[error("Lock id does not match : {lock_id}")]
+    LockIdMismatch { lock_id : String },
+
+    #[error("Lock timeout not reached")]
+    LockTimeoutNotReached { },
+
+    #[error("Metadata is frozen, token id : {token_id}")]
+    MetadataFrozen { token_id : String },
+
+    #[error("Collection metadata is frozen")]
+    CollectionMetadataFrozen { },
+
+    #[error("Collection has no delayed reveal")]
+    RevealNotConfigured { },
+
+    #[error("Collection is already revealed")]
+    AlreadyRevealed { },
+
+    #[error("Collection is not revealed yet")]
+    NotRevealed { },
+
+    #[error("Sale phase {name} is invalid : {reason}")]
+    InvalidSalePhase { name : String, reason : String },
+
+    #[error("No sale phase is open")]
+    SaleNotActive { },
+
+    #[error("Count must not be zero")]
+    InvalidZeroAmount { },
+
+    #[error("Address is not whitelisted in this phase")]
+    NotWhitelisted { },
+
+    #[error("Wrong payment, expected {expected}")]
+    WrongPayment { expected : String },
+
+    #[error("Wallet limit of {limit} reached in this phase")]
+    WalletLimitReached { limit : u32 },
+
+    #[error("Signer has no voucher key registered")]
+    VoucherSignerNotSet { },
+
+    #[error("Voucher signature is invalid")]
+    InvalidVoucherSignature { },
+
+    #[error("Voucher signer key is not a secp256k1 public key")]
+    InvalidVoucherPubkey { },
+
+    #[error("Voucher expired")]
+    VoucherExpired { },
+
+    #[error("Voucher already redeemed, nonce : {nonce}")]
+    VoucherRedeemed { nonce : u64 },
+
+    #[error("Cannot equip into {token_id}, {reason}")]
+    CannotEquip { token_id : String, reason : String },
+
+    #[error("Tokens of {contract} cannot be equipped")]
+    ChildCollectionNotAllowed { contract : String },
+
+    #[error("Child is not equipped or not enough of it")]
+    ChildNotEquipped { },
+
+    #[error("Token {token_id} still has equipped children")]
+    HasChildren { token_id : String },
+
+    #[error("Clawback has been renounced")]
+    ClawbackRenounced { },
+
+    #[error("Clawback reason must not be empty")]
+    EmptyClawbackReason { },
+
+    #[error("No holder migration is pending")]
+    NoHolderMigration { },
 }
@@ -1,18 +1,27 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use cosmwasm_std::{Binary,Addr, CustomMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult,Uint128,Attribute,attr};
+use cosmwasm_std::{to_binary,Binary,Addr,BlockInfo, CustomMsg, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,Storage,Uint128,Attribute,attr,Event,to_vec};
+use sha2::{Digest, Sha256};
+use cw_storage_plus::Bound;
 
 use cw2::set_contract_version;
-use cw721::{Cw721Execute,Cw721ExecuteMsg, Cw721ReceiveMsg, Expiration};
+use cw721::{BatchReceiveMsg, Cw721Execute,Cw721ExecuteMsg, Cw721BatchReceiveMsg, Cw721ReceiveMsg, Expiration, HistoryAction, MintInfo, MintManyResponse, MintVoucher, MintedTokens, ReceiveMsg, TransferPolicy};
 
 use crate::error::ContractError;
-use crate::msg::{InstantiateMsg};
-use crate::state::{Cw721Contract, TokenInfo,ContractInfo,CooperativeData};
+use crate::msg::{InstantiateMsg, MigrateMsg};
+use crate::state::{Cw721Contract, TokenInfo,ContractInfo,CooperativeData,ClawbackRecord,HistoryRecord,HolderMigration,TokenUser,TokenLock,BurnRecord,RevealState};
 
 // Version info for migration
-const CONTRACT_NAME: &str = "crates.io:cw721-base";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub(crate) const CONTRACT_NAME: &str = "crates.io:cw721-base";
+pub(crate) const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// max entries a single prune may look at
+const MAX_PRUNE_LIMIT: u32 = 100;
+// operators of the owner checked for expiry each time an operator transfers for it
+const TOUCH_PRUNE_LIMIT: usize = 5;
+// max tokens a single MigrateHolders call may count
+const MAX_MIGRATE_LIMIT: u32 = 100;
 
 impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
 where
@@ -37,6 +46,14 @@ where
         };
         self.contract_info.save(deps.storage, &contract_info)?;
         self.max_supply.save(deps.storage,&msg.max_supply.into())?;
+        self.transfer_policy.save(deps.storage,&msg.transfer_policy.unwrap_or_default())?;
+        if let Some(reveal) = msg.reveal {
+            self.reveal.save(deps.storage, &RevealState {
+                placeholder_uri: reveal.placeholder_uri,
+                provenance_hash: reveal.provenance_hash,
+                revealed: false,
+            })?;
+        }
         Ok(Response::default())
     }
 
@@ -55,6 +72,22 @@ where
 
             Cw721ExecuteMsg::RevokeAll { spender } => self.revoke_all(deps, env, info, spender),
 
+            Cw721ExecuteMsg::SetUser { token_id, user, expires } => self.set_user(deps, env, info, token_id, user, expires),
+
+            Cw721ExecuteMsg::Lock { token_id, lock_id } => self.lock(deps, env, info, token_id, lock_id),
+
+            Cw721ExecuteMsg::Unlock { token_id, lock_id } => self.unlock(deps, info, token_id, lock_id),
+
+            Cw721ExecuteMsg::EmergencyUnlock { token_id } => self.emergency_unlock(deps, env, info, token_id),
+
+            Cw721ExecuteMsg::UpdateLockTimeout { lock_timeout } => self.update_lock_timeout(deps, info, lock_timeout),
+
+            Cw721ExecuteMsg::UpdateHistoryCap { history_cap } => self.update_history_cap(deps, info, history_cap),
+
+            Cw721ExecuteMsg::PruneExpired { owner, limit } => self.prune_expired(deps, env, info, owner, limit),
+
+            Cw721ExecuteMsg::MigrateHolders { limit } => self.migrate_holders(deps, info, limit),
+
             Cw721ExecuteMsg::Transfer {to,token_id} => self.transfer(deps, env, info, to, token_id),
 
             Cw721ExecuteMsg::TransferFrom { from, to,token_id } => self.transfer_from(deps, env, info, from,to,token_id),
@@ -63,9 +96,21 @@ where
 
             Cw721ExecuteMsg::SendFrom {from, contract,token_id, msg} => self.send_from(deps, env, info, from,contract,token_id,msg),
 
+            Cw721ExecuteMsg::BatchTransfer { to, token_ids } => self.batch_transfer(deps, env, info, to, token_ids),
+
+            Cw721ExecuteMsg::BatchTransferFrom { from, to, token_ids } => self.batch_transfer_from(deps, env, info, from, to, token_ids),
+
+            Cw721ExecuteMsg::BatchSend { contract, token_ids, msg } => self.batch_send(deps, env, info, contract, token_ids, msg),
+
             Cw721ExecuteMsg::UpdateTokenURI {token_id , token_uri } => self.update_token_uri(deps, info, token_id,token_uri), 
             
             Cw721ExecuteMsg::UpdateTokenExtension {token_id , extension }=> self.update_token_extension(deps, info, token_id,extension), 
+
+            Cw721ExecuteMsg::UpdateBaseUri { base_uri } => self.update_base_uri(deps, info, base_uri),
+
+            Cw721ExecuteMsg::Reveal { base_uri } => self.reveal(deps, info, base_uri),
+
+            Cw721ExecuteMsg::FreezeMetadata { token_id } => self.freeze_metadata(deps, info, token_id),
             
             Cw721ExecuteMsg::SetCooperative { cooperative , can_mint_for, can_burn_from} => self.set_cooperative(deps,info,cooperative,can_mint_for,can_burn_from),
 
@@ -73,14 +118,49 @@ where
 
             Cw721ExecuteMsg::UpdateMaxSupply { max_supply } => self.update_max_supply(deps,info,max_supply),
             
-            Cw721ExecuteMsg::Mint{token_owner,token_uri,extension} => self.mint(deps, info, token_owner,token_uri,extension),
+            Cw721ExecuteMsg::Mint{token_owner,token_uri,extension,transfer_policy} => self.mint(deps, env, info, token_owner,token_uri,extension,transfer_policy),
 
-            Cw721ExecuteMsg::MintBatch{token_owner,token_uris,extensions} => self.mint_batch(deps,info,token_owner,token_uris,extensions),
+            Cw721ExecuteMsg::MintBatch{token_owner,token_uris,extensions,transfer_policy} => self.mint_batch(deps,env,info,token_owner,token_uris,extensions,transfer_policy),
+
+            Cw721ExecuteMsg::MintMany{mints,transfer_policy} => self.mint_many(deps,env,info,mints,transfer_policy),
 
             Cw721ExecuteMsg::BurnBatch { token_ids ,from_address} => self.burn_batch(deps, env,info,token_ids,from_address),
 
             Cw721ExecuteMsg::Burn { token_id,from_address } => self.burn(deps, env, info, token_id,from_address),
             
+            Cw721ExecuteMsg::ForceTransfer { from, to, token_id, reason } => self.force_transfer(deps, env, info, from, to, token_id, reason),
+
+            Cw721ExecuteMsg::RenounceClawback {} => self.renounce_clawback(deps, info),
+
+            Cw721ExecuteMsg::UpdateSale { sale } => self.update_sale(deps, info, sale),
+
+            Cw721ExecuteMsg::Buy { count, proof } => self.buy(deps, env, info, count, proof),
+
+            Cw721ExecuteMsg::Receive(msg) => match msg {
+                ReceiveMsg::Cw20(msg) => self.receive_cw20(deps, env, info, msg),
+                ReceiveMsg::Cw1155(msg) => self.receive_cw1155(deps, env, info, msg),
+                ReceiveMsg::Cw721(msg) => self.receive_cw721(deps, env, info, msg),
+            },
+
+            Cw721ExecuteMsg::BatchReceive(msg) => match msg {
+                BatchReceiveMsg::Cw1155(msg) => self.batch_receive_cw1155(deps, env, info, msg),
+                BatchReceiveMsg::Cw721(msg) => self.batch_receive_cw721(deps, env, info, msg),
+            },
+
+            Cw721ExecuteMsg::WithdrawProceeds {} => self.withdraw_proceeds(deps, info),
+
+            Cw721ExecuteMsg::SetVoucherSigner { pubkey } => self.set_voucher_signer(deps, info, pubkey),
+
+            Cw721ExecuteMsg::RedeemVoucher { voucher, signature } => self.redeem_voucher(deps, env, voucher, signature),
+
+            Cw721ExecuteMsg::Unequip { token_id, child, recipient } => self.unequip(deps, env, info, token_id, child, recipient),
+
+            Cw721ExecuteMsg::SetChildCollection { contract, allowed } => self.set_child_collection(deps, info, contract, allowed),
+
+            Cw721ExecuteMsg::DropChild { token_id, contract, child_token_id } => {
+                self.drop_child(deps, env, info, token_id, contract, child_token_id)
+            }
+
             Cw721ExecuteMsg::Extension { msg: _ } => Ok(Response::default()),
         }
     }
@@ -107,6 +187,7 @@ where
                 return Err(ContractError::Unauthorized {});
             }
         }
+        self.check_metadata_not_frozen(deps.storage, &token_id)?;
 
         self.tokens.update(deps.storage, &token_id, 
             |old| {
@@ -140,6 +221,7 @@ where
                 return Err(ContractError::Unauthorized {});
             }
         }
+        self.check_metadata_not_frozen(deps.storage, &token_id)?;
 
         self.tokens.update(deps.storage, &token_id, 
             |old| {
@@ -235,6 +317,588 @@ where
         )
     }
 
+    pub fn set_user(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        user: Option<String>,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, ContractError> {
+
+        let mut token = self.tokens.load(deps.storage, &token_id)?;
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) &&
+           !self.check_is_token_owner(&info.sender,&token) &&
+           !self.check_is_token_operator(deps.as_ref(),&env,&info,&token) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // reject expired data as invalid
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        token.user = user
+            .as_ref()
+            .map(|user| -> StdResult<_> {
+                Ok(TokenUser {
+                    user: deps.api.addr_validate(user)?,
+                    expires,
+                })
+            })
+            .transpose()?;
+        self.tokens.save(deps.storage, &token_id, &token)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_user")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id)
+            .add_attribute("user", user.unwrap_or_default())
+        )
+    }
+
+    pub fn mint_many(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        mints: Vec<MintInfo<T>>,
+        transfer_policy: Option<TransferPolicy>,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) &&
+           !self.check_as_cooperative(deps.as_ref(),&info.sender,true,false) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // supply is checked once for the whole drop
+        self.check_max_supply(deps.storage, mints.len() as u128)?;
+        let mut mint_attrs : Vec<Attribute> = vec![];
+        let mut minted : Vec<MintedTokens> = vec![];
+        for (index, mint) in mints.into_iter().enumerate() {
+            let owner = deps.api.addr_validate(&mint.owner)?;
+            let token_id_str = self._mint_token(deps.storage, &env.block, &info.sender, owner, mint.token_uri, mint.extension, transfer_policy.clone())?;
+
+            mint_attrs.push(attr(format!("token_owner[{}]",index),&mint.owner));
+            mint_attrs.push(attr(format!("token_id[{}]",index),&token_id_str));
+            match minted.iter_mut().find(|m| m.owner == mint.owner) {
+                Some(entry) => entry.token_ids.push(token_id_str),
+                None => minted.push(MintedTokens { owner: mint.owner, token_ids: vec![token_id_str] }),
+            }
+        }
+
+        Ok(Response::new()
+            .set_data(to_binary(&MintManyResponse { minted })?)
+            .add_attribute("action", "mint_many")
+            .add_attribute("minter", info.sender)
+            .add_attributes(mint_attrs))
+    }
+
+    pub fn batch_transfer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        token_ids: Vec<String>,
+    ) -> Result<Response<C>, ContractError> {
+        let sender = info.sender.clone();
+        let transfer_attrs = self._transfer_batch(deps, &env, &info, &sender, &recipient, &token_ids, HistoryAction::Transfer)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "batch_transfer")
+            .add_attribute("from", info.sender)
+            .add_attribute("recipient", recipient)
+            .add_attributes(transfer_attrs))
+    }
+
+    pub fn batch_transfer_from(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        from: String,
+        recipient: String,
+        token_ids: Vec<String>,
+    ) -> Result<Response<C>, ContractError> {
+        let from_addr = deps.api.addr_validate(&from)?;
+        let transfer_attrs = self._transfer_batch(deps, &env, &info, &from_addr, &recipient, &token_ids, HistoryAction::Transfer)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "batch_transfer_from")
+            .add_attribute("from", from)
+            .add_attribute("recipient", recipient)
+            .add_attributes(transfer_attrs))
+    }
+
+    pub fn batch_send(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    ) -> Result<Response<C>, ContractError> {
+        let sender = info.sender.clone();
+        let transfer_attrs = self._transfer_batch(deps, &env, &info, &sender, &contract, &token_ids, HistoryAction::Send)?;
+
+        let send = Cw721BatchReceiveMsg {
+            from : info.sender.to_string(),
+            operator: info.sender.to_string(),
+            token_ids,
+            msg,
+        };
+
+        Ok(Response::new()
+            .add_message(send.into_cosmos_msg(contract.clone())?)
+            .add_attribute("action", "batch_send")
+            .add_attribute("from", info.sender)
+            .add_attribute("recipient", contract)
+            .add_attributes(transfer_attrs))
+    }
+
+    pub fn lock(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        lock_id: String,
+    ) -> Result<Response<C>, ContractError> {
+
+        let mut token = self.tokens.load(deps.storage, &token_id)?;
+        // a cooperative needs the holder's consent, given as an operator grant
+        let consented = self.check_as_cooperative(deps.as_ref(),&info.sender,false,false) &&
+            self.check_is_token_operator(deps.as_ref(),&env,&info,&token);
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) && !consented {
+            return Err(ContractError::Unauthorized {});
+        }
+        if token.lock.is_some() {
+            return Err(ContractError::TokenLocked { token_id });
+        }
+        token.lock = Some(TokenLock {
+            locker: info.sender.clone(),
+            lock_id: lock_id.clone(),
+            locked_at: env.block.time,
+        });
+        self.tokens.save(deps.storage, &token_id, &token)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "lock")
+            .add_attribute("by", info.sender)
+            .add_attribute("token_id", token_id)
+            .add_attribute("lock_id", lock_id)
+        )
+    }
+
+    pub fn unlock(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        token_id: String,
+        lock_id: String,
+    ) -> Result<Response<C>, ContractError> {
+
+        let mut token = self.tokens.load(deps.storage, &token_id)?;
+        let lock = token.lock.as_ref().ok_or_else(|| ContractError::TokenNotLocked { token_id: token_id.clone() })?;
+        if lock.locker != info.sender && !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if lock.lock_id != lock_id {
+            return Err(ContractError::LockIdMismatch { lock_id });
+        }
+        token.lock = None;
+        self.tokens.save(deps.storage, &token_id, &token)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "unlock")
+            .add_attribute("by", info.sender)
+            .add_attribute("token_id", token_id)
+            .add_attribute("lock_id", lock_id)
+        )
+    }
+
+    pub fn emergency_unlock(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response<C>, ContractError> {
+
+        let mut token = self.tokens.load(deps.storage, &token_id)?;
+        if !self.check_is_token_owner(&info.sender,&token) {
+            return Err(ContractError::NotTokenOwner {});
+        }
+        let lock = token.lock.take().ok_or_else(|| ContractError::TokenNotLocked { token_id: token_id.clone() })?;
+        let lock_timeout = self.get_lock_timeout(deps.storage)?;
+        if env.block.time < lock.locked_at.plus_seconds(lock_timeout) {
+            return Err(ContractError::LockTimeoutNotReached {});
+        }
+        self.tokens.save(deps.storage, &token_id, &token)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "emergency_unlock")
+            .add_attribute("by", info.sender)
+            .add_attribute("token_id", token_id)
+            .add_attribute("lock_id", lock.lock_id)
+        )
+    }
+
+    pub fn update_history_cap(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        history_cap: u32,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.history_cap.save(deps.storage, &history_cap)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_history_cap")
+            .add_attribute("by", info.sender)
+            .add_attribute("history_cap", history_cap.to_string())
+        )
+    }
+
+    pub fn update_lock_timeout(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        lock_timeout: u64,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.lock_timeout.save(deps.storage, &lock_timeout)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_lock_timeout")
+            .add_attribute("by", info.sender)
+            .add_attribute("lock_timeout", lock_timeout.to_string())
+        )
+    }
+
+    pub fn prune_expired(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: Option<String>,
+        limit: u32,
+    ) -> Result<Response<C>, ContractError> {
+
+        let owner_addr = owner.map(|owner| deps.api.addr_validate(&owner)).transpose()?;
+        let limit = limit.min(MAX_PRUNE_LIMIT) as usize;
+        let pruned = self.prune_expired_spenders(deps.storage, &env.block, owner_addr.as_ref(), limit)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "prune_expired")
+            .add_attribute("by", info.sender)
+            .add_attribute("pruned", pruned.to_string())
+        )
+    }
+
+    /// Storage changes of a code upgrade, the version check is left to the entry point
+    /// since contracts built on this one store their own name.
+    pub fn migrate(&self, deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response<C>, ContractError> {
+        let holders = &self.tokens.idx.holders;
+        let has_tokens = self.tokens.keys(deps.storage, None, None, Order::Ascending).next().is_some();
+        // tokens minted before the holder index are counted by MigrateHolders
+        if has_tokens
+            && holders.holders.may_load(deps.storage)?.is_none()
+            && holders.migration.may_load(deps.storage)?.is_none()
+        {
+            holders.migration.save(deps.storage, &HolderMigration { cursor: None })?;
+        }
+        Ok(Response::default())
+    }
+
+    /// Counts the owners of the next page of tokens, the last page ends the backfill
+    pub fn migrate_holders(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        limit: u32,
+    ) -> Result<Response<C>, ContractError> {
+        let holders = &self.tokens.idx.holders;
+        let mut migration = holders
+            .migration
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoHolderMigration {})?;
+        let limit = limit.clamp(1, MAX_MIGRATE_LIMIT) as usize;
+
+        let start = migration.cursor.as_deref().map(Bound::exclusive);
+        let page = self
+            .tokens
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (_, token) in page.iter() {
+            holders.add(deps.storage, &token.owner)?;
+        }
+
+        let done = page.len() < limit;
+        if done {
+            holders.migration.remove(deps.storage);
+        } else {
+            migration.cursor = page.last().map(|(token_id, _)| token_id.clone());
+            holders.migration.save(deps.storage, &migration)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "migrate_holders")
+            .add_attribute("by", info.sender)
+            .add_attribute("migrated", page.len().to_string())
+            .add_attribute("done", done.to_string()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn force_transfer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        from: String,
+        to: String,
+        token_id: String,
+        reason: String,
+    ) -> Result<Response<C>, ContractError> {
+
+        //Only contract owner can force transfer.
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if self.clawback_renounced.may_load(deps.storage)?.unwrap_or(false) {
+            return Err(ContractError::ClawbackRenounced {});
+        }
+        if reason.trim().is_empty() {
+            return Err(ContractError::EmptyClawbackReason {});
+        }
+
+        let from_addr = deps.api.addr_validate(&from)?;
+        let to_addr = deps.api.addr_validate(&to)?;
+        let mut token = self.tokens.load(deps.storage, &token_id)?;
+        if !self.check_is_token_owner(&from_addr,&token) {
+            return Err(ContractError::NotTokenOwner {});
+        }
+        // approvals, locks and binding are bypassed on purpose, the lock was given by the old
+        // owner and a bound token gets back the transfer the clawback undoes
+        let released = token.lock.take();
+        token.owner = to_addr.clone();
+        token.user = None;
+        token.bound = false;
+        self.tokens.save(deps.storage, &token_id, &token)?;
+        self.count_transfer(deps.storage)?;
+        self.record_history(deps.storage, &env.block, &token_id, &info.sender, &to_addr, HistoryAction::Force)?;
+
+        let id = self.clawback_log
+            .keys(deps.storage, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(0, |last| last + 1);
+        let record = ClawbackRecord {
+            operator: info.sender.clone(),
+            from: from_addr,
+            to: to_addr,
+            token_id: token_id.clone(),
+            reason: reason.clone(),
+            height: env.block.height,
+            time: env.block.time,
+        };
+        self.clawback_log.save(deps.storage, id, &record)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "force_transfer")
+            .add_attribute("by", info.sender)
+            .add_attribute("from", from)
+            .add_attribute("recipient", to)
+            .add_attribute("token_id", token_id)
+            .add_attribute("reason", reason)
+            .add_attribute("clawback_id", id.to_string())
+            .add_attributes(released.map(|lock| ("released_lock_id", lock.lock_id)))
+        )
+    }
+
+    pub fn renounce_clawback(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.clawback_renounced.save(deps.storage, &true)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "renounce_clawback")
+            .add_attribute("by", info.sender)
+        )
+    }
+
+    pub fn update_base_uri(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        base_uri: Option<String>,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if self.metadata_frozen.may_load(deps.storage)?.unwrap_or_default() {
+            return Err(ContractError::CollectionMetadataFrozen {});
+        }
+        // base_uri is public in ContractInfo, setting it early would leak the revealed metadata
+        if self.reveal.may_load(deps.storage)?.is_some_and(|reveal| !reveal.revealed) {
+            return Err(ContractError::NotRevealed {});
+        }
+        match &base_uri {
+            Some(base_uri) => self.base_uri.save(deps.storage, base_uri)?,
+            None => self.base_uri.remove(deps.storage),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "update_base_uri")
+            .add_attribute("by", info.sender)
+            .add_attribute("base_uri", base_uri.unwrap_or_default())
+        )
+    }
+
+    pub fn reveal(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        base_uri: String,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        let mut reveal = self.reveal.may_load(deps.storage)?.ok_or(ContractError::RevealNotConfigured {})?;
+        if reveal.revealed {
+            return Err(ContractError::AlreadyRevealed {});
+        }
+        if self.metadata_frozen.may_load(deps.storage)?.unwrap_or_default() {
+            return Err(ContractError::CollectionMetadataFrozen {});
+        }
+        reveal.revealed = true;
+        self.reveal.save(deps.storage, &reveal)?;
+        self.base_uri.save(deps.storage, &base_uri)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "reveal")
+            .add_attribute("by", info.sender)
+            .add_attribute("base_uri", base_uri)
+            .add_attribute("provenance_hash", reveal.provenance_hash)
+        )
+    }
+
+    pub fn freeze_metadata(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        token_id: Option<String>,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        let event = match &token_id {
+            Some(token_id) => {
+                let mut token = self.tokens.may_load(deps.storage, token_id)?
+                    .ok_or_else(|| ContractError::TokenNotFound { token_id: token_id.clone() })?;
+                // pin the uri, a later base_uri update must not move it
+                if token.token_uri.is_none() {
+                    token.token_uri = self.resolve_token_uri(deps.storage, token_id, None)?;
+                    self.tokens.save(deps.storage, token_id, &token)?;
+                }
+                self.frozen_tokens.save(deps.storage, token_id, &true)?;
+                Event::new("metadata_frozen").add_attribute("token_id", token_id)
+            }
+            None => {
+                // Reveal is blocked once frozen, the collection would keep the placeholder for good
+                if self.reveal.may_load(deps.storage)?.is_some_and(|reveal| !reveal.revealed) {
+                    return Err(ContractError::NotRevealed {});
+                }
+                self.metadata_frozen.save(deps.storage, &true)?;
+                Event::new("metadata_frozen").add_attribute("scope", "collection")
+            }
+        };
+
+        Ok(Response::new()
+            .add_attribute("action", "freeze_metadata")
+            .add_attribute("by", info.sender)
+            .add_attribute("token_id", token_id.unwrap_or_default())
+            .add_event(event)
+        )
+    }
+
+    pub fn set_voucher_signer(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        pubkey: Option<Binary>,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) &&
+           !self.check_as_cooperative(deps.as_ref(),&info.sender,true,false) {
+            return Err(ContractError::Unauthorized {});
+        }
+        match &pubkey {
+            Some(pubkey) if !is_secp256k1_pubkey(pubkey) => return Err(ContractError::InvalidVoucherPubkey {}),
+            Some(pubkey) => self.voucher_signers.save(deps.storage, &info.sender, pubkey)?,
+            None => self.voucher_signers.remove(deps.storage, &info.sender),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_voucher_signer")
+            .add_attribute("signer", info.sender)
+            .add_attribute("pubkey", pubkey.map(|pubkey| pubkey.to_base64()).unwrap_or_default())
+        )
+    }
+
+    /// Mints as if the signer sent Mint, so it needs the signer's mint rights
+    pub fn redeem_voucher(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        voucher: MintVoucher<T>,
+        signature: Binary,
+    ) -> Result<Response<C>, ContractError> {
+
+        let signer = deps.api.addr_validate(&voucher.signer)?;
+        let pubkey = self.voucher_signers.may_load(deps.storage, &signer)?
+            .ok_or(ContractError::VoucherSignerNotSet {})?;
+        if voucher.expires.is_expired(&env.block) {
+            return Err(ContractError::VoucherExpired {});
+        }
+        if self.redeemed_vouchers.has(deps.storage, (&signer, voucher.nonce)) {
+            return Err(ContractError::VoucherRedeemed { nonce: voucher.nonce });
+        }
+
+        let digest = Sha256::new()
+            .chain_update(env.contract.address.as_bytes())
+            .chain_update(to_vec(&voucher)?)
+            .finalize();
+        if !deps.api.secp256k1_verify(&digest, &signature, &pubkey).map_err(StdError::from)? {
+            return Err(ContractError::InvalidVoucherSignature {});
+        }
+        self.redeemed_vouchers.save(deps.storage, (&signer, voucher.nonce), &true)?;
+
+        let info = MessageInfo { sender: signer, funds: vec![] };
+        let response = self.mint(deps, env, info, voucher.recipient, voucher.token_uri, voucher.extension, None)?;
+        Ok(response.add_attribute("voucher_nonce", voucher.nonce.to_string()))
+    }
+
     pub fn change_owner(
         &self,
         deps: DepsMut,
@@ -276,7 +940,7 @@ where
         recipient: String,
         token_id: String,
     ) -> Result<Response<C>, ContractError> {
-        self._transfer(deps, &env,&info, &info.sender, &recipient, &token_id)?;
+        self._transfer(deps, &env,&info, &info.sender, &recipient, &token_id, HistoryAction::Transfer)?;
 
         Ok(Response::new()
             .add_attribute("action", "transfer")
@@ -295,7 +959,7 @@ where
         msg: Binary,
     ) -> Result<Response<C>, ContractError> {
         // Transfer token
-        self._transfer(deps, &env,&info, &info.sender, &contract, &token_id)?;
+        self._transfer(deps, &env,&info, &info.sender, &contract, &token_id, HistoryAction::Send)?;
 
         let send = Cw721ReceiveMsg {
             from : info.sender.to_string(),
@@ -324,7 +988,7 @@ where
     ) -> Result<Response<C>, ContractError> {
 
         let from_addr = deps.api.addr_validate(&from)?;
-        self._transfer(deps, &env,&info, &from_addr, &recipient, &token_id)?;
+        self._transfer(deps, &env,&info, &from_addr, &recipient, &token_id, HistoryAction::Transfer)?;
 
         Ok(Response::new()
             .add_attribute("action", "transfer_from")
@@ -345,7 +1009,7 @@ where
     ) -> Result<Response<C>, ContractError> {
         // Transfer token
         let from_addr = deps.api.addr_validate(&from)?;
-        self._transfer(deps, &env,&info, &from_addr, &contract, &token_id)?;
+        self._transfer(deps, &env,&info, &from_addr, &contract, &token_id, HistoryAction::Send)?;
 
         let send = Cw721ReceiveMsg {
             from : from.clone(),
@@ -412,9 +1076,11 @@ where
         from_address : Option<String>,
     ) -> Result<Response<C>, ContractError> {
 
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        self.check_not_locked(&token_id, &token)?;
+        self.check_no_children(deps.storage, &token_id)?;
         if  !self.is_contract_owner(deps.as_ref(),&info.sender) {
             
-            let token = self.tokens.load(deps.storage, &token_id)?;
             if !self.check_is_token_owner_ifneed(deps.as_ref(),&from_address,&token) {
                 return Err(ContractError::Unauthorized{});
             }
@@ -429,6 +1095,7 @@ where
         }
 
         self.tokens.remove(deps.storage, &token_id)?;
+        self.save_tombstone(deps.storage, &env.block, &info.sender, &token_id, &token)?;
         
         let new_token_supply = self.get_total_supply(deps.storage)?.checked_sub(1).unwrap();
         self.total_supply.save(deps.storage, &new_token_supply)?;
@@ -485,7 +1152,11 @@ where
             if index >= amount_to_burn {
                 break;
             }
+            let token = self.tokens.load(deps.storage, &token_ids[index])?;
+            self.check_not_locked(&token_ids[index], &token)?;
+            self.check_no_children(deps.storage, &token_ids[index])?;
             self.tokens.remove(deps.storage, &token_ids[index])?;
+            self.save_tombstone(deps.storage, &env.block, &info.sender, &token_ids[index], &token)?;
             burnt_attrs.push(attr(format!("token_id[{}]",index),&token_ids[index]));
             total_supply = total_supply - 1;
             index = index + 1;
@@ -501,10 +1172,12 @@ where
     fn mint(
         &self,
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         to : String,
         token_uri: Option<String>,
         extension: T,
+        transfer_policy: Option<TransferPolicy>,
     ) -> Result<Response<C>, ContractError> {
 
         if !self.is_contract_owner(deps.as_ref(),&info.sender) {
@@ -513,31 +1186,9 @@ where
             }
         }
 
-        let total_supply = self.get_total_supply(deps.storage)?;
-        let max_supply = self.get_max_supply(deps.storage)?;
-
-        if total_supply >= max_supply{
-            return Err(ContractError::ExceedMaxSupply{});
-        }
-
-        // create the token
-        let token = TokenInfo {
-            owner: deps.api.addr_validate(&to)?,
-            token_uri: token_uri,
-            extension: extension,
-        };
-
-        let token_id = self.get_last_running_id(deps.storage)?;
-        let token_id_str: String = token_id.to_string();
-
-        //Token ID is generate and alway uniqued so just save instead updated.
-        self.tokens.save(deps.storage, &token_id_str,&token)?;
-
-        let new_total_supply = total_supply + 1;
-        self.total_supply.save(deps.storage, &new_total_supply)?;
-        
-        let next_token_id = token_id + 1;
-        self.token_running_id.save(deps.storage, &next_token_id)?;
+        self.check_max_supply(deps.storage, 1)?;
+        let owner = deps.api.addr_validate(&to)?;
+        let token_id_str = self._mint_token(deps.storage, &env.block, &info.sender, owner, token_uri, extension, transfer_policy)?;
 
         Ok(Response::new()
             .add_attribute("action", "mint")
@@ -549,10 +1200,12 @@ where
     fn mint_batch(
         &self,
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         to : String,
         token_uris: Vec<Option<String>>,
         extensions: Vec<T>,
+        transfer_policy: Option<TransferPolicy>,
     ) -> Result<Response<C>, ContractError> {
 
         if !self.is_contract_owner(deps.as_ref(),&info.sender) {
@@ -565,37 +1218,13 @@ where
             return Err(ContractError::BatchDimensionMismatch {}); 
         }
 
-        let amount_to_mint = token_uris.len();
-        let total_supply = self.get_total_supply(deps.storage)?;
-        let max_supply = self.get_max_supply(deps.storage)?;
-
-        let new_total_supply = total_supply + (amount_to_mint as u128);
-        if new_total_supply >= max_supply {
-            return Err(ContractError::ExceedMaxSupply{});
-        }
-
-        let mut index = 0;
+        self.check_max_supply(deps.storage, token_uris.len() as u128)?;
+        let owner = deps.api.addr_validate(&to)?;
         let mut mint_attrs : Vec<Attribute> = vec![];
-        let mut token_id = self.get_last_running_id(deps.storage)?;
-        loop {
-
-            if index >= amount_to_mint {break;}
-            let token_id_str : String = token_id.to_string();
-            // create the token
-            let token = TokenInfo {
-                owner: deps.api.addr_validate(&to)?,
-                token_uri: token_uris[index].clone(),
-                extension: extensions[index].clone(),
-            };
-            self.tokens.save(deps.storage, &token_id_str,&token)?;
-
+        for (index, (token_uri, extension)) in token_uris.into_iter().zip(extensions).enumerate() {
+            let token_id_str = self._mint_token(deps.storage, &env.block, &info.sender, owner.clone(), token_uri, extension, transfer_policy.clone())?;
             mint_attrs.push(attr(format!("token_id[{}]",index),token_id_str));
- 
-            token_id = token_id + 1;
-            index = index + 1;
-       }
-        self.total_supply.save(deps.storage, &new_total_supply)?;
-        self.token_running_id.save(deps.storage, &token_id)?;
+        }
 
         Ok(Response::new()
             .add_attribute("action", "mint")
@@ -613,6 +1242,7 @@ where
     E: CustomMsg,
     Q: CustomMsg,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn _transfer(
         &self,
         deps: DepsMut,
@@ -621,18 +1251,265 @@ where
         from : &Addr,
         recipient: &str,
         token_id: &str,
+        action: HistoryAction,
     ) -> Result<TokenInfo<T>, ContractError> {
         
         let mut token = self.tokens.load(deps.storage, token_id)?;
         // ensure we have permissions
         self.check_can_send(deps.as_ref(), env,info, from, &token)?;
-        // set owner and remove existing approvals
+        // an expired operator fails the check and reverts any delete, so clean up on success
+        if info.sender != token.owner {
+            self.prune_expired_spenders(deps.storage, &env.block, Some(&token.owner), TOUCH_PRUNE_LIMIT)?;
+        }
+        self.check_not_locked(token_id, &token)?;
+        self.check_transfer_policy(deps.as_ref(), info, token_id, &mut token)?;
+        // set owner and remove existing approvals and user
         token.owner = deps.api.addr_validate(recipient)?;
+        token.user = None;
         //token.approvals = vec![];
         self.tokens.save(deps.storage, token_id, &token)?;
+        self.count_transfer(deps.storage)?;
+        self.record_history(deps.storage, &env.block, token_id, &info.sender, &token.owner, action)?;
         Ok(token)
     }
 
+    /// Adds one to the transfers of StatsResponse
+    pub fn count_transfer(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        let transfers = self.transfer_count.may_load(storage)?.unwrap_or_default();
+        self.transfer_count.save(storage, &(transfers + 1))
+    }
+
+    /// Fails when minting count more tokens would go over max supply
+    pub fn check_max_supply(&self, storage: &dyn Storage, count: u128) -> Result<(), ContractError> {
+        if self.get_total_supply(storage)? + count > self.get_max_supply(storage)? {
+            return Err(ContractError::ExceedMaxSupply{});
+        }
+        Ok(())
+    }
+
+    /// Saves a new token under the next running id, records its mint and counts it in supply.
+    /// Every mint goes through here, callers check the whole mint with check_max_supply first.
+    #[allow(clippy::too_many_arguments)]
+    pub fn _mint_token(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        minter: &Addr,
+        owner: Addr,
+        token_uri: Option<String>,
+        extension: T,
+        transfer_policy: Option<TransferPolicy>,
+    ) -> Result<String, ContractError> {
+        let token = TokenInfo {
+            owner,
+            token_uri,
+            extension,
+            transfer_policy,
+            bound: false,
+            user: None,
+            lock: None,
+        };
+        let token_id = self.get_last_running_id(storage)?;
+        let token_id_str = token_id.to_string();
+
+        //Token ID is generate and alway uniqued so just save instead updated.
+        self.tokens.save(storage, &token_id_str, &token)?;
+        self.record_history(storage, block, &token_id_str, minter, &token.owner, HistoryAction::Mint)?;
+
+        let total_supply = self.get_total_supply(storage)?;
+        self.total_supply.save(storage, &(total_supply + 1))?;
+        self.token_running_id.save(storage, &(token_id + 1))?;
+        Ok(token_id_str)
+    }
+
+    /// Appends to the token history when enabled, dropping the oldest entries above the cap.
+    /// Entries left over from a larger cap are trimmed here, on the next write to the token.
+    pub fn record_history(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+        actor: &Addr,
+        owner: &Addr,
+        action: HistoryAction,
+    ) -> StdResult<()> {
+        let history_cap = self.history_cap.may_load(storage)?.unwrap_or_default() as u64;
+        let history = self.token_history.prefix(token_id);
+        let first = history.keys(storage, None, None, Order::Ascending).next().transpose()?;
+        if history_cap == 0 {
+            // recording was turned off, drop what the token still has
+            if first.is_some() {
+                let ids = history.keys(storage, None, None, Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+                for id in ids {
+                    self.token_history.remove(storage, (token_id, id));
+                }
+            }
+            return Ok(());
+        }
+
+        let last = history.keys(storage, None, None, Order::Descending).next().transpose()?;
+        let id = last.map_or(0, |last| last + 1);
+        let record = HistoryRecord {
+            actor: actor.clone(),
+            owner: owner.clone(),
+            action,
+            height: block.height,
+            time: block.time,
+        };
+        self.token_history.save(storage, (token_id, id), &record)?;
+
+        // ids are contiguous, only the oldest ones ever get removed
+        let mut oldest = first.unwrap_or(id);
+        while id + 1 - oldest > history_cap {
+            self.token_history.remove(storage, (token_id, oldest));
+            oldest += 1;
+        }
+        Ok(())
+    }
+
+    pub fn save_tombstone(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        burner: &Addr,
+        token_id: &str,
+        token: &TokenInfo<T>,
+    ) -> StdResult<()> {
+        let record = BurnRecord {
+            burner: burner.clone(),
+            owner: token.owner.clone(),
+            height: block.height,
+            time: block.time,
+        };
+        self.burned.save(storage, token_id, &record)
+    }
+
+    /// Runs _transfer for every token, the first failure fails the whole batch.
+    /// Returns the `token_id[i]` attributes.
+    #[allow(clippy::too_many_arguments)]
+    pub fn _transfer_batch(
+        &self,
+        mut deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        from : &Addr,
+        recipient: &str,
+        token_ids: &[String],
+        action: HistoryAction,
+    ) -> Result<Vec<Attribute>, ContractError> {
+        let mut transfer_attrs : Vec<Attribute> = vec![];
+        for (index, token_id) in token_ids.iter().enumerate() {
+            self._transfer(deps.branch(), env, info, from, recipient, token_id, action.clone())?;
+            transfer_attrs.push(attr(format!("token_id[{}]",index), token_id));
+        }
+        Ok(transfer_attrs)
+    }
+
+    /// Removes operators whose ApproveAll expired, at most limit entries per call.
+    /// Both the per granter and the collection wide sweep resume after the last operator
+    /// they saw, starting over once they reach the end.
+    pub fn prune_expired_spenders(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        owner: Option<&Addr>,
+        limit: usize,
+    ) -> StdResult<u32> {
+        let scanned: Vec<((Addr, Addr), Expiration)> = match owner {
+            Some(owner) => {
+                let cursor = self.spenders_owner_prune_cursors.may_load(storage, owner)?;
+                let start = cursor.as_ref().map(Bound::exclusive);
+                let scanned = self.spenders
+                    .prefix(owner)
+                    .range(storage, start, None, Order::Ascending)
+                    .take(limit)
+                    .map(|item| item.map(|(spender, expires)| ((owner.clone(), spender), expires)))
+                    .collect::<StdResult<Vec<_>>>()?;
+                match scanned.last() {
+                    Some(((_, last), _)) if scanned.len() == limit => {
+                        self.spenders_owner_prune_cursors.save(storage, owner, last)?
+                    }
+                    _ => self.spenders_owner_prune_cursors.remove(storage, owner),
+                }
+                scanned
+            }
+            None => {
+                let cursor = self.spenders_prune_cursor.may_load(storage)?;
+                let start = cursor.as_ref().map(|(owner, spender)| Bound::exclusive((owner, spender)));
+                let scanned = self.spenders
+                    .range(storage, start, None, Order::Ascending)
+                    .take(limit)
+                    .collect::<StdResult<Vec<_>>>()?;
+                match scanned.last() {
+                    Some((last, _)) if scanned.len() == limit => {
+                        self.spenders_prune_cursor.save(storage, last)?
+                    }
+                    _ => self.spenders_prune_cursor.remove(storage),
+                }
+                scanned
+            }
+        };
+
+        let mut pruned = 0u32;
+        for ((owner, spender), expires) in scanned {
+            if expires.is_expired(block) {
+                self.spenders.remove(storage, (&owner, &spender));
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Fails when the token policy forbids this transfer, marks BindOnTransfer tokens as bound.
+    pub fn check_transfer_policy(
+        &self,
+        deps: Deps,
+        info: &MessageInfo,
+        token_id: &str,
+        token: &mut TokenInfo<T>,
+    ) -> Result<(), ContractError> {
+        match self.get_transfer_policy(deps.storage, token)? {
+            TransferPolicy::Free => {},
+            TransferPolicy::Soulbound => {
+                return Err(ContractError::TransferNotAllowed { token_id: token_id.to_string() });
+            },
+            TransferPolicy::CooperativeOnly => {
+                if !self.is_contract_owner(deps,&info.sender) && !self.check_as_cooperative(deps,&info.sender,false,false) {
+                    return Err(ContractError::TransferNotAllowed { token_id: token_id.to_string() });
+                }
+            },
+            TransferPolicy::BindOnTransfer => {
+                if token.bound {
+                    return Err(ContractError::TransferNotAllowed { token_id: token_id.to_string() });
+                }
+                token.bound = true;
+            },
+        }
+        Ok(())
+    }
+
+    pub fn check_not_locked(
+        &self,
+        token_id: &str,
+        token: &TokenInfo<T>,
+    ) -> Result<(), ContractError> {
+        match token.lock {
+            Some(_) => Err(ContractError::TokenLocked { token_id: token_id.to_string() }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn check_metadata_not_frozen(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+    ) -> Result<(), ContractError> {
+        if self.is_metadata_frozen(storage, token_id)? {
+            return Err(ContractError::MetadataFrozen { token_id: token_id.to_string() });
+        }
+        Ok(())
+    }
+
     /// returns true iff the sender can execute approve or reject on the contract
     pub fn check_is_token_owner(
         &self,
@@ -756,3 +1633,8 @@ where
         
     }
 }
+
+/// 33 byte compressed or 65 byte uncompressed SEC1 encoding, as secp256k1_verify takes it
+fn is_secp256k1_pubkey(pubkey: &[u8]) -> bool {
+    matches!((pubkey.len(), pubkey.first()), (33, Some(0x02 | 0x03)) | (65, Some(0x04)))
+}
@@ -4,10 +4,12 @@ mod error;
 pub mod msg;
 mod query;
 mod execute;
+mod sale;
+mod compose;
 pub mod state;
 
 pub use crate::error::ContractError;
-pub use crate::msg::{InstantiateMsg};
+pub use crate::msg::{InstantiateMsg, MigrateMsg, RevealConfig};
 pub use cw721::{Cw721ExecuteMsg,Cw721QueryMsg};
 pub use crate::state::Cw721Contract;
 use cosmwasm_std::Empty;
@@ -21,6 +23,9 @@ pub mod entry {
     #[cfg(not(feature = "library"))]
     use cosmwasm_std::entry_point;
     use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cw_utils::ensure_from_older_version;
+
+    use crate::execute::{CONTRACT_NAME, CONTRACT_VERSION};
 
     // This makes a conscious choice on the various generics used by the contract
     #[cfg_attr(not(feature = "library"), entry_point)]
@@ -50,4 +55,11 @@ pub mod entry {
         let tract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
         tract.query(deps, env, msg)
     }
+
+    #[cfg_attr(not(feature = "library"), entry_point)]
+    pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+        ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        let tract = Cw721Contract::<Extension, Empty, Empty, Empty>::default();
+        tract.migrate(deps, env, msg)
+    }
 }
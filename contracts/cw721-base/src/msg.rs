@@ -1,5 +1,6 @@
 use cosmwasm_schema::{cw_serde};
 use cosmwasm_std::{Uint128/*,Binary */};
+use cw721::TransferPolicy;
 
 #[cw_serde]
 #[cfg_attr(test, derive(Default))]
@@ -10,4 +11,20 @@ pub struct InstantiateMsg {
     pub symbol: String,
     /// Symbol of the NFT contract
     pub max_supply : Uint128,
+    /// Policy for tokens minted without one, Free when not set
+    #[serde(default)]
+    pub transfer_policy : Option<TransferPolicy>,
+    /// Set for a delayed reveal, every token shows the placeholder until Reveal
+    pub reveal : Option<RevealConfig>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+#[cw_serde]
+pub struct RevealConfig {
+    /// Uri every token shows before the reveal
+    pub placeholder_uri: String,
+    /// Commitment to the final metadata, e.g. sha256 over the concatenated metadata hashes
+    pub provenance_hash: String,
 }
\ No newline at end of file
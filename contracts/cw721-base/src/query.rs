@@ -6,16 +6,16 @@ use cosmwasm_std::{
 };
 
 use cw721::{
-    AllTokenInfoResponse, ContractInfoResponse, Cw721Query,
+    AllTokenInfoResponse, ClawbackLogEntry, ClawbackLogResponse, ContractInfoResponse, Cw721Query, LockInfo,
     Expiration, TokenInfoResponse, TokenSupplyResponse, OperatorsResponse, OwnerOfResponse,
-    TokensResponse,TokenSupply,Cw721QueryMsg,AllowanceResponse,
+    TokensResponse,TokenSupply,Cw721QueryMsg,AllowanceResponse,TransferPolicyResponse,UserOfResponse,TokenHistoryEntry,TokenHistoryResponse,IsBurnedResponse,Tombstone,StatsResponse,IsMetadataFrozenResponse,ProvenanceResponse,IsVoucherRedeemedResponse,
 };
 use cw_storage_plus::Bound;
 use cw_utils::maybe_addr;
 use crate::state::{Cw721Contract};
 
-const DEFAULT_LIMIT: u32 = 10;
-const MAX_LIMIT: u32 = 100;
+pub(crate) const DEFAULT_LIMIT: u32 = 10;
+pub(crate) const MAX_LIMIT: u32 = 100;
 
 impl<'a, T, C, E, Q> Cw721Query<T> for Cw721Contract<'a, T, C, E, Q>
 where
@@ -30,6 +30,7 @@ where
             name : contract_info.name,
             symbol : contract_info.symbol,
             owner : contract_info.owner.to_string(),
+            base_uri : self.base_uri.may_load(deps.storage)?,
        })
     }
 
@@ -44,8 +45,13 @@ where
     fn token_info(&self, deps: Deps, token_id: String) -> StdResult<TokenInfoResponse<T>> {
         let info = self.tokens.load(deps.storage, &token_id)?;
         Ok(TokenInfoResponse {
-            token_uri: info.token_uri,
+            token_uri: self.display_token_uri(deps.storage, &token_id, info.token_uri)?,
             extension: info.extension,
+            lock: info.lock.map(|lock| LockInfo {
+                locker: lock.locker.to_string(),
+                lock_id: lock.lock_id,
+                locked_at: lock.locked_at,
+            }),
         })
     }
     fn owner_of(
@@ -169,14 +175,23 @@ where
     fn all_token_info(
         &self,
         deps: Deps,
+        env: Env,
         token_id: String,
     ) -> StdResult<AllTokenInfoResponse<T>> {
         let info = self.tokens.load(deps.storage, &token_id)?;
         Ok(AllTokenInfoResponse {
             owner: info.owner.to_string(),
+            user: info.user
+                .filter(|user| !user.is_expired(&env.block))
+                .map(|user| user.user.to_string()),
             info: TokenInfoResponse {
-                token_uri: info.token_uri,
+                token_uri: self.display_token_uri(deps.storage, &token_id, info.token_uri)?,
                 extension: info.extension,
+                lock: info.lock.map(|lock| LockInfo {
+                    locker: lock.locker.to_string(),
+                    lock_id: lock.lock_id,
+                    locked_at: lock.locked_at,
+                }),
             },
         })
     }
@@ -189,6 +204,178 @@ where
     E: CustomMsg,
     Q: CustomMsg,
 {
+    pub fn user_of(
+        &self,
+        deps: Deps,
+        env: Env,
+        token_id: String,
+    ) -> StdResult<UserOfResponse> {
+        let info = self.tokens.load(deps.storage, &token_id)?;
+        match info.user.filter(|user| !user.is_expired(&env.block)) {
+            Some(user) => Ok(UserOfResponse {
+                user: Some(user.user.to_string()),
+                expires: Some(user.expires),
+            }),
+            None => Ok(UserOfResponse { user: None, expires: None }),
+        }
+    }
+
+    pub fn query_transfer_policy(
+        &self,
+        deps: Deps,
+        token_id: Option<String>,
+    ) -> StdResult<TransferPolicyResponse> {
+        match token_id {
+            Some(token_id) => {
+                let token = self.tokens.load(deps.storage, &token_id)?;
+                Ok(TransferPolicyResponse {
+                    policy: self.get_transfer_policy(deps.storage, &token)?,
+                    bound: token.bound,
+                })
+            }
+            None => Ok(TransferPolicyResponse {
+                policy: self.transfer_policy.may_load(deps.storage)?.unwrap_or_default(),
+                bound: false,
+            }),
+        }
+    }
+
+    pub fn clawback_log(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ClawbackLogResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let entries = self
+            .clawback_log
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(id, record)| ClawbackLogEntry {
+                    id,
+                    operator: record.operator.to_string(),
+                    from: record.from.to_string(),
+                    to: record.to.to_string(),
+                    token_id: record.token_id,
+                    reason: record.reason,
+                    height: record.height,
+                    time: record.time,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        let renounced = self.clawback_renounced.may_load(deps.storage)?.unwrap_or(false);
+        Ok(ClawbackLogResponse { renounced, entries })
+    }
+
+    pub fn is_burned(&self, deps: Deps, token_id: String) -> StdResult<IsBurnedResponse> {
+        let tombstone = self
+            .burned
+            .may_load(deps.storage, &token_id)?
+            .map(|record| Tombstone {
+                burner: record.burner.to_string(),
+                owner: record.owner.to_string(),
+                height: record.height,
+                time: record.time,
+            });
+        Ok(IsBurnedResponse {
+            burned: tombstone.is_some(),
+            tombstone,
+        })
+    }
+
+    pub fn query_metadata_frozen(
+        &self,
+        deps: Deps,
+        token_id: Option<String>,
+    ) -> StdResult<IsMetadataFrozenResponse> {
+        let frozen = match token_id {
+            Some(token_id) => self.is_metadata_frozen(deps.storage, &token_id)?,
+            None => self.metadata_frozen.may_load(deps.storage)?.unwrap_or_default(),
+        };
+        Ok(IsMetadataFrozenResponse { frozen })
+    }
+
+    pub fn is_voucher_redeemed(
+        &self,
+        deps: Deps,
+        signer: String,
+        nonce: u64,
+    ) -> StdResult<IsVoucherRedeemedResponse> {
+        let signer = deps.api.addr_validate(&signer)?;
+        Ok(IsVoucherRedeemedResponse {
+            redeemed: self.redeemed_vouchers.has(deps.storage, (&signer, nonce)),
+        })
+    }
+
+    pub fn provenance(&self, deps: Deps) -> StdResult<ProvenanceResponse> {
+        let reveal = self.reveal.may_load(deps.storage)?;
+        Ok(ProvenanceResponse {
+            revealed: reveal.as_ref().map(|r| r.revealed).unwrap_or_default(),
+            provenance_hash: reveal.map(|r| r.provenance_hash),
+        })
+    }
+
+    pub fn stats(&self, deps: Deps) -> StdResult<StatsResponse> {
+        // every minted token got a running id, the ones missing from supply were burned
+        let minted = self.get_last_running_id(deps.storage)?;
+        let burned = minted - self.get_total_supply(deps.storage)?;
+        Ok(StatsResponse {
+            minted: minted.into(),
+            burned: burned.into(),
+            transfers: self
+                .transfer_count
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+            unique_holders: self
+                .tokens
+                .idx
+                .holders
+                .holders
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn token_history(
+        &self,
+        deps: Deps,
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<TokenHistoryResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let history = self.token_history.prefix(&token_id);
+
+        // a lowered cap only trims storage on the next write, hide the surplus until then
+        let history_cap = self.history_cap.may_load(deps.storage)?.unwrap_or_default() as u64;
+        let last = history.keys(deps.storage, None, None, Order::Descending).next().transpose()?;
+        let last = match last {
+            Some(last) if history_cap > 0 => last,
+            _ => return Ok(TokenHistoryResponse { entries: vec![] }),
+        };
+        let oldest_kept = (last + 1).saturating_sub(history_cap);
+        let start = start_after.map_or(oldest_kept, |start_after| start_after.saturating_add(1).max(oldest_kept));
+
+        let entries = history
+            .range(deps.storage, Some(Bound::inclusive(start)), None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(id, record)| TokenHistoryEntry {
+                    id,
+                    actor: record.actor.to_string(),
+                    owner: record.owner.to_string(),
+                    action: record.action,
+                    height: record.height,
+                    time: record.time,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(TokenHistoryResponse { entries })
+    }
+
     pub fn query(&self, deps: Deps, env: Env, msg: Cw721QueryMsg<Q>) -> StdResult<Binary> {
         match msg {
             
@@ -200,7 +387,9 @@ where
 
             Cw721QueryMsg::AllBalance{owner} => to_binary(&self.all_balance(deps, owner)?),
 
-            Cw721QueryMsg::AllTokenInfo {token_id} => to_binary(&self.all_token_info(deps,token_id)?),
+            Cw721QueryMsg::AllTokenInfo {token_id} => to_binary(&self.all_token_info(deps,env,token_id)?),
+
+            Cw721QueryMsg::UserOf {token_id} => to_binary(&self.user_of(deps,env,token_id)?),
             
             Cw721QueryMsg::AllOperators { owner,include_expired,start_after,limit} => to_binary(&self.operators(deps,env,owner,include_expired,start_after,limit)?),
 
@@ -212,6 +401,32 @@ where
 
             Cw721QueryMsg::Allowance { owner, spender } => to_binary(&self.allowance(deps, env, owner, spender)?),
             
+            Cw721QueryMsg::TransferPolicy { token_id } => to_binary(&self.query_transfer_policy(deps, token_id)?),
+
+            Cw721QueryMsg::ClawbackLog { start_after, limit } => to_binary(&self.clawback_log(deps, start_after, limit)?),
+
+            Cw721QueryMsg::TokenHistory { token_id, start_after, limit } => to_binary(&self.token_history(deps, token_id, start_after, limit)?),
+
+            Cw721QueryMsg::IsBurned { token_id } => to_binary(&self.is_burned(deps, token_id)?),
+
+            Cw721QueryMsg::Stats {} => to_binary(&self.stats(deps)?),
+
+            Cw721QueryMsg::IsMetadataFrozen { token_id } => to_binary(&self.query_metadata_frozen(deps, token_id)?),
+
+            Cw721QueryMsg::Provenance {} => to_binary(&self.provenance(deps)?),
+
+            Cw721QueryMsg::SaleStatus {} => to_binary(&self.sale_status(deps, env)?),
+
+            Cw721QueryMsg::IsVoucherRedeemed { signer, nonce } => to_binary(&self.is_voucher_redeemed(deps, signer, nonce)?),
+
+            Cw721QueryMsg::ChildrenOf { token_id, start_after, limit } => {
+                to_binary(&self.children_of(deps, token_id, start_after, limit)?)
+            }
+
+            Cw721QueryMsg::ChildCollections { start_after, limit } => {
+                to_binary(&self.child_collections(deps, start_after, limit)?)
+            }
+
             Cw721QueryMsg::Extension { msg: _ } => Ok(Binary::default()),
         }
     }
@@ -0,0 +1,270 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, Attribute, BankMsg, BlockInfo, Coin, CustomMsg, Deps,
+    DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721::{SaleAsset, SaleConfig, SalePhase, SaleReceiveMsg, SaleStatusResponse};
+
+use crate::error::ContractError;
+use crate::state::Cw721Contract;
+
+impl<'a, T, C, E, Q> Cw721Contract<'a, T, C, E, Q>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    C: CustomMsg,
+    E: CustomMsg,
+    Q: CustomMsg,
+{
+    pub fn update_sale(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        sale: Option<SaleConfig<T>>,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        match sale {
+            Some(sale) => {
+                for phase in sale.phases.iter() {
+                    validate_phase(deps.as_ref(), phase)?;
+                }
+                self.sale.save(deps.storage, &sale)?;
+            }
+            None => self.sale.remove(deps.storage),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "update_sale")
+            .add_attribute("by", info.sender)
+        )
+    }
+
+    pub fn buy(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        count: u32,
+        proof: Option<Vec<String>>,
+    ) -> Result<Response<C>, ContractError> {
+        let buyer = info.sender.clone();
+        self._buy(deps, &env.block, &buyer, count, proof, |price, total| match price {
+            SaleAsset::Native { denom, .. } => {
+                let expected: Vec<Coin> = if total.is_zero() { vec![] } else { vec![Coin { denom: denom.clone(), amount: total }] };
+                Ok(info.funds == expected)
+            }
+            SaleAsset::Cw20 { .. } => Ok(false),
+        })
+    }
+
+    /// Cw20 Send into a cw20 priced phase, the cw20 sender is the buyer
+    pub fn receive_cw20(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        wrapper: Cw20ReceiveMsg,
+    ) -> Result<Response<C>, ContractError> {
+        let buyer = deps.api.addr_validate(&wrapper.sender)?;
+        let SaleReceiveMsg::Buy { count, proof } = from_binary(&wrapper.msg)?;
+        self._buy(deps, &env.block, &buyer, count, proof, |price, total| match price {
+            SaleAsset::Cw20 { token, .. } => Ok(info.sender.as_str() == token && wrapper.amount == total),
+            SaleAsset::Native { .. } => Ok(false),
+        })
+    }
+
+    pub fn withdraw_proceeds(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response<C>, ContractError> {
+
+        if !self.is_contract_owner(deps.as_ref(),&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        let proceeds = self.sale_proceeds.may_load(deps.storage)?.unwrap_or_default();
+        self.sale_proceeds.remove(deps.storage);
+
+        let mut response = Response::new()
+            .add_attribute("action", "withdraw_proceeds")
+            .add_attribute("by", info.sender.clone());
+        for proceed in proceeds {
+            response = match proceed {
+                SaleAsset::Native { denom, amount } => response.add_message(BankMsg::Send {
+                    to_address: info.sender.to_string(),
+                    amount: vec![Coin { denom, amount }],
+                }),
+                SaleAsset::Cw20 { token, amount } => response.add_message(WasmMsg::Execute {
+                    contract_addr: token,
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer { to: info.sender.to_string(), amount })?,
+                    funds: vec![],
+                }),
+            };
+        }
+        Ok(response)
+    }
+
+    pub fn sale_status(&self, deps: Deps, env: Env) -> StdResult<SaleStatusResponse> {
+        let phases = self.sale.may_load(deps.storage)?.map(|sale| sale.phases).unwrap_or_default();
+        let sold = (0..phases.len() as u32)
+            .map(|index| Ok(self.sale_sold.may_load(deps.storage, index)?.unwrap_or_default()))
+            .collect::<StdResult<Vec<u32>>>()?;
+        Ok(SaleStatusResponse {
+            active_phase: active_phase(&phases, &env.block).map(|(index, _)| index),
+            phases,
+            sold,
+            total_supply: self.get_total_supply(deps.storage)?.into(),
+            max_supply: self.get_max_supply(deps.storage)?.into(),
+            proceeds: self.sale_proceeds.may_load(deps.storage)?.unwrap_or_default(),
+        })
+    }
+
+    /// Checks the active phase rules, is_paid tells whether the buyer paid the total price,
+    /// then mints count tokens to buyer.
+    fn _buy<P>(
+        &self,
+        deps: DepsMut,
+        block: &BlockInfo,
+        buyer: &Addr,
+        count: u32,
+        proof: Option<Vec<String>>,
+        is_paid: P,
+    ) -> Result<Response<C>, ContractError>
+    where
+        P: FnOnce(&SaleAsset, Uint128) -> StdResult<bool>,
+    {
+        if count == 0 {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        let sale = self.sale.may_load(deps.storage)?.ok_or(ContractError::SaleNotActive {})?;
+        let (phase_index, phase) = active_phase(&sale.phases, block).ok_or(ContractError::SaleNotActive {})?;
+
+        if let Some(merkle_root) = &phase.merkle_root {
+            if !verify_merkle_proof(merkle_root, buyer, &proof.unwrap_or_default())? {
+                return Err(ContractError::NotWhitelisted {});
+            }
+        }
+
+        let (price, price_amount) = match &phase.price {
+            SaleAsset::Native { denom, amount } => (denom.clone(), amount),
+            SaleAsset::Cw20 { token, amount } => (token.clone(), amount),
+        };
+        let total = price_amount.checked_mul(Uint128::from(count)).map_err(StdError::from)?;
+        if !is_paid(&phase.price, total)? {
+            return Err(ContractError::WrongPayment { expected: format!("{}{}", total, price) });
+        }
+
+        let bought = self.sale_bought.may_load(deps.storage, (phase_index, buyer))?.unwrap_or_default() + count;
+        if let Some(limit) = phase.per_wallet_limit {
+            if bought > limit {
+                return Err(ContractError::WalletLimitReached { limit });
+            }
+        }
+        self.check_max_supply(deps.storage, count as u128)?;
+        self.sale_bought.save(deps.storage, (phase_index, buyer), &bought)?;
+        let sold = self.sale_sold.may_load(deps.storage, phase_index)?.unwrap_or_default();
+        self.sale_sold.save(deps.storage, phase_index, &(sold + count))?;
+
+        let mut mint_attrs : Vec<Attribute> = vec![];
+        for index in 0..count {
+            let token_id_str = self._mint_token(deps.storage, block, buyer, buyer.clone(), None, sale.extension.clone(), None)?;
+            mint_attrs.push(attr(format!("token_id[{}]", index), token_id_str));
+        }
+
+        if !total.is_zero() {
+            let mut proceeds = self.sale_proceeds.may_load(deps.storage)?.unwrap_or_default();
+            add_proceeds(&mut proceeds, &phase.price, total);
+            self.sale_proceeds.save(deps.storage, &proceeds)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "buy")
+            .add_attribute("buyer", buyer)
+            .add_attribute("phase", &phase.name)
+            .add_attribute("paid", format!("{}{}", total, price))
+            .add_attributes(mint_attrs))
+    }
+}
+
+/// The open phase at block time, the later one in the list wins when phases overlap
+fn active_phase<'p>(phases: &'p [SalePhase], block: &BlockInfo) -> Option<(u32, &'p SalePhase)> {
+    phases
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, phase)| {
+            phase.start_time <= block.time && !matches!(phase.end_time, Some(end) if end <= block.time)
+        })
+        .map(|(index, phase)| (index as u32, phase))
+}
+
+fn validate_phase(deps: Deps, phase: &SalePhase) -> Result<(), ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidSalePhase { name: phase.name.clone(), reason: reason.to_string() };
+    if let Some(end_time) = phase.end_time {
+        if end_time <= phase.start_time {
+            return Err(invalid("ends before it starts"));
+        }
+    }
+    if let Some(merkle_root) = &phase.merkle_root {
+        if hex::decode(merkle_root).map(|root| root.len()) != Ok(32) {
+            return Err(invalid("merkle root is not a hex sha256 hash"));
+        }
+    }
+    if let SaleAsset::Cw20 { token, .. } = &phase.price {
+        deps.api.addr_validate(token)?;
+    }
+    Ok(())
+}
+
+fn add_proceeds(proceeds: &mut Vec<SaleAsset>, price: &SaleAsset, total: Uint128) {
+    for proceed in proceeds.iter_mut() {
+        match (proceed, price) {
+            (SaleAsset::Native { denom, amount }, SaleAsset::Native { denom: price_denom, .. }) if denom == price_denom => {
+                *amount += total;
+                return;
+            }
+            (SaleAsset::Cw20 { token, amount }, SaleAsset::Cw20 { token: price_token, .. }) if token == price_token => {
+                *amount += total;
+                return;
+            }
+            _ => {}
+        }
+    }
+    proceeds.push(match price {
+        SaleAsset::Native { denom, .. } => SaleAsset::Native { denom: denom.clone(), amount: total },
+        SaleAsset::Cw20 { token, .. } => SaleAsset::Cw20 { token: token.clone(), amount: total },
+    });
+}
+
+// Prefixes keep a node from passing as a leaf
+const MERKLE_LEAF_PREFIX: u8 = 0x00;
+const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+/// Leaves are `sha256(0x00 || address)`, nodes `sha256(0x01 || a || b)` with the pair sorted
+pub fn verify_merkle_proof(merkle_root: &str, address: &Addr, proof: &[String]) -> Result<bool, ContractError> {
+    let mut hash: [u8; 32] = Sha256::new()
+        .chain_update([MERKLE_LEAF_PREFIX])
+        .chain_update(address.as_bytes())
+        .finalize()
+        .into();
+    for step in proof {
+        let step: [u8; 32] = hex::decode(step)
+            .ok()
+            .and_then(|step| step.try_into().ok())
+            .ok_or(ContractError::NotWhitelisted {})?;
+        let (first, second) = if hash <= step { (hash, step) } else { (step, hash) };
+        hash = Sha256::new()
+            .chain_update([MERKLE_NODE_PREFIX])
+            .chain_update(first)
+            .chain_update(second)
+            .finalize()
+            .into();
+    }
+    Ok(hex::encode(hash) == merkle_root.to_lowercase())
+}
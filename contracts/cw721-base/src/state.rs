@@ -3,9 +3,9 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
-use cosmwasm_std::{Addr, BlockInfo, CustomMsg, StdResult, Storage};
+use cosmwasm_std::{Addr, Binary, BlockInfo, CustomMsg, StdResult, Storage, Timestamp};
 
-use cw721::{Cw721, Expiration};
+use cw721::{ChildToken, Cw721, Expiration, HistoryAction, SaleAsset, SaleConfig, TransferPolicy};
 use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 use cosmwasm_schema::cw_serde;
@@ -15,6 +15,52 @@ pub struct CooperativeData{
     pub can_burn_from : bool,
 }
 
+#[cw_serde]
+pub struct ClawbackRecord{
+    pub operator : Addr,
+    pub from : Addr,
+    pub to : Addr,
+    pub token_id : String,
+    pub reason : String,
+    pub height : u64,
+    pub time : Timestamp,
+}
+
+/// Default seconds before a token owner can emergency unlock (7 days)
+pub const DEFAULT_LOCK_TIMEOUT: u64 = 7 * 24 * 60 * 60;
+
+#[cw_serde]
+pub struct RevealState {
+    pub placeholder_uri: String,
+    pub provenance_hash: String,
+    pub revealed: bool,
+}
+
+#[cw_serde]
+pub struct BurnRecord {
+    pub burner: Addr,
+    pub owner: Addr,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct HistoryRecord{
+    /// Sender of the action, the minter for a mint
+    pub actor : Addr,
+    pub owner : Addr,
+    pub action : HistoryAction,
+    pub height : u64,
+    pub time : Timestamp,
+}
+
+/// Progress of the holder backfill started by `migrate` and finished by `MigrateHolders`
+#[cw_serde]
+pub struct HolderMigration {
+    /// Last token counted, the next page starts after it
+    pub cursor: Option<String>,
+}
+
 #[cw_serde]
 pub struct ContractInfo{
     pub name: String,
@@ -32,11 +78,55 @@ where
     pub total_supply : Item<'a, u128>,
     pub max_supply : Item<'a, u128>,
     pub token_running_id : Item<'a,u128>,
+    /// Policy of tokens minted without their own one
+    pub transfer_policy : Item<'a, TransferPolicy>,
 
     /// Stored as (granter, operator) giving operator full control over granter's account
     pub spenders: Map<'a, (&'a Addr, &'a Addr), Expiration>,
+    /// Last (granter, operator) looked at by an unscoped prune, the next one starts after it
+    pub spenders_prune_cursor: Item<'a, (Addr, Addr)>,
+    /// Last operator looked at by a prune scoped to the granter, `granter -> operator`
+    pub spenders_owner_prune_cursors: Map<'a, &'a Addr, Addr>,
     pub cooperatives : Map<'a, &'a Addr, CooperativeData>,
     pub tokens: IndexedMap<'a, &'a str, TokenInfo<T>, TokenIndexes<'a, T>>,
+    /// Set once the owner renounced ForceTransfer
+    pub clawback_renounced: Item<'a, bool>,
+    /// Every ForceTransfer ever executed, `id -> record`
+    pub clawback_log: Map<'a, u64, ClawbackRecord>,
+    /// Seconds a lock must be held before the token owner can emergency unlock
+    pub lock_timeout: Item<'a, u64>,
+    /// Max history entries kept per token, 0 disables history
+    pub history_cap: Item<'a, u32>,
+    /// `(token_id, id) -> record`, ids keep growing while the oldest get dropped
+    pub token_history: Map<'a, (&'a str, u64), HistoryRecord>,
+    /// Tombstones of burned tokens
+    pub burned: Map<'a, &'a str, BurnRecord>,
+    /// Number of transfers of any kind
+    pub transfer_count: Item<'a, u64>,
+    /// Set once the owner froze the metadata of every token
+    pub metadata_frozen: Item<'a, bool>,
+    /// Tokens whose metadata the owner froze one by one
+    pub frozen_tokens: Map<'a, &'a str, bool>,
+    /// Uri template of tokens minted without their own token_uri
+    pub base_uri: Item<'a, String>,
+    /// Only set for a delayed reveal
+    pub reveal: Item<'a, RevealState>,
+    /// Primary sale, unset when there is none
+    pub sale: Item<'a, SaleConfig<T>>,
+    /// Tokens sold per phase, `phase -> count`
+    pub sale_sold: Map<'a, u32, u32>,
+    /// Tokens bought per wallet and phase, `(phase, buyer) -> count`
+    pub sale_bought: Map<'a, (u32, &'a Addr), u32>,
+    /// Sale proceeds the owner did not withdraw yet
+    pub sale_proceeds: Item<'a, Vec<SaleAsset>>,
+    /// secp256k1 public keys of voucher signers
+    pub voucher_signers: Map<'a, &'a Addr, Binary>,
+    /// `(signer, nonce)` of redeemed vouchers
+    pub redeemed_vouchers: Map<'a, (&'a Addr, u64), bool>,
+    /// Equipped tokens, `(parent token_id, child contract, child token_id) -> child`
+    pub children: Map<'a, (&'a str, &'a Addr, &'a str), ChildToken>,
+    /// Collections the owner allows to be equipped
+    pub child_collections: Map<'a, &'a Addr, bool>,
    
     pub(crate) _custom_response: PhantomData<C>,
     pub(crate) _custom_query: PhantomData<Q>,
@@ -79,6 +169,7 @@ where
     E: CustomMsg,
     Q: CustomMsg,
 {
+    /// Storage added after the original keys is named after its field
     fn new(
         contract_key: &'a str,
         total_supply_key: &'a str,
@@ -91,21 +182,52 @@ where
     ) -> Self {
         let indexes = TokenIndexes {
             owner: MultiIndex::new(token_owner_idx, tokens_key, tokens_owner_key),
+            holders: HolderIndex::new("tokens__holder_counts", "tokens__holders", "tokens__holders_migration"),
+            custom: None,
         };
         Self {
             contract_info: Item::new(contract_key),
             total_supply: Item::new(total_supply_key),
             max_supply: Item::new(max_supply_key),
             token_running_id: Item::new(token_running_id_key),
+            transfer_policy: Item::new("transfer_policy"),
             spenders: Map::new(spenders_key),
+            spenders_prune_cursor: Item::new("spenders_prune_cursor"),
+            spenders_owner_prune_cursors: Map::new("spenders_owner_prune_cursors"),
             cooperatives: Map::new(cooperative_key),
             tokens: IndexedMap::new(tokens_key, indexes),
+            clawback_renounced: Item::new("clawback_renounced"),
+            clawback_log: Map::new("clawback_log"),
+            lock_timeout: Item::new("lock_timeout"),
+            history_cap: Item::new("history_cap"),
+            token_history: Map::new("token_history"),
+            burned: Map::new("burned"),
+            transfer_count: Item::new("transfer_count"),
+            metadata_frozen: Item::new("metadata_frozen"),
+            frozen_tokens: Map::new("frozen_tokens"),
+            base_uri: Item::new("base_uri"),
+            reveal: Item::new("reveal"),
+            sale: Item::new("sale"),
+            sale_sold: Map::new("sale_sold"),
+            sale_bought: Map::new("sale_bought"),
+            sale_proceeds: Item::new("sale_proceeds"),
+            voucher_signers: Map::new("voucher_signers"),
+            redeemed_vouchers: Map::new("redeemed_vouchers"),
+            children: Map::new("children"),
+            child_collections: Map::new("child_collections"),
             _custom_response: PhantomData,
             _custom_execute: PhantomData,
             _custom_query: PhantomData,
         }
     }
 
+    /// Adds an index of the contract built on top of this one, kept up to date on every token write.
+    /// Without it tokens only pay for the owner and holder indexes.
+    pub fn with_token_index(mut self, index: Box<dyn Index<TokenInfo<T>> + 'a>) -> Self {
+        self.tokens.idx.custom = Some(index);
+        self
+    }
+
     pub fn get_total_supply(&self, storage: &dyn Storage) -> StdResult<u128> {
         Ok(self.total_supply.may_load(storage)?.unwrap_or_default())
     }
@@ -117,6 +239,58 @@ where
     pub fn get_last_running_id(&self, storage: &dyn Storage) -> StdResult<u128> {
         Ok(self.token_running_id.may_load(storage)?.unwrap_or_default())
     }
+
+    pub fn get_lock_timeout(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.lock_timeout.may_load(storage)?.unwrap_or(DEFAULT_LOCK_TIMEOUT))
+    }
+
+    pub fn is_metadata_frozen(&self, storage: &dyn Storage, token_id: &str) -> StdResult<bool> {
+        Ok(self.metadata_frozen.may_load(storage)?.unwrap_or_default()
+            || self.frozen_tokens.has(storage, token_id))
+    }
+
+    /// The token's own uri, or else the collection base_uri filled in with token_id
+    pub fn resolve_token_uri(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+        token_uri: Option<String>,
+    ) -> StdResult<Option<String>> {
+        if token_uri.is_some() {
+            return Ok(token_uri);
+        }
+        Ok(self.base_uri.may_load(storage)?.map(|base_uri| expand_base_uri(&base_uri, token_id)))
+    }
+
+    /// What queries show, the placeholder until the reveal or else resolve_token_uri
+    pub fn display_token_uri(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+        token_uri: Option<String>,
+    ) -> StdResult<Option<String>> {
+        match self.reveal.may_load(storage)? {
+            Some(reveal) if !reveal.revealed => Ok(Some(reveal.placeholder_uri)),
+            _ => self.resolve_token_uri(storage, token_id, token_uri),
+        }
+    }
+
+    /// Policy that applies to token, its own one or else the collection one.
+    pub fn get_transfer_policy(&self, storage: &dyn Storage, token: &TokenInfo<T>) -> StdResult<TransferPolicy> {
+        match &token.transfer_policy {
+            Some(policy) => Ok(policy.clone()),
+            None => Ok(self.transfer_policy.may_load(storage)?.unwrap_or_default()),
+        }
+    }
+}
+
+/// Replaces every `{id}` in base_uri with token_id, without a placeholder token_id is appended
+pub fn expand_base_uri(base_uri: &str, token_id: &str) -> String {
+    if base_uri.contains("{id}") {
+        base_uri.replace("{id}", token_id)
+    } else {
+        format!("{}{}", base_uri, token_id)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -131,6 +305,44 @@ pub struct TokenInfo<T> {
 
     /// You can add any custom metadata here when you extend cw721-base
     pub extension: T,
+
+    /// Set at mint time, when None the collection policy applies
+    #[serde(default)]
+    pub transfer_policy: Option<TransferPolicy>,
+
+    /// Only used by BindOnTransfer, set once the token got transferred
+    #[serde(default)]
+    pub bound: bool,
+
+    /// Who may use the token without owning it, cleared on transfer
+    #[serde(default)]
+    pub user: Option<TokenUser>,
+
+    /// Set by a cooperative, a locked token can not be transferred or burned
+    #[serde(default)]
+    pub lock: Option<TokenLock>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenLock {
+    /// Account that locked the token, the only one besides contract owner that can unlock it
+    pub locker: Addr,
+    pub lock_id: String,
+    pub locked_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenUser {
+    /// Account that can use the token
+    pub user: Addr,
+    /// When the usage right ends (maybe Expiration::never)
+    pub expires: Expiration,
+}
+
+impl TokenUser {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.expires.is_expired(block)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -152,6 +364,9 @@ where
     T: Serialize + DeserializeOwned + Clone,
 {
     pub owner: MultiIndex<'a, Addr, TokenInfo<T>, String>,
+    pub holders: HolderIndex<'a>,
+    /// Set with `Cw721Contract::with_token_index`
+    pub custom: Option<Box<dyn Index<TokenInfo<T>> + 'a>>,
 }
 
 impl<'a, T> IndexList<TokenInfo<T>> for TokenIndexes<'a, T>
@@ -159,7 +374,8 @@ where
     T: Serialize + DeserializeOwned + Clone,
 {
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<TokenInfo<T>>> + '_> {
-        let v: Vec<&dyn Index<TokenInfo<T>>> = vec![&self.owner];
+        let mut v: Vec<&dyn Index<TokenInfo<T>>> = vec![&self.owner, &self.holders];
+        v.extend(self.custom.as_deref());
         Box::new(v.into_iter())
     }
 }
@@ -167,3 +383,73 @@ where
 pub fn token_owner_idx<T>(_pk: &[u8], d: &TokenInfo<T>) -> Addr {
     d.owner.clone()
 }
+
+/// Counts tokens per owner, `holders` is the number of owners with at least one token
+pub struct HolderIndex<'a> {
+    pub counts: Map<'a, &'a Addr, u64>,
+    pub holders: Item<'a, u64>,
+    /// Only set while tokens minted before the index are being counted
+    pub migration: Item<'a, HolderMigration>,
+}
+
+impl<'a> HolderIndex<'a> {
+    pub fn new(counts_key: &'a str, holders_key: &'a str, migration_key: &'a str) -> Self {
+        Self {
+            counts: Map::new(counts_key),
+            holders: Item::new(holders_key),
+            migration: Item::new(migration_key),
+        }
+    }
+
+    /// Tokens past the backfill cursor are counted once the backfill reaches them
+    fn is_counted(&self, store: &dyn Storage, pk: &[u8]) -> StdResult<bool> {
+        Ok(match self.migration.may_load(store)? {
+            Some(migration) => migration.cursor.is_some_and(|cursor| pk <= cursor.as_bytes()),
+            None => true,
+        })
+    }
+
+    pub fn add(&self, store: &mut dyn Storage, owner: &Addr) -> StdResult<()> {
+        let count = self.counts.may_load(store, owner)?.unwrap_or_default();
+        if count == 0 {
+            let holders = self.holders.may_load(store)?.unwrap_or_default();
+            self.holders.save(store, &(holders + 1))?;
+        }
+        self.counts.save(store, owner, &(count + 1))
+    }
+
+    pub fn sub(&self, store: &mut dyn Storage, owner: &Addr) -> StdResult<()> {
+        let count = self.counts.may_load(store, owner)?.unwrap_or_default();
+        if count <= 1 {
+            self.counts.remove(store, owner);
+            let holders = self.holders.may_load(store)?.unwrap_or_default();
+            self.holders.save(store, &holders.saturating_sub(1))?;
+            return Ok(());
+        }
+        self.counts.save(store, owner, &(count - 1))
+    }
+}
+
+impl<'a, T> Index<TokenInfo<T>> for HolderIndex<'a>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &TokenInfo<T>) -> StdResult<()> {
+        if !self.is_counted(store, pk)? {
+            return Ok(());
+        }
+        self.add(store, &data.owner)
+    }
+
+    fn remove(
+        &self,
+        store: &mut dyn Storage,
+        pk: &[u8],
+        old_data: &TokenInfo<T>,
+    ) -> StdResult<()> {
+        if !self.is_counted(store, pk)? {
+            return Ok(());
+        }
+        self.sub(store, &old_data.owner)
+    }
+}
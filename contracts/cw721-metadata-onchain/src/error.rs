@@ -0,0 +1,20 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Base(#[from] cw721_base::ContractError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Trait is immutable : {trait_type}")]
+    TraitImmutable { trait_type : String },
+
+    #[error("Indexed traits can only be chosen before the first mint")]
+    IndexedTraitsLocked { },
+}
@@ -0,0 +1,226 @@
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+
+use crate::msg::{MetadataExecuteMsg, TraitPermission};
+use crate::state::{trait_index, TRAIT_PERMISSIONS};
+use crate::{ContractError, Cw721MetadataContract, ExecuteMsg, Extension, Metadata, Trait};
+
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let contract = Cw721MetadataContract::default();
+    match msg {
+        ExecuteMsg::Extension { msg } => match msg {
+            MetadataExecuteMsg::SetTraits { token_id, traits } => {
+                execute_set_traits(deps, info, token_id, traits)
+            }
+            MetadataExecuteMsg::RemoveTraits { token_id, trait_types } => {
+                execute_remove_traits(deps, info, token_id, trait_types)
+            }
+            MetadataExecuteMsg::UpdateImage { token_id, image } => {
+                execute_update_fields(deps, info, token_id, "update_image", |metadata| metadata.image = image)
+            }
+            MetadataExecuteMsg::UpdateDescription { token_id, description } => {
+                execute_update_fields(deps, info, token_id, "update_description", |metadata| {
+                    metadata.description = description
+                })
+            }
+            MetadataExecuteMsg::SetTraitPermission { trait_type, permission } => {
+                execute_set_trait_permission(deps, info, trait_type, permission)
+            }
+            MetadataExecuteMsg::SetIndexedTraits { trait_types } => {
+                execute_set_indexed_traits(deps, info, trait_types)
+            }
+        },
+        ExecuteMsg::UpdateTokenExtension { token_id, extension } => {
+            // whole replacement must leave birth traits as they are
+            let token = contract.tokens.load(deps.storage, &token_id)?;
+            check_immutable_unchanged(deps.storage, &token.extension, &extension)?;
+            Ok(contract.execute(deps, env, info, ExecuteMsg::UpdateTokenExtension { token_id, extension })?)
+        }
+        msg => Ok(contract.execute(deps, env, info, msg)?),
+    }
+}
+
+pub fn execute_set_traits(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    traits: Vec<Trait>,
+) -> Result<Response, ContractError> {
+    let contract = Cw721MetadataContract::default();
+    let mut token = contract.tokens.load(deps.storage, &token_id)?;
+    contract.check_metadata_not_frozen(deps.storage, &token_id)?;
+    let is_token_owner = token.owner == info.sender;
+
+    let metadata = token.extension.get_or_insert_with(Metadata::default);
+    let attributes = metadata.attributes.get_or_insert_with(Vec::new);
+    for new_trait in traits.iter() {
+        check_can_write_trait(deps.as_ref(), &info, is_token_owner, &new_trait.trait_type)?;
+        match attributes.iter_mut().find(|t| t.trait_type == new_trait.trait_type) {
+            Some(existing) => *existing = new_trait.clone(),
+            None => attributes.push(new_trait.clone()),
+        }
+    }
+    contract.tokens.save(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_traits")
+        .add_attribute("by", info.sender)
+        .add_attribute("token_id", token_id)
+        .add_attributes(traits.into_iter().map(|t| (format!("trait[{}]", t.trait_type), t.value))))
+}
+
+pub fn execute_remove_traits(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    trait_types: Vec<String>,
+) -> Result<Response, ContractError> {
+    let contract = Cw721MetadataContract::default();
+    let mut token = contract.tokens.load(deps.storage, &token_id)?;
+    contract.check_metadata_not_frozen(deps.storage, &token_id)?;
+    let is_token_owner = token.owner == info.sender;
+
+    for trait_type in trait_types.iter() {
+        check_can_write_trait(deps.as_ref(), &info, is_token_owner, trait_type)?;
+    }
+    if let Some(attributes) = token.extension.as_mut().and_then(|m| m.attributes.as_mut()) {
+        attributes.retain(|t| !trait_types.contains(&t.trait_type));
+    }
+    contract.tokens.save(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_traits")
+        .add_attribute("by", info.sender)
+        .add_attribute("token_id", token_id)
+        .add_attribute("trait_types", trait_types.join(",")))
+}
+
+/// Same permission as UpdateTokenExtension, but only touches the fields changed by update
+pub fn execute_update_fields<F>(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    action: &str,
+    update: F,
+) -> Result<Response, ContractError>
+where
+    F: FnOnce(&mut Metadata),
+{
+    let contract = Cw721MetadataContract::default();
+    if !is_minter(deps.as_ref(), &info) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut token = contract.tokens.load(deps.storage, &token_id)?;
+    contract.check_metadata_not_frozen(deps.storage, &token_id)?;
+    update(token.extension.get_or_insert_with(Metadata::default));
+    contract.tokens.save(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("by", info.sender)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn execute_set_trait_permission(
+    deps: DepsMut,
+    info: MessageInfo,
+    trait_type: String,
+    permission: TraitPermission,
+) -> Result<Response, ContractError> {
+    let contract = Cw721MetadataContract::default();
+    if !contract.is_contract_owner(deps.as_ref(), &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if load_trait_permission(deps.storage, &trait_type)? == TraitPermission::Immutable {
+        return Err(ContractError::TraitImmutable { trait_type });
+    }
+    TRAIT_PERMISSIONS.save(deps.storage, &trait_type, &permission)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_trait_permission")
+        .add_attribute("by", info.sender)
+        .add_attribute("trait_type", trait_type)
+        .add_attribute("permission", format!("{:?}", permission)))
+}
+
+pub fn execute_set_indexed_traits(
+    deps: DepsMut,
+    info: MessageInfo,
+    trait_types: Vec<String>,
+) -> Result<Response, ContractError> {
+    let contract = Cw721MetadataContract::default();
+    if !contract.is_contract_owner(deps.as_ref(), &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    // the index of existing tokens would go stale
+    if contract.get_last_running_id(deps.storage)? > 0 {
+        return Err(ContractError::IndexedTraitsLocked {});
+    }
+    trait_index().indexed.save(deps.storage, &trait_types)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_indexed_traits")
+        .add_attribute("by", info.sender)
+        .add_attribute("trait_types", trait_types.join(",")))
+}
+
+pub fn load_trait_permission(storage: &dyn Storage, trait_type: &str) -> StdResult<TraitPermission> {
+    Ok(TRAIT_PERMISSIONS.may_load(storage, trait_type)?.unwrap_or_default())
+}
+
+fn is_minter(deps: Deps, info: &MessageInfo) -> bool {
+    let contract = Cw721MetadataContract::default();
+    contract.is_contract_owner(deps, &info.sender)
+        || contract.check_as_cooperative(deps, &info.sender, true, false)
+}
+
+fn check_can_write_trait(
+    deps: Deps,
+    info: &MessageInfo,
+    is_token_owner: bool,
+    trait_type: &str,
+) -> Result<(), ContractError> {
+    match load_trait_permission(deps.storage, trait_type)? {
+        TraitPermission::Immutable => {
+            return Err(ContractError::TraitImmutable { trait_type: trait_type.to_string() });
+        }
+        TraitPermission::Minter => {
+            if !is_minter(deps, info) {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+        TraitPermission::TokenOwner => {
+            if !is_token_owner && !is_minter(deps, info) {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fails when an immutable trait would be added, changed or removed
+fn check_immutable_unchanged(
+    storage: &dyn Storage,
+    old: &Extension,
+    new: &Extension,
+) -> Result<(), ContractError> {
+    let empty = vec![];
+    let old_traits = old.as_ref().and_then(|m| m.attributes.as_ref()).unwrap_or(&empty);
+    let new_traits = new.as_ref().and_then(|m| m.attributes.as_ref()).unwrap_or(&empty);
+    for t in old_traits.iter().chain(new_traits.iter()) {
+        if load_trait_permission(storage, &t.trait_type)? != TraitPermission::Immutable {
+            continue;
+        }
+        let before = old_traits.iter().find(|o| o.trait_type == t.trait_type);
+        let after = new_traits.iter().find(|n| n.trait_type == t.trait_type);
+        if before != after {
+            return Err(ContractError::TraitImmutable { trait_type: t.trait_type.clone() });
+        }
+    }
+    Ok(())
+}
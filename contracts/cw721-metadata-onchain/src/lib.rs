@@ -1,7 +1,17 @@
+mod error;
+pub mod execute;
+pub mod msg;
+pub mod query;
+pub mod state;
+
 use cosmwasm_schema::cw_serde;
+use std::ops::Deref;
+
 use cosmwasm_std::{Empty};
 use cw2::set_contract_version;
-pub use cw721_base::{ContractError, InstantiateMsg};
+pub use cw721_base::{InstantiateMsg, MigrateMsg};
+pub use crate::error::ContractError;
+pub use crate::msg::{IndexedTraitsResponse, MetadataExecuteMsg, MetadataQueryMsg, TraitPermission};
 
 // Version info for migration
 const CONTRACT_NAME: &str = "crates.io:cw721-metadata-onchain";
@@ -31,9 +41,27 @@ pub struct Metadata {
 
 pub type Extension = Option<Metadata>;
 
-pub type Cw721MetadataContract<'a> = cw721_base::Cw721Contract<'a, Extension, Empty, Empty, Empty>;
-pub type ExecuteMsg = cw721::Cw721ExecuteMsg<Extension, Empty>;
-pub type QueryMsg = cw721::Cw721QueryMsg<Empty>;
+pub type ExecuteMsg = cw721::Cw721ExecuteMsg<Extension, MetadataExecuteMsg>;
+pub type QueryMsg = cw721::Cw721QueryMsg<MetadataQueryMsg>;
+
+type BaseContract<'a> = cw721_base::Cw721Contract<'a, Extension, Empty, MetadataExecuteMsg, MetadataQueryMsg>;
+
+/// cw721-base contract that also keeps the trait index of Metadata.attributes up to date
+pub struct Cw721MetadataContract<'a>(pub BaseContract<'a>);
+
+impl Default for Cw721MetadataContract<'static> {
+    fn default() -> Self {
+        Self(BaseContract::default().with_token_index(Box::new(state::trait_index())))
+    }
+}
+
+impl<'a> Deref for Cw721MetadataContract<'a> {
+    type Target = BaseContract<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
 #[cfg(not(feature = "library"))]
 pub mod entry {
@@ -41,6 +69,7 @@ pub mod entry {
 
     use cosmwasm_std::entry_point;
     use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cw_utils::ensure_from_older_version;
 
     // This makes a conscious choice on the various generics used by the contract
     #[entry_point]
@@ -63,12 +92,18 @@ pub mod entry {
         info: MessageInfo,
         msg: ExecuteMsg,
     ) -> Result<Response, ContractError> {
-        Cw721MetadataContract::default().execute(deps, env, info, msg)
+        execute::execute(deps, env, info, msg)
     }
 
     #[entry_point]
     pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-        Cw721MetadataContract::default().query(deps, env, msg)
+        query::query(deps, env, msg)
+    }
+
+    #[entry_point]
+    pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+        ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        Ok(Cw721MetadataContract::default().migrate(deps, env, msg)?)
     }
 }
 
@@ -77,8 +112,8 @@ mod tests {
     use super::*;
 
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::Uint128;
-    use cw721::Cw721Query;
+    use cosmwasm_std::{from_binary, Deps, Uint128};
+    use cw721::{Cw721Query, TokensResponse};
 
     const CREATOR: &str = "creator";
 
@@ -92,6 +127,8 @@ mod tests {
             name: "SpaceShips".to_string(),
             symbol: "SPACE".to_string(),
             max_supply : Uint128::from(10000u128),
+            transfer_policy : None,
+            reveal : None,
         };
         contract.instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
 
@@ -106,6 +143,7 @@ mod tests {
             token_owner: "john".to_string(),
             token_uri: token_uri.clone(),
             extension: token_metadata.clone(),
+            transfer_policy: None,
         };
 
         let res = contract.execute(deps.as_mut(), mock_env(), info, exec_msg).unwrap();
@@ -115,4 +153,198 @@ mod tests {
         assert_eq!(res.token_uri, token_uri);
         assert_eq!(res.extension, token_metadata);
     }
+
+    #[test]
+    fn patching_traits() {
+        let mut deps = mock_dependencies();
+        let contract = Cw721MetadataContract::default();
+
+        let info = mock_info(CREATOR, &[]);
+        let init_msg = InstantiateMsg {
+            name: "SpaceShips".to_string(),
+            symbol: "SPACE".to_string(),
+            max_supply : Uint128::from(10000u128),
+            transfer_policy : None,
+            reveal : None,
+        };
+        contract.instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
+
+        let trait_of = |trait_type: &str, value: &str| Trait {
+            display_type: None,
+            trait_type: trait_type.to_string(),
+            value: value.to_string(),
+        };
+        let exec_msg =  ExecuteMsg::Mint{
+            token_owner: "john".to_string(),
+            token_uri: None,
+            extension: Some(Metadata {
+                attributes: Some(vec![trait_of("class", "frigate"), trait_of("level", "1")]),
+                ..Metadata::default()
+            }),
+            transfer_policy: None,
+        };
+        let res = contract.execute(deps.as_mut(), mock_env(), info.clone(), exec_msg).unwrap();
+        let token_id = res.attributes[3].value.clone();
+
+        let set_permission = |trait_type: &str, permission: TraitPermission| ExecuteMsg::Extension {
+            msg: MetadataExecuteMsg::SetTraitPermission { trait_type: trait_type.to_string(), permission },
+        };
+        execute::execute(deps.as_mut(), mock_env(), info.clone(), set_permission("class", TraitPermission::Immutable)).unwrap();
+        execute::execute(deps.as_mut(), mock_env(), info.clone(), set_permission("nickname", TraitPermission::TokenOwner)).unwrap();
+        let err = execute::execute(deps.as_mut(), mock_env(), info.clone(), set_permission("class", TraitPermission::Minter)).unwrap_err();
+        assert_eq!(err, ContractError::TraitImmutable { trait_type: "class".to_string() });
+
+        // minter patches level, owner names the ship, nobody changes the class
+        let set_traits = |traits: Vec<Trait>| ExecuteMsg::Extension {
+            msg: MetadataExecuteMsg::SetTraits { token_id: token_id.clone(), traits },
+        };
+        execute::execute(deps.as_mut(), mock_env(), info.clone(), set_traits(vec![trait_of("level", "2")])).unwrap();
+        let err = execute::execute(deps.as_mut(), mock_env(), mock_info("john", &[]), set_traits(vec![trait_of("level", "99")])).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute::execute(deps.as_mut(), mock_env(), mock_info("john", &[]), set_traits(vec![trait_of("nickname", "Rocinante")])).unwrap();
+        let err = execute::execute(deps.as_mut(), mock_env(), info.clone(), set_traits(vec![trait_of("class", "cruiser")])).unwrap_err();
+        assert_eq!(err, ContractError::TraitImmutable { trait_type: "class".to_string() });
+
+        execute::execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Extension {
+            msg: MetadataExecuteMsg::UpdateImage { token_id: token_id.clone(), image: Some("ipfs://ship.png".to_string()) },
+        }).unwrap();
+
+        let res = contract.token_info(deps.as_ref(), token_id.clone()).unwrap();
+        let metadata = res.extension.unwrap();
+        assert_eq!(metadata.image, Some("ipfs://ship.png".to_string()));
+        assert_eq!(
+            metadata.attributes,
+            Some(vec![trait_of("class", "frigate"), trait_of("level", "2"), trait_of("nickname", "Rocinante")])
+        );
+
+        // birth traits can not be removed or replaced through the whole extension either
+        let err = execute::execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Extension {
+            msg: MetadataExecuteMsg::RemoveTraits { token_id: token_id.clone(), trait_types: vec!["class".to_string()] },
+        }).unwrap_err();
+        assert_eq!(err, ContractError::TraitImmutable { trait_type: "class".to_string() });
+        let err = execute::execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::UpdateTokenExtension {
+            token_id: token_id.clone(),
+            extension: Some(Metadata::default()),
+        }).unwrap_err();
+        assert_eq!(err, ContractError::TraitImmutable { trait_type: "class".to_string() });
+
+        execute::execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Extension {
+            msg: MetadataExecuteMsg::RemoveTraits { token_id: token_id.clone(), trait_types: vec!["nickname".to_string()] },
+        }).unwrap();
+        let res = contract.token_info(deps.as_ref(), token_id).unwrap();
+        assert_eq!(
+            res.extension.unwrap().attributes,
+            Some(vec![trait_of("class", "frigate"), trait_of("level", "2")])
+        );
+    }
+
+    #[test]
+    fn searching_by_trait() {
+        let mut deps = mock_dependencies();
+        let contract = Cw721MetadataContract::default();
+
+        let info = mock_info(CREATOR, &[]);
+        let init_msg = InstantiateMsg {
+            name: "SpaceShips".to_string(),
+            symbol: "SPACE".to_string(),
+            max_supply : Uint128::from(10000u128),
+            transfer_policy : None,
+            reveal : None,
+        };
+        contract.instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
+        let set_indexed = ExecuteMsg::Extension {
+            msg: MetadataExecuteMsg::SetIndexedTraits { trait_types: vec!["rarity".to_string()] },
+        };
+        execute::execute(deps.as_mut(), mock_env(), info.clone(), set_indexed.clone()).unwrap();
+
+        let mut token_ids = vec![];
+        for (owner, rarity) in [("john", "legendary"), ("jane", "common"), ("john", "common"), ("jane", "legendary")] {
+            let exec_msg =  ExecuteMsg::Mint{
+                token_owner: owner.to_string(),
+                token_uri: None,
+                extension: Some(Metadata {
+                    attributes: Some(vec![
+                        Trait { display_type: None, trait_type: "rarity".to_string(), value: rarity.to_string() },
+                        Trait { display_type: None, trait_type: "color".to_string(), value: "red".to_string() },
+                    ]),
+                    ..Metadata::default()
+                }),
+                transfer_policy: None,
+            };
+            // minting through the plain contract keeps the index too
+            let res = contract.execute(deps.as_mut(), mock_env(), info.clone(), exec_msg).unwrap();
+            token_ids.push(res.attributes[3].value.clone());
+        }
+
+        // too late to change the index now
+        let err = execute::execute(deps.as_mut(), mock_env(), info.clone(), set_indexed).unwrap_err();
+        assert_eq!(err, ContractError::IndexedTraitsLocked {});
+
+        let by_trait = |deps: Deps, msg: MetadataQueryMsg| -> Vec<String> {
+            let res: TokensResponse = from_binary(&query::query(deps, mock_env(), QueryMsg::Extension { msg }).unwrap()).unwrap();
+            res.tokens
+        };
+        let legendary = by_trait(deps.as_ref(), MetadataQueryMsg::TokensByTrait {
+            trait_type: "rarity".to_string(),
+            value: "legendary".to_string(),
+            start_after: None,
+            limit: None,
+        });
+        assert_eq!(legendary, vec![token_ids[0].clone(), token_ids[3].clone()]);
+        let page = by_trait(deps.as_ref(), MetadataQueryMsg::TokensByTrait {
+            trait_type: "rarity".to_string(),
+            value: "legendary".to_string(),
+            start_after: Some(token_ids[0].clone()),
+            limit: Some(1),
+        });
+        assert_eq!(page, vec![token_ids[3].clone()]);
+        // color is not indexed
+        let red = by_trait(deps.as_ref(), MetadataQueryMsg::TokensByTrait {
+            trait_type: "color".to_string(),
+            value: "red".to_string(),
+            start_after: None,
+            limit: None,
+        });
+        assert!(red.is_empty());
+
+        // index follows transfers and trait patches
+        execute::execute(deps.as_mut(), mock_env(), mock_info("john", &[]), ExecuteMsg::Transfer {
+            to: "jane".to_string(),
+            token_id: token_ids[0].clone(),
+        }).unwrap();
+        execute::execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Extension {
+            msg: MetadataExecuteMsg::SetTraits {
+                token_id: token_ids[3].clone(),
+                traits: vec![Trait { display_type: None, trait_type: "rarity".to_string(), value: "common".to_string() }],
+            },
+        }).unwrap();
+        let jane_legendary = by_trait(deps.as_ref(), MetadataQueryMsg::TokensByOwnerAndTrait {
+            owner: "jane".to_string(),
+            trait_type: "rarity".to_string(),
+            value: "legendary".to_string(),
+            start_after: None,
+            limit: None,
+        });
+        assert_eq!(jane_legendary, vec![token_ids[0].clone()]);
+        let john_legendary = by_trait(deps.as_ref(), MetadataQueryMsg::TokensByOwnerAndTrait {
+            owner: "john".to_string(),
+            trait_type: "rarity".to_string(),
+            value: "legendary".to_string(),
+            start_after: None,
+            limit: None,
+        });
+        assert!(john_legendary.is_empty());
+        let common = by_trait(deps.as_ref(), MetadataQueryMsg::TokensByTrait {
+            trait_type: "rarity".to_string(),
+            value: "common".to_string(),
+            start_after: None,
+            limit: None,
+        });
+        assert_eq!(common, vec![token_ids[1].clone(), token_ids[2].clone(), token_ids[3].clone()]);
+
+        let res: IndexedTraitsResponse = from_binary(
+            &query::query(deps.as_ref(), mock_env(), QueryMsg::Extension { msg: MetadataQueryMsg::IndexedTraits {} }).unwrap()
+        ).unwrap();
+        assert_eq!(res.trait_types, vec!["rarity".to_string()]);
+    }
 }
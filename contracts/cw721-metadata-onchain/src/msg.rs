@@ -0,0 +1,79 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cw721::TokensResponse;
+
+use crate::Trait;
+
+/// Who may write a trait_type after mint.
+#[cw_serde]
+#[derive(Default)]
+pub enum TraitPermission {
+    /// "Birth" trait, only set at mint and never changed afterwards
+    Immutable,
+    /// Contract owner and cooperatives that can mint
+    #[default]
+    Minter,
+    /// Minters and the token owner
+    TokenOwner,
+}
+
+/// Sent inside `ExecuteMsg::Extension { msg }`
+#[cw_serde]
+pub enum MetadataExecuteMsg {
+    /// Adds traits or replaces the value of traits with the same trait_type,
+    /// other attributes are left untouched.
+    SetTraits { token_id: String, traits: Vec<Trait> },
+
+    /// Removes the traits with these trait_types, missing ones are ignored
+    RemoveTraits { token_id: String, trait_types: Vec<String> },
+
+    /// Replaces only Metadata.image
+    UpdateImage { token_id: String, image: Option<String> },
+
+    /// Replaces only Metadata.description
+    UpdateDescription { token_id: String, description: Option<String> },
+
+    /// Owner sets who may write trait_type. Immutable can not be changed afterwards.
+    SetTraitPermission { trait_type: String, permission: TraitPermission },
+
+    /// Owner chooses the trait types searchable with TokensByTrait.
+    /// Only allowed before the first mint.
+    SetIndexedTraits { trait_types: Vec<String> },
+}
+
+impl cosmwasm_std::CustomMsg for MetadataExecuteMsg {}
+
+/// Sent inside `QueryMsg::Extension { msg }`
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum MetadataQueryMsg {
+    /// Tokens with trait_type = value, trait_type must be indexed.
+    /// Supports pagination.
+    #[returns(TokensResponse)]
+    TokensByTrait {
+        trait_type: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Same as TokensByTrait, only tokens of owner
+    #[returns(TokensResponse)]
+    TokensByOwnerAndTrait {
+        owner: String,
+        trait_type: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Trait types chosen by SetIndexedTraits
+    #[returns(IndexedTraitsResponse)]
+    IndexedTraits {},
+}
+
+impl cosmwasm_std::CustomMsg for MetadataQueryMsg {}
+
+#[cw_serde]
+pub struct IndexedTraitsResponse {
+    pub trait_types: Vec<String>,
+}
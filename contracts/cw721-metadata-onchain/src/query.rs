@@ -0,0 +1,75 @@
+use cosmwasm_std::{to_binary, Binary, Deps, Env, Order, StdResult};
+use cw721::TokensResponse;
+use cw_storage_plus::Bound;
+
+use crate::msg::{IndexedTraitsResponse, MetadataQueryMsg};
+use crate::state::trait_index;
+use crate::{Cw721MetadataContract, QueryMsg};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let contract = Cw721MetadataContract::default();
+    match msg {
+        QueryMsg::Extension { msg } => match msg {
+            MetadataQueryMsg::TokensByTrait { trait_type, value, start_after, limit } => {
+                to_binary(&query_tokens_by_trait(deps, trait_type, value, start_after, limit)?)
+            }
+            MetadataQueryMsg::TokensByOwnerAndTrait { owner, trait_type, value, start_after, limit } => {
+                to_binary(&query_tokens_by_owner_and_trait(deps, owner, trait_type, value, start_after, limit)?)
+            }
+            MetadataQueryMsg::IndexedTraits {} => to_binary(&query_indexed_traits(deps)?),
+        },
+        msg => contract.query(deps, env, msg),
+    }
+}
+
+/// Token ids that carry trait_type = value, only for indexed trait types
+pub fn query_tokens_by_trait(
+    deps: Deps,
+    trait_type: String,
+    value: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let tokens = trait_index()
+        .by_trait
+        .prefix((&trait_type, &value))
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TokensResponse { tokens })
+}
+
+/// Same as query_tokens_by_trait, limited to tokens of owner
+pub fn query_tokens_by_owner_and_trait(
+    deps: Deps,
+    owner: String,
+    trait_type: String,
+    value: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let tokens = trait_index()
+        .by_owner_trait
+        .prefix(((&trait_type, &value), &owner_addr))
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TokensResponse { tokens })
+}
+
+pub fn query_indexed_traits(deps: Deps) -> StdResult<IndexedTraitsResponse> {
+    let trait_types = trait_index().indexed.may_load(deps.storage)?.unwrap_or_default();
+    Ok(IndexedTraitsResponse { trait_types })
+}
@@ -0,0 +1,64 @@
+use cosmwasm_std::{Addr, Empty, StdError, StdResult, Storage};
+use cw721_base::state::TokenInfo;
+use cw_storage_plus::{Index, Item, Map};
+
+use crate::msg::TraitPermission;
+use crate::Extension;
+
+/// `trait_type -> permission`, trait types not in here use TraitPermission::default()
+pub const TRAIT_PERMISSIONS: Map<&str, TraitPermission> = Map::new("trait_permissions");
+
+/// Secondary index over the (trait_type, value) pairs of Metadata.attributes.
+/// Only trait types listed in `indexed` are stored, so the list must not change once tokens exist.
+pub struct TraitIndex<'a> {
+    /// Chosen trait types
+    pub indexed: Item<'a, Vec<String>>,
+    /// (trait_type, value, token_id)
+    pub by_trait: Map<'a, (&'a str, &'a str, &'a str), Empty>,
+    /// ((trait_type, value), owner, token_id)
+    pub by_owner_trait: Map<'a, ((&'a str, &'a str), &'a Addr, &'a str), Empty>,
+}
+
+pub fn trait_index<'a>() -> TraitIndex<'a> {
+    TraitIndex {
+        indexed: Item::new("tokens__indexed_traits"),
+        by_trait: Map::new("tokens__trait"),
+        by_owner_trait: Map::new("tokens__owner_trait"),
+    }
+}
+
+impl<'a> TraitIndex<'a> {
+    /// Attributes of extension whose trait_type is indexed
+    fn indexed_traits(&self, store: &dyn Storage, extension: &Extension) -> StdResult<Vec<(String, String)>> {
+        let attributes = match extension.as_ref().and_then(|metadata| metadata.attributes.as_ref()) {
+            Some(attributes) if !attributes.is_empty() => attributes,
+            _ => return Ok(vec![]),
+        };
+        let indexed = self.indexed.may_load(store)?.unwrap_or_default();
+        Ok(attributes
+            .iter()
+            .filter(|t| indexed.contains(&t.trait_type))
+            .map(|t| (t.trait_type.clone(), t.value.clone()))
+            .collect())
+    }
+}
+
+impl<'a> Index<TokenInfo<Extension>> for TraitIndex<'a> {
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &TokenInfo<Extension>) -> StdResult<()> {
+        let token_id = std::str::from_utf8(pk).map_err(StdError::invalid_utf8)?;
+        for (trait_type, value) in self.indexed_traits(store, &data.extension)? {
+            self.by_trait.save(store, (&trait_type, &value, token_id), &Empty {})?;
+            self.by_owner_trait.save(store, ((&trait_type, &value), &data.owner, token_id), &Empty {})?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &TokenInfo<Extension>) -> StdResult<()> {
+        let token_id = std::str::from_utf8(pk).map_err(StdError::invalid_utf8)?;
+        for (trait_type, value) in self.indexed_traits(store, &old_data.extension)? {
+            self.by_trait.remove(store, (&trait_type, &value, token_id));
+            self.by_owner_trait.remove(store, ((&trait_type, &value), &old_data.owner, token_id));
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use cosmwasm_std::{Empty};
 use cw2::set_contract_version;
-pub use cw721_base::{ContractError, InstantiateMsg};
+pub use cw721_base::{ContractError, InstantiateMsg, MigrateMsg};
 
 // Version info for migration
 const CONTRACT_NAME: &str = "crates.io:cw721-metadata-url";
@@ -19,6 +19,7 @@ pub mod entry {
 
     use cosmwasm_std::entry_point;
     use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cw_utils::ensure_from_older_version;
 
     // This makes a conscious choice on the various generics used by the contract
     #[entry_point]
@@ -48,6 +49,12 @@ pub mod entry {
     pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         Cw721URLContract::default().query(deps, env, msg)
     }
+
+    #[entry_point]
+    pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+        ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        Cw721URLContract::default().migrate(deps, env, msg)
+    }
 }
 
 #[cfg(test)]
@@ -69,6 +76,8 @@ mod tests {
             name: "Vehicles".to_string(),
             symbol: "VEHICLE".to_string(),
             max_supply : Uint128::from(10000u128),
+            transfer_policy : None,
+            reveal : None,
         };
         contract.instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
 
@@ -77,6 +86,7 @@ mod tests {
             token_owner: "john".to_string(),
             token_uri: token_uri.clone(),
             extension: Some(Empty{}),
+            transfer_policy: None,
         };
 
         let res = contract.execute(deps.as_mut(), mock_env(), info, exec_msg).unwrap();
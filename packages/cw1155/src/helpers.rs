@@ -5,7 +5,9 @@ use cosmwasm_std::{
 use crate::{
     TokenId,BalanceResponse,BatchBalanceResponse,AllBalanceResponse,OperatorsResponse,Approval,
     TokenInfoResponse,TokenSupplyResponse,TokensResponse,TokenSuppliesResponse,ContractInfoResponse,
-    AllowanceResponse,
+    AllowanceResponse,StatsResponse,IsMetadataFrozenResponse,IsVoucherRedeemedResponse,OwnerOfResponse,
+    TokenAllowanceResponse,TokenAllowanceInfo,TokenAllowancesResponse,CooperativeScopeResponse,
+    TokenDefinitionInfo,TokenDefinitionsResponse,RoyaltyInfoResponse,
 };
 use serde::de::DeserializeOwned;
 use cosmwasm_std::Binary;
@@ -106,6 +108,15 @@ impl Cw1155Contract {
         self.query(querier, req)
     }
 
+    pub fn owner_of(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: String,
+    ) -> StdResult<OwnerOfResponse> {
+        let req = Cw1155QueryMsg::OwnerOf { token_id };
+        self.query(querier, req)
+    }
+
     pub fn token_supply(
         &self,
         querier: &QuerierWrapper,
@@ -116,6 +127,30 @@ impl Cw1155Contract {
         self.query(querier, req)
     }
 
+    pub fn stats(&self, querier: &QuerierWrapper, token_id: String) -> StdResult<StatsResponse> {
+        let req = Cw1155QueryMsg::Stats { token_id };
+        self.query(querier, req)
+    }
+
+    pub fn is_metadata_frozen(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: Option<TokenId>,
+    ) -> StdResult<IsMetadataFrozenResponse> {
+        let req = Cw1155QueryMsg::IsMetadataFrozen { token_id };
+        self.query(querier, req)
+    }
+
+    pub fn is_voucher_redeemed(
+        &self,
+        querier: &QuerierWrapper,
+        signer: String,
+        nonce: u64,
+    ) -> StdResult<IsVoucherRedeemedResponse> {
+        let req = Cw1155QueryMsg::IsVoucherRedeemed { signer, nonce };
+        self.query(querier, req)
+    }
+
     pub fn token_supplies(
         &self,
         querier: &QuerierWrapper,
@@ -172,15 +207,86 @@ impl Cw1155Contract {
         self.query(querier, req)
     }
 
+    pub fn tokens_by_category(
+        &self,
+        querier: &QuerierWrapper,
+        category: String,
+        start_after: Option<TokenId>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        let req = Cw1155QueryMsg::TokensByCategory { category, start_after, limit };
+        self.query(querier, req)
+    }
+
+    pub fn token_definitions(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<TokenId>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<TokenDefinitionInfo>> {
+        let req = Cw1155QueryMsg::TokenDefinitions { start_after, limit };
+        let res: TokenDefinitionsResponse = self.query(querier, req)?;
+        Ok(res.definitions)
+    }
+
+    pub fn royalty_info(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: TokenId,
+        sale_price: Uint128,
+    ) -> StdResult<RoyaltyInfoResponse> {
+        let req = Cw1155QueryMsg::RoyaltyInfo { token_id, sale_price };
+        self.query(querier, req)
+    }
+
+    pub fn cooperative_scope(
+        &self,
+        querier: &QuerierWrapper,
+        address: String,
+    ) -> StdResult<CooperativeScopeResponse> {
+        let req = Cw1155QueryMsg::CooperativeScope { address };
+        self.query(querier, req)
+    }
+
+    pub fn token_allowance(
+        &self,
+        querier: &QuerierWrapper,
+        owner: String,
+        operator: String,
+        token_id: TokenId,
+    ) -> StdResult<TokenAllowanceResponse> {
+        let req = Cw1155QueryMsg::TokenAllowance { owner, operator, token_id };
+        self.query(querier, req)
+    }
+
+    pub fn token_allowances(
+        &self,
+        querier: &QuerierWrapper,
+        owner: String,
+        include_expired: bool,
+        start_after: Option<(String, TokenId)>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<TokenAllowanceInfo>> {
+        let req = Cw1155QueryMsg::TokenAllowances {
+            owner,
+            include_expired,
+            start_after,
+            limit,
+        };
+        let res: TokenAllowancesResponse = self.query(querier, req)?;
+        Ok(res.allowances)
+    }
+
     /*** Calls ***/
     pub fn mint_for (
         &self,
         to: String,
         token_id: TokenId,
         amount: Uint128,
+        msg: Option<Binary>,
         ) -> StdResult<CosmosMsg> {
 
-        let mint_msg = Cw1155ExecuteMsg::Mint{to,token_id,amount};
+        let mint_msg = Cw1155ExecuteMsg::Mint{to,token_id,amount,msg};
         Ok(self.call(mint_msg)?)
     }
     
@@ -188,9 +294,10 @@ impl Cw1155Contract {
         &self,
         to: String,
         batch: Vec<(TokenId, Uint128)>,
+        msg: Option<Binary>,
         ) -> StdResult<CosmosMsg> {
             
-        let mint_msg = Cw1155ExecuteMsg::BatchMint{to,batch};
+        let mint_msg = Cw1155ExecuteMsg::BatchMint{to,batch,msg};
         Ok(self.call(mint_msg)?)
     }
 
@@ -1,8 +1,12 @@
 pub use cw_utils::Expiration;
-pub use crate::msg::{Cw1155ExecuteMsg, TokenId};
+pub use crate::msg::{
+    CooperativeScope, Cw1155ExecuteMsg, MintVoucher, Royalty, TokenDefinition, TokenId, TokenType, TransferPolicy,
+};
 pub use crate::query::{
-    Approval, OperatorsResponse, BalanceResponse, BatchBalanceResponse, Cw1155QueryMsg,ContractInfoResponse,
-    AllowanceResponse, TokenInfoResponse, TokensResponse,AllBalanceResponse,TokenSupplyResponse,TokenSupply,TokenSuppliesResponse,
+    Approval, ClawbackLogEntry, ClawbackLogResponse, OperatorsResponse, BalanceResponse, BatchBalanceResponse, Cw1155QueryMsg,ContractInfoResponse,
+    AllowanceResponse, TokenInfoResponse, TokensResponse,AllBalanceResponse,TokenSupplyResponse,TokenSupply,TokenSuppliesResponse,TransferPolicyResponse,StatsResponse,IsMetadataFrozenResponse,IsVoucherRedeemedResponse,OwnerOfResponse,
+    TokenAllowanceResponse,TokenAllowanceInfo,TokenAllowancesResponse,CooperativeScopeResponse,
+    TokenDefinitionInfo,TokenDefinitionsResponse,RoyaltyInfoResponse,
 };
 pub use crate::receiver::{Cw1155BatchReceiveMsg, Cw1155ReceiveMsg};
 
@@ -1,11 +1,80 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Binary, Uint128};
+use cosmwasm_std::{Binary, Decimal, Uint128};
 use cw_utils::Expiration;
 
 pub type TokenId = String;
 
+/// Decides who may move a token between accounts. Mint and burn are never restricted.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferPolicy {
+    /// Anyone allowed by the usual approval rules can transfer
+    #[default]
+    Free,
+    /// Can never be transferred, only minted and burned
+    Soulbound,
+    /// Only cooperative contracts (and the contract owner) can transfer
+    CooperativeOnly,
+    /// Tradable until the first transfer, received amounts are locked to the receiver
+    BindOnTransfer,
+}
+
+/// Royalty owed on secondary sales of a token, as a share of the sale price
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct Royalty {
+    pub payment_address: String,
+    /// At most 1
+    pub share: Decimal,
+}
+
+/// One token of DefineTokens
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct TokenDefinition {
+    /// None takes the next running number. Chosen ids cannot be numbers
+    /// or end in `:<number>`, which is how serials are named.
+    pub token_id: Option<TokenId>,
+    pub token_uri: Option<String>,
+    pub max_supply: Uint128,
+    pub transfer_policy: Option<TransferPolicy>,
+    pub token_type: Option<TokenType>,
+    pub category: Option<String>,
+    pub royalty: Option<Royalty>,
+}
+
+/// Token ids a cooperative may mint and burn. Serials are covered through their base type.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CooperativeScope {
+    /// Every token id in the contract
+    #[default]
+    All,
+    TokenIds { token_ids: Vec<TokenId> },
+    /// Token ids the owner put in category
+    Category { category: String },
+}
+
+/// How amounts of a token id behave, set once at DefineToken
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    /// Stackable, decimals only tell clients how to display amounts
+    Fungible { decimals: u8 },
+    /// Unique items, minted one at a time and with an OwnerOf. With serials the id is a base type:
+    /// every mint of it creates the next `<base>:<serial>` id and max_supply caps the serials.
+    /// Without serials the id itself is the item and max_supply must be 1.
+    NonFungible { serials: bool },
+}
+
+impl Default for TokenType {
+    fn default() -> Self {
+        TokenType::Fungible { decimals: 0 }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw1155ExecuteMsg {
@@ -69,16 +138,20 @@ pub enum Cw1155ExecuteMsg {
 
     /// Mint is a base message to mint tokens.
     Mint {
-        /// If `to` is not contract, `msg` should be `None`
         to: String,
         token_id: TokenId,
         amount: Uint128,
+        /// If `to` is not contract, `msg` should be `None`.
+        /// Otherwise `to` gets a Cw1155ReceiveMsg with `from: None`.
+        msg: Option<Binary>,
     },
     /// BatchMint is a base message to mint multiple types of tokens in batch.
     BatchMint {
-        /// If `to` is not contract, `msg` should be `None`
         to: String,
         batch: Vec<(TokenId, Uint128)>,
+        /// If `to` is not contract, `msg` should be `None`.
+        /// Otherwise `to` gets a Cw1155BatchReceiveMsg with `from: None`.
+        msg: Option<Binary>,
     },
     /// Burn is a base message to burn tokens.
     Burn {
@@ -100,16 +173,98 @@ pub enum Cw1155ExecuteMsg {
     /// Remove previously granted ApproveAll permission
     RevokeAll { operator: String },
 
-    // URI to define token
-    DefineToken {token_uri : String, max_supply : Uint128},
+    /// Allows operator to move up to amount of token_id out of the owner's account,
+    /// every transfer spends from it like a cw20 allowance. Replaces any earlier amount, zero removes it.
+    /// ApproveAll, when granted to a cooperative, is used instead and spends nothing.
+    /// Burning for the owner also spends it, but needs a cooperative that can burn.
+    Approve {
+        operator: String,
+        token_id: TokenId,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+
+    /// Deletes expired ApproveAll permissions, looking at no more than limit entries.
+    /// Sweeps of one owner's operators, or of all of them, continue where the previous call stopped.
+    /// Anyone can call this.
+    PruneExpired { owner: Option<String>, limit: u32 },
+    /// Marks up to limit tokens defined before a migration so TokenDefinitions lists them,
+    /// starting where the previous call stopped. Anyone can call this.
+    MigrateDefinitions { limit: u32 },
+    /// Counts the holders of up to limit balances left from before stats, resuming where
+    /// the previous call stopped. Stats holders are only right once the last page is done.
+    /// Anyone can call this.
+    MigrateHolders { limit: u32 },
+
+    // URI to define token, without transfer_policy the collection policy applies
+    // Without token_uri the collection base_uri applies, without token_type it is Fungible with 0 decimals
+    // category and royalty work as in TokenDefinition
+    DefineToken {token_uri : Option<String>, max_supply : Uint128,
+        #[serde(default)]
+        transfer_policy : Option<TransferPolicy>,
+        token_type : Option<TokenType>,
+        #[serde(default)]
+        category : Option<String>,
+        #[serde(default)]
+        royalty : Option<Royalty>},
+
+    /// Owner defines many tokens at once, all or nothing. Unset fields work as in DefineToken.
+    DefineTokens { tokens: Vec<TokenDefinition> },
 
-    // Update token uri
-    UpdateTokenUri {token_id : TokenId,token_uri : String},
+    // Update token uri, None falls back to the collection base_uri
+    UpdateTokenUri {token_id : TokenId,token_uri : Option<String>},
+
+    /// Owner sets the uri of tokens without their own token_uri, `{id}` is replaced by the
+    /// token id as 64 lowercase hex digits (ERC-1155 metadata), or else the id is appended.
+    /// Blocked once every token is frozen.
+    UpdateBaseUri { base_uri: Option<String> },
+
+    /// Owner permanently blocks uri updates of token_id,
+    /// or of every token when token_id is None. Cannot be undone.
+    FreezeMetadata { token_id: Option<TokenId> },
 
     // Update token max supply
     UpdateMaxSupply {token_id : TokenId,max_supply : Uint128},
 
-    SetCooperative { cooperative : String , can_mint_for : bool , can_burn_from : bool},
+    /// Owner puts token_id in category, None takes it out. Serials share the category of their base type.
+    SetTokenCategory { token_id : TokenId, category : Option<String> },
+
+    // Without scope the cooperative mints and burns every token id
+    SetCooperative { cooperative : String , can_mint_for : bool , can_burn_from : bool, scope : Option<CooperativeScope>},
+
+    UnsetCooperative { cooperative : String },
+
+    /// Owner moves tokens out of any account without approvals, e.g. to recover stolen items.
+    /// Every use is recorded in the clawback log.
+    ForceTransfer {
+        from: String,
+        to: String,
+        token_id: TokenId,
+        amount: Uint128,
+        reason: String,
+    },
+
+    /// Owner permanently gives up ForceTransfer, cannot be undone.
+    RenounceClawback {},
 
-    UnsetCooperative { cooperative : String }
+    /// Sender registers the secp256k1 public key its vouchers are signed with, None removes it.
+    /// The key is 33 bytes compressed or 65 bytes uncompressed.
+    /// Vouchers only mint while the sender is owner or a cooperative that can mint.
+    SetVoucherSigner { pubkey: Option<Binary> },
+
+    /// Mints the voucher to its recipient, anyone can submit it.
+    /// signature is over sha256 of the contract address followed by the JSON voucher.
+    RedeemVoucher { voucher: MintVoucher, signature: Binary },
+}
+
+/// Off-chain mint permission, each nonce of a signer can be redeemed once
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct MintVoucher {
+    pub signer: String,
+    pub recipient: String,
+    pub token_id: TokenId,
+    pub amount: Uint128,
+    pub nonce: u64,
+    pub expires: Expiration,
 }
@@ -1,9 +1,9 @@
 use cosmwasm_schema::{cw_serde,QueryResponses};
 
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Timestamp, Uint128};
 use cw_utils::Expiration;
 
-use crate::msg::TokenId;
+use crate::msg::{CooperativeScope, Royalty, TokenId, TokenType, TransferPolicy};
 
 #[cw_serde]
 pub struct TokenSupply {
@@ -59,12 +59,31 @@ pub enum Cw1155QueryMsg {
     #[returns(AllowanceResponse)]
     Allowance { owner: String, operator: String },
 
+    /// Returns what is left of the owner's Approve for operator on token_id, zero if unset.
+    #[returns(TokenAllowanceResponse)]
+    TokenAllowance { owner: String, operator: String, token_id: TokenId },
+
+    /// Lists the owner's per token approvals ordered by operator, then token id.
+    /// Supports pagination, start_after is `(operator, token_id)`.
+    #[returns(TokenAllowancesResponse)]
+    TokenAllowances {
+        owner: String,
+        /// unset or false will filter out expired approvals, you must set to true to see them
+        include_expired: bool,
+        start_after: Option<(String, TokenId)>,
+        limit: Option<u32>,
+    },
+
     /// With MetaData Extension.
     /// Query metadata of token
     /// Return type: TokenInfoResponse.
     #[returns(TokenInfoResponse)]
     TokenInfo { token_id: TokenId },
 
+    /// Returns the holder of a NonFungible token, fails for fungible and unminted ids
+    #[returns(OwnerOfResponse)]
+    OwnerOf { token_id: TokenId },
+
     /// With Enumerable extension.
     /// Returns all tokens owned by the given address, [] if unset.
     /// Return type: TokensResponse.
@@ -84,6 +103,26 @@ pub enum Cw1155QueryMsg {
         limit: Option<u32>,
     },
 
+    /// Lists the token ids in category. Supports pagination.
+    #[returns(TokensResponse)]
+    TokensByCategory {
+        category: String,
+        start_after: Option<TokenId>,
+        limit: Option<u32>,
+    },
+
+    /// Lists every defined token with its settings. Supports pagination.
+    #[returns(TokenDefinitionsResponse)]
+    TokenDefinitions {
+        start_after: Option<TokenId>,
+        limit: Option<u32>,
+    },
+
+    /// Returns who is owed what on a sale of token_id for sale_price,
+    /// serials use the royalty of their base type. Empty address when there is none.
+    #[returns(RoyaltyInfoResponse)]
+    RoyaltyInfo { token_id: TokenId, sale_price: Uint128 },
+
     /// With MetaData Extension.
     /// Returns top-level metadata about the contract
     /// Return type : ContractInfoResponse
@@ -98,6 +137,40 @@ pub enum Cw1155QueryMsg {
 
     #[returns(LastTokenIdResponse)]
     LastTokenID {},
+
+    /// Returns transfer policy of the token, or of the collection when token_id is not set.
+    #[returns(TransferPolicyResponse)]
+    TransferPolicy { token_id: Option<TokenId> },
+
+    /// Returns how much of the owner's balance is locked by BindOnTransfer.
+    #[returns(BalanceResponse)]
+    BoundBalance { owner: String, token_id: TokenId },
+
+    /// Returns recorded ForceTransfer uses, oldest first. Supports pagination.
+    #[returns(ClawbackLogResponse)]
+    ClawbackLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Returns mint, burn, transfer and holder counts of the token.
+    /// On a contract migrated from before stats, mints, burns and transfers count from the upgrade on
+    /// and holders are only complete once MigrateHolders is done.
+    #[returns(StatsResponse)]
+    Stats { token_id: TokenId },
+
+    /// Returns whether the uri of token_id can no longer be updated,
+    /// with token_id None whether the whole collection is frozen
+    #[returns(IsMetadataFrozenResponse)]
+    IsMetadataFrozen { token_id: Option<TokenId> },
+
+    /// Returns the rights of a cooperative and the token ids they cover
+    #[returns(CooperativeScopeResponse)]
+    CooperativeScope { address: String },
+
+    /// Returns whether the voucher with this signer and nonce was redeemed
+    #[returns(IsVoucherRedeemedResponse)]
+    IsVoucherRedeemed { signer: String, nonce: u64 },
 }
 
 #[cw_serde]
@@ -105,6 +178,8 @@ pub struct ContractInfoResponse {
     pub name: String,
     pub symbol: String,
     pub owner : String,
+    /// Uri template of tokens without their own token_uri
+    pub base_uri : Option<String>,
 }
 
 #[cw_serde]
@@ -141,10 +216,68 @@ pub struct AllowanceResponse {
     pub approved: bool
 }
 
+#[cw_serde]
+pub struct TokenDefinitionInfo {
+    pub token_id: TokenId,
+    /// The token's own uri, None when it uses the collection base_uri
+    pub token_uri: Option<String>,
+    pub supply: TokenSupply,
+    /// Resolved policy, the collection one when the token has none
+    pub transfer_policy: TransferPolicy,
+    pub token_type: TokenType,
+    pub category: Option<String>,
+    pub royalty: Option<Royalty>,
+}
+
+#[cw_serde]
+pub struct TokenDefinitionsResponse {
+    pub definitions: Vec<TokenDefinitionInfo>,
+}
+
+#[cw_serde]
+pub struct RoyaltyInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[cw_serde]
+pub struct CooperativeScopeResponse {
+    pub can_mint_for: bool,
+    pub can_burn_from: bool,
+    pub scope: CooperativeScope,
+}
+
+#[cw_serde]
+pub struct TokenAllowanceResponse {
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct TokenAllowanceInfo {
+    pub operator: String,
+    pub token_id: TokenId,
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct TokenAllowancesResponse {
+    pub allowances: Vec<TokenAllowanceInfo>,
+}
+
 #[cw_serde]
 pub struct TokenInfoResponse {
     /// Should be a url point to a json file
+    /// The token's own uri, or else the one resolved from the collection base_uri.
+    /// Empty when neither is set.
     pub url: String,
+    pub token_type: TokenType,
+}
+
+#[cw_serde]
+pub struct OwnerOfResponse {
+    pub owner: String,
 }
 
 #[cw_serde]
@@ -170,4 +303,51 @@ pub struct TokensResponse {
 #[cw_serde]
 pub struct LastTokenIdResponse {
     pub token_id: Uint128,
-}
\ No newline at end of file
+}
+#[cw_serde]
+pub struct ClawbackLogEntry {
+    pub id: u64,
+    /// Who executed the ForceTransfer
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    pub token_id: TokenId,
+    pub amount: Uint128,
+    pub reason: String,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct ClawbackLogResponse {
+    /// True once the owner renounced clawback, no new entries can be added then
+    pub renounced: bool,
+    pub entries: Vec<ClawbackLogEntry>,
+}
+
+#[cw_serde]
+pub struct TransferPolicyResponse {
+    pub policy: TransferPolicy,
+}
+
+#[cw_serde]
+pub struct IsVoucherRedeemedResponse {
+    pub redeemed: bool,
+}
+
+#[cw_serde]
+pub struct IsMetadataFrozenResponse {
+    pub frozen: bool,
+}
+
+#[cw_serde]
+pub struct StatsResponse {
+    /// Amount ever minted
+    pub minted: Uint128,
+    /// Amount ever burned
+    pub burned: Uint128,
+    /// Transfers of any kind, including ForceTransfer
+    pub transfers: u64,
+    /// Accounts with a non zero balance
+    pub unique_holders: u64,
+}
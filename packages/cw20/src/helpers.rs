@@ -4,9 +4,10 @@ use cosmwasm_std::{
     WasmMsg, WasmQuery,Binary,
 };
 use serde::de::DeserializeOwned;
+use cw_utils::Expiration;
 use crate::{
-    AllowanceResponse, BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg,
-    TokenInfoResponse,TokenSupplyResponse,
+    AllowanceResponse, AvailableBalanceResponse, BalanceResponse, BalancesResponse, Cw20ExecuteMsg, Cw20QueryMsg,
+    TokenInfoResponse,TokenSupplyResponse,StatsResponse,
 };
 
 /// Cw20Contract is a wrapper around Addr that provides a lot of helpers
@@ -55,6 +56,28 @@ impl Cw20Contract {
         self.query(querier,req)
     }
 
+    /// Get token balances for the given addresses, in the same order
+    pub fn balances(
+        &self,
+        querier: &QuerierWrapper,
+        addresses: Vec<String>
+    )
+    -> StdResult<BalancesResponse> {
+        let req = Cw20QueryMsg::Balances {addresses};
+        self.query(querier,req)
+    }
+
+    /// Get token balance for the given address, with the part locked by holds
+    pub fn available_balance(
+        &self,
+        querier: &QuerierWrapper,
+        address: String
+    )
+    -> StdResult<AvailableBalanceResponse> {
+        let req = Cw20QueryMsg::AvailableBalance {address};
+        self.query(querier,req)
+    }
+
     /// Get metadata from the contract. This is a good check that the address
     /// is a valid Cw20 contract.
     pub fn meta (
@@ -84,6 +107,11 @@ impl Cw20Contract {
         self.query(querier,req)
     }
 
+    pub fn stats(&self, querier: &QuerierWrapper) -> StdResult<StatsResponse> {
+        let req = Cw20QueryMsg::Stats {};
+        self.query(querier, req)
+    }
+
     /*Caller*/
     pub fn mint_for(
         &self,
@@ -113,6 +141,39 @@ impl Cw20Contract {
         Ok(self.call(burn_msg)?)
     }
     
+    pub fn hold(
+        &self,
+        account : String,
+        amount : Uint128,
+        hold_id : String,
+        expires : Option<Expiration>,
+        ) -> StdResult<CosmosMsg> {
+
+        let hold_msg = Cw20ExecuteMsg::Hold{account,amount,hold_id,expires};
+        self.call(hold_msg)
+    }
+
+    pub fn release_hold(
+        &self,
+        account : String,
+        hold_id : String,
+        ) -> StdResult<CosmosMsg> {
+
+        let release_msg = Cw20ExecuteMsg::ReleaseHold{account,hold_id};
+        self.call(release_msg)
+    }
+
+    pub fn settle_hold(
+        &self,
+        account : String,
+        hold_id : String,
+        to : String,
+        ) -> StdResult<CosmosMsg> {
+
+        let settle_msg = Cw20ExecuteMsg::SettleHold{account,hold_id,to};
+        self.call(settle_msg)
+    }
+
     pub fn transfer(
         &self,
         to: String,
@@ -18,9 +18,9 @@ pub use crate::helpers::Cw20Contract;
 pub use crate::logo::{EmbeddedLogo, Logo, LogoInfo};
 pub use crate::msg::Cw20ExecuteMsg;
 pub use crate::query::{
-    AllAccountsResponse,  AllAllowancesResponse,AllSpenderAllowancesResponse, AllowanceInfo,
-    AllowanceResponse, BalanceResponse , Cw20QueryMsg, DownloadLogoResponse, MarketingInfoResponse,
-    SpenderAllowanceInfo, TokenInfoResponse,TokenSupplyResponse,
+    AccountBalance, AllAccountsResponse, AllAccountsWithBalancesResponse, AllAllowancesResponse,AllSpenderAllowancesResponse, AllowanceInfo,
+    AllowanceResponse, AvailableBalanceResponse, ClawbackLogEntry, ClawbackLogResponse, BalanceResponse, BalancesResponse, Cw20QueryMsg, DownloadLogoResponse,
+    HolderCountResponse, HoldInfo, HoldsResponse, TopHoldersResponse, MarketingInfoResponse, SpenderAllowanceInfo, TokenInfoResponse,TokenSupplyResponse,StatsResponse,
 };
 pub use crate::receiver::Cw20ReceiveMsg;
 
@@ -55,6 +55,14 @@ pub enum Cw20ExecuteMsg {
     },
     /// Only with "approval" extension. Destroys tokens forever
     BurnFrom { from: String, amount: Uint128 },
+    /// Only with "approval" extension. Deletes expired allowances, looking at no more than
+    /// limit entries. Calls for the same owner, or for all owners, continue the sweep where
+    /// the previous one stopped. Anyone can call this.
+    PruneExpired { owner: Option<String>, limit: u32 },
+    /// Re-indexes up to limit balances left over from a migration, starting where the
+    /// previous call stopped. The holder count is set once the last page is done.
+    /// Anyone can call this.
+    MigrateBalances { limit: u32 },
     /// Only with the "mintable" extension. If authorized, creates amount new tokens
     /// and adds to the recipient balance.
     Mint { to: String, amount: Uint128 },
@@ -69,6 +77,37 @@ pub enum Cw20ExecuteMsg {
     /*Set Max Supply for token*/
     SetMaxSupply {max_supply : Uint128},
 
+    /// Only with "holds" extension. Locks amount tokens of account under hold_id so they
+    /// cannot be transferred, sent or burned until the hold is released, settled or expired.
+    /// Can only be called by the owner or a cooperative. An account has at most 20 live holds.
+    Hold {
+        account: String,
+        amount: Uint128,
+        hold_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Only with "holds" extension. Unlocks held tokens back to the account.
+    /// Can be called by whoever placed the hold or the owner, or by anyone once it is expired.
+    ReleaseHold { account: String, hold_id: String },
+    /// Only with "holds" extension. Moves the held tokens from account to recipient and removes the hold.
+    /// Can only be called by whoever placed the hold or the owner, before it expires.
+    SettleHold {
+        account: String,
+        hold_id: String,
+        to: String,
+    },
+    /// Only with "clawback" extension. Owner moves tokens out of any account without
+    /// needing an allowance, e.g. to recover stolen funds. Every use is recorded in the clawback log.
+    /// Holds are ignored, the ones the remaining balance no longer covers get released.
+    ForceTransfer {
+        from: String,
+        to: String,
+        amount: Uint128,
+        reason: String,
+    },
+    /// Only with "clawback" extension. Owner permanently gives up ForceTransfer, cannot be undone.
+    RenounceClawback {},
+
     /// Only with the "marketing" extension. If authorized, updates marketing metadata.
     /// Setting None/null for any of these will leave it unchanged.
     /// Setting Some("") will clear this field on the contract storage
@@ -1,6 +1,6 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::{Addr, Binary/*,StdError, StdResult */, Uint128};
+use cosmwasm_std::{Addr, Binary/*,StdError, StdResult */, Timestamp, Uint128};
 use cosmwasm_schema::{cw_serde, QueryResponses};
 
 use crate::logo::LogoInfo;
@@ -14,6 +14,11 @@ pub enum Cw20QueryMsg {
     /// Returns the current balance of the given address, 0 if unset.
     #[returns(BalanceResponse)]
     Balance { address: String },
+
+    /// Returns the current balances of the given addresses, 0 if unset.
+    /// Balances are in the same order as the addresses.
+    #[returns(BalancesResponse)]
+    Balances { addresses: Vec<String> },
     
     /// Returns metadata on the contract - name, decimals, supply, etc.
     #[returns(TokenInfoResponse)]
@@ -22,6 +27,33 @@ pub enum Cw20QueryMsg {
     #[returns(TokenSupplyResponse)]
     TokenSupply {},
 
+    /// Returns total minted, burned, transfer count and holder count.
+    #[returns(StatsResponse)]
+    Stats {},
+
+    /// Only with "holds" extension.
+    /// Returns the balance of the given address together with the amount locked by
+    /// active holds and what is left to spend.
+    #[returns(AvailableBalanceResponse)]
+    AvailableBalance { address: String },
+
+    /// Only with "holds" extension.
+    /// Returns all active (not expired) holds placed on the given account. Supports pagination.
+    #[returns(HoldsResponse)]
+    Holds {
+        account: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Only with "clawback" extension.
+    /// Returns recorded ForceTransfer uses, oldest first. Supports pagination.
+    #[returns(ClawbackLogResponse)]
+    ClawbackLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
     /// Only with "allowance" extension.
     /// Returns how much spender can use from owner account, 0 if unset.
     #[returns(AllowanceResponse)]
@@ -52,6 +84,21 @@ pub enum Cw20QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Only with "enumerable" extension
+    /// Returns all accounts that have balances together with the balance. Supports pagination.
+    #[returns(AllAccountsWithBalancesResponse)]
+    AllAccountsWithBalances {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Only with "enumerable" extension
+    /// Returns accounts with the highest balances, richest first.
+    #[returns(TopHoldersResponse)]
+    TopHolders { limit: Option<u32> },
+    /// Only with "enumerable" extension
+    /// Returns number of accounts holding a non-zero balance.
+    #[returns(HolderCountResponse)]
+    HolderCount {},
     /// Only with "marketing" extension
     /// Returns more metadata on the contract to display in the client:
     /// - description, logo, project url, etc.
@@ -70,6 +117,33 @@ pub struct BalanceResponse {
     pub balance: Uint128,
 }
 
+#[cw_serde]
+pub struct BalancesResponse {
+    pub balances: Vec<Uint128>,
+}
+
+#[cw_serde]
+pub struct AvailableBalanceResponse {
+    pub balance: Uint128,
+    pub held: Uint128,
+    pub available: Uint128,
+}
+
+#[cw_serde]
+pub struct HoldInfo {
+    pub hold_id: String,
+    /// The owner or cooperative who placed the hold
+    pub holder: String,
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct HoldsResponse {
+    pub holds: Vec<HoldInfo>,
+}
+
 #[cw_serde]
 pub struct TokenInfoResponse {
     pub name: String,
@@ -142,3 +216,56 @@ pub struct AllSpenderAllowancesResponse {
 pub struct AllAccountsResponse {
     pub accounts: Vec<String>,
 }
+
+#[cw_serde]
+pub struct AccountBalance {
+    pub address: String,
+    pub balance: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AllAccountsWithBalancesResponse {
+    pub accounts: Vec<AccountBalance>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct TopHoldersResponse {
+    pub holders: Vec<AccountBalance>,
+}
+
+#[cw_serde]
+pub struct StatsResponse {
+    pub minted: Uint128,
+    pub burned: Uint128,
+    /// Transfers of any kind, including sends, settled holds and ForceTransfer
+    pub transfers: u64,
+    /// Accounts holding a non-zero balance
+    pub unique_holders: u64,
+}
+
+#[cw_serde]
+pub struct HolderCountResponse {
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct ClawbackLogEntry {
+    pub id: u64,
+    /// Who executed the ForceTransfer
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    pub amount: Uint128,
+    pub reason: String,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct ClawbackLogResponse {
+    /// True once the owner renounced clawback, no new entries can be added then
+    pub renounced: bool,
+    pub entries: Vec<ClawbackLogEntry>,
+}
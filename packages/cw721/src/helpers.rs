@@ -7,11 +7,14 @@ use cosmwasm_std::{
 use crate::{
     AllTokenInfoResponse, ContractInfoResponse,AllowanceResponse,
     TokenInfoResponse, TokenSupplyResponse, OperatorsResponse, OwnerOfResponse, TokensResponse,
+    UserOfResponse, TokenHistoryResponse,IsBurnedResponse,StatsResponse,
+    IsMetadataFrozenResponse, ProvenanceResponse, SaleStatusResponse,
+    IsVoucherRedeemedResponse, ChildrenResponse, ChildCollectionsResponse,
 };
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use cosmwasm_std::Binary;
-use crate::{Cw721ExecuteMsg, Cw721QueryMsg};
+use crate::{Cw721ExecuteMsg, Cw721QueryMsg, MintInfo};
 
 #[cw_serde]
 pub struct Cw721Contract<Q: CustomMsg, E: CustomMsg>(
@@ -60,6 +63,89 @@ impl<Q: CustomMsg, E: CustomMsg> Cw721Contract<Q, E> {
         let req = Cw721QueryMsg::OwnerOf {token_id};
         self.query(querier, req)
     }
+    pub fn user_of(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: String,
+    ) -> StdResult<UserOfResponse> {
+        let req = Cw721QueryMsg::UserOf {token_id};
+        self.query(querier, req)
+    }
+
+    pub fn is_burned(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: String,
+    ) -> StdResult<IsBurnedResponse> {
+        let req = Cw721QueryMsg::IsBurned { token_id };
+        self.query(querier, req)
+    }
+
+    pub fn stats(&self, querier: &QuerierWrapper) -> StdResult<StatsResponse> {
+        let req = Cw721QueryMsg::Stats {};
+        self.query(querier, req)
+    }
+
+    pub fn is_metadata_frozen(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: Option<String>,
+    ) -> StdResult<IsMetadataFrozenResponse> {
+        let req = Cw721QueryMsg::IsMetadataFrozen { token_id };
+        self.query(querier, req)
+    }
+
+    pub fn provenance(&self, querier: &QuerierWrapper) -> StdResult<ProvenanceResponse> {
+        let req = Cw721QueryMsg::Provenance {};
+        self.query(querier, req)
+    }
+
+    pub fn sale_status(&self, querier: &QuerierWrapper) -> StdResult<SaleStatusResponse> {
+        let req = Cw721QueryMsg::SaleStatus {};
+        self.query(querier, req)
+    }
+
+    pub fn is_voucher_redeemed(
+        &self,
+        querier: &QuerierWrapper,
+        signer: String,
+        nonce: u64,
+    ) -> StdResult<IsVoucherRedeemedResponse> {
+        let req = Cw721QueryMsg::IsVoucherRedeemed { signer, nonce };
+        self.query(querier, req)
+    }
+
+    pub fn children_of(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<ChildrenResponse> {
+        let req = Cw721QueryMsg::ChildrenOf { token_id, start_after, limit };
+        self.query(querier, req)
+    }
+
+    pub fn child_collections(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ChildCollectionsResponse> {
+        let req = Cw721QueryMsg::ChildCollections { start_after, limit };
+        self.query(querier, req)
+    }
+
+    pub fn token_history(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<TokenHistoryResponse> {
+        let req = Cw721QueryMsg::TokenHistory {token_id, start_after, limit};
+        self.query(querier, req)
+    }
     pub fn all_balance (
         &self,
         querier: &QuerierWrapper,
@@ -203,6 +289,7 @@ impl<Q: CustomMsg, E: CustomMsg> Cw721Contract<Q, E> {
             token_owner: recipient,
             token_uri: token_uri,
             extension: extension,
+            transfer_policy: None,
         };
         Ok(self.call(mint_msg)?)
     }
@@ -231,10 +318,22 @@ impl<Q: CustomMsg, E: CustomMsg> Cw721Contract<Q, E> {
             token_owner: to,
             token_uris: token_uris,
             extensions: extensions,
+            transfer_policy: None,
         };
         Ok(self.call(mint_msg)?)
     }
 
+    pub fn mint_many_for<T: Serialize>(
+        &self,
+        mints : Vec<MintInfo<T>>,
+        ) -> StdResult<CosmosMsg> {
+        let mint_msg = Cw721ExecuteMsg::<T, E>::MintMany{
+            mints,
+            transfer_policy: None,
+        };
+        self.call(mint_msg)
+    }
+
     pub fn burn_batch_from (
         &self,
         token_ids : Vec<String>,
@@ -309,4 +408,47 @@ impl<Q: CustomMsg, E: CustomMsg> Cw721Contract<Q, E> {
         };
         Ok(self.call(transfer_msg)?)
     }   
+
+    pub fn batch_transfer (
+        &self,
+        token_ids: Vec<String>,
+        to: String,
+    ) -> StdResult<CosmosMsg> {
+
+        let transfer_msg = Cw721ExecuteMsg::<Empty, E>::BatchTransfer{
+            to,
+            token_ids,
+        };
+        self.call(transfer_msg)
+    }
+
+    pub fn batch_transfer_from (
+        &self,
+        token_ids: Vec<String>,
+        from : String,
+        to: String,
+    ) -> StdResult<CosmosMsg> {
+
+        let transfer_msg = Cw721ExecuteMsg::<Empty, E>::BatchTransferFrom{
+            from,
+            to,
+            token_ids,
+        };
+        self.call(transfer_msg)
+    }
+
+    pub fn batch_send (
+        &self,
+        token_ids: Vec<String>,
+        contract: String,
+        msg: Binary,
+    ) -> StdResult<CosmosMsg> {
+
+        let transfer_msg = Cw721ExecuteMsg::<Empty, E>::BatchSend{
+            contract,
+            token_ids,
+            msg,
+        };
+        self.call(transfer_msg)
+    }
 }
@@ -6,11 +6,15 @@ mod traits;
 pub use cw_utils::Expiration;
 pub mod helpers;
 
-pub use crate::msg::Cw721ExecuteMsg;
+pub use crate::msg::{
+    BatchReceiveMsg, ChildToken, ComposeReceiveMsg, Cw721ExecuteMsg, MintInfo, MintManyResponse, MintVoucher, MintedTokens,
+    ReceiveMsg, SaleAsset, SaleConfig, SalePhase, SaleReceiveMsg, TransferPolicy,
+};
 pub use crate::query::{
-    AllTokenInfoResponse, Approval, ApprovalResponse, ApprovalsResponse, ContractInfoResponse,
+    AllTokenInfoResponse, Approval, ApprovalResponse, ApprovalsResponse, ClawbackLogEntry,
+    ClawbackLogResponse, ContractInfoResponse, HistoryAction, LockInfo, TokenHistoryEntry, TokenHistoryResponse,
     Cw721QueryMsg, TokenInfoResponse, TokenSupplyResponse, OperatorsResponse, OwnerOfResponse,
-    TokensResponse,TokenSupply,AllowanceResponse,
+    TokensResponse,TokenSupply,AllowanceResponse,TransferPolicyResponse,UserOfResponse,IsBurnedResponse,StatsResponse,Tombstone,IsMetadataFrozenResponse,ProvenanceResponse,SaleStatusResponse,IsVoucherRedeemedResponse,ChildrenResponse,ChildCollectionsResponse,
 };
-pub use crate::receiver::Cw721ReceiveMsg;
+pub use crate::receiver::{Cw721BatchReceiveMsg, Cw721ReceiveMsg};
 pub use crate::traits::{Cw721, Cw721Execute, Cw721Query};
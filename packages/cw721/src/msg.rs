@@ -1,6 +1,27 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Uint128,Binary};
+use cosmwasm_std::{Uint128,Binary,Timestamp};
 use cw_utils::Expiration;
+use cw20::Cw20ReceiveMsg;
+use cw1155::{Cw1155BatchReceiveMsg, Cw1155ReceiveMsg};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::receiver::{Cw721BatchReceiveMsg, Cw721ReceiveMsg};
+
+/// Decides who may move a token between accounts. Mint and burn are never restricted.
+#[cw_serde]
+#[derive(Default)]
+pub enum TransferPolicy {
+    /// Anyone allowed by the usual approval rules can transfer
+    #[default]
+    Free,
+    /// Can never be transferred, only minted and burned
+    Soulbound,
+    /// Only cooperative contracts (and the contract owner) can transfer
+    CooperativeOnly,
+    /// Tradable until the first transfer, locked to whoever received it
+    BindOnTransfer,
+}
 
 /// This is like Cw721ExecuteMsg but we add a Mint command for an owner
 /// to make this stand-alone. You will likely want to remove mint and
@@ -42,6 +63,27 @@ pub enum Cw721ExecuteMsg<T, E> {
         msg: Binary,
     },
 
+    /// Moves several tokens of the sender to one account, all or nothing
+    BatchTransfer {
+        to: String,
+        token_ids: Vec<String>,
+    },
+
+    /// Same as TransferFrom for several tokens, all or nothing
+    BatchTransferFrom {
+        from: String,
+        to: String,
+        token_ids: Vec<String>,
+    },
+
+    /// Moves several tokens of the sender to a contract and triggers
+    /// one Cw721BatchReceiveMsg on it, all or nothing
+    BatchSend {
+        contract: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    },
+
     /// Allows spender to transfer / send any token from the owner's account.
     /// If expiration is set, then this allowance has a time/height limit
     ApproveAll {
@@ -51,17 +93,77 @@ pub enum Cw721ExecuteMsg<T, E> {
     /// Remove previously granted ApproveAll permission
     RevokeAll { spender: String },
 
+    /// Lets user use the token until expires while owner keeps it, None user clears it.
+    /// Can be called by the token owner or its operators, cleared on every transfer.
+    SetUser {
+        token_id: String,
+        user: Option<String>,
+        expires: Option<Expiration>,
+    },
+
+    /// Cooperative locks a token in place, e.g. while it is used in game.
+    /// The token owner must have made the cooperative an operator with ApproveAll.
+    /// A locked token can not be transferred or burned until unlocked.
+    Lock { token_id: String, lock_id: String },
+
+    /// Removes the lock, only by whoever locked it (or contract owner) with the same lock_id
+    Unlock { token_id: String, lock_id: String },
+
+    /// Token owner removes a lock that is older than the lock timeout
+    EmergencyUnlock { token_id: String },
+
+    /// Owner sets how many seconds a lock must be held before the token owner can emergency unlock
+    UpdateLockTimeout { lock_timeout: u64 },
+
+    /// Owner sets how many history entries are kept per token, the oldest are dropped first.
+    /// 0 (default) stops recording history. Entries above a lowered cap are hidden at once
+    /// and deleted on the next write to their token.
+    UpdateHistoryCap { history_cap: u32 },
+
+    /// Removes operators whose approval expired, at most limit per call and callable by anyone.
+    /// Scoped to owner or collection wide, each call resumes after the last operator the previous one saw.
+    PruneExpired { owner: Option<String>, limit: u32 },
+
+    /// Counts the holders of up to limit tokens minted before the holder index, resuming where
+    /// the previous call stopped. UniqueHolders is only right once the last page is done.
+    /// Anyone can call this.
+    MigrateHolders { limit: u32 },
+
     // Update exist NFT token uri at specific id.
     UpdateTokenURI { token_id : String,token_uri: Option<String>},
     
     // Update exist NFT token extension data at specific id.
     UpdateTokenExtension { token_id : String , extension: T},
 
-    /// Mint a new NFT, can only be called by the contract minter
-    Mint{token_owner: String,token_uri: Option<String>,extension: T},
+    /// Owner sets the uri of tokens minted without their own token_uri, `{id}` is
+    /// replaced by the token id or else the id is appended. Blocked once every token is frozen,
+    /// and while a delayed reveal is pending.
+    UpdateBaseUri { base_uri: Option<String> },
+
+    /// Owner ends the delayed reveal, tokens resolve against base_uri from now on.
+    /// Only once and only when a reveal was configured at instantiate.
+    Reveal { base_uri: String },
+
+    /// Owner permanently blocks metadata updates of token_id,
+    /// or of every token when token_id is None. Cannot be undone.
+    /// A collection with a pending delayed reveal can only be frozen after Reveal.
+    FreezeMetadata { token_id: Option<String> },
+
+    /// Mint a new NFT, can only be called by the contract minter.
+    /// Without transfer_policy the collection policy applies.
+    Mint{token_owner: String,token_uri: Option<String>,extension: T,
+        #[serde(default)]
+        transfer_policy: Option<TransferPolicy>},
 
     /// Mint a new NFT, can only be called by the contract minter
-    MintBatch{token_owner: String,token_uris: Vec<Option<String>>,extensions: Vec<T>},
+    /// Without transfer_policy the collection policy applies.
+    MintBatch{token_owner: String,token_uris: Vec<Option<String>>,extensions: Vec<T>,
+        #[serde(default)]
+        transfer_policy: Option<TransferPolicy>},
+
+    /// Mint distinct NFTs to many owners at once, e.g. for airdrops.
+    /// Returns MintManyResponse as data. Without transfer_policy the collection policy applies.
+    MintMany { mints: Vec<MintInfo<T>>, transfer_policy: Option<TransferPolicy> },
 
     /// Burn an NFT the sender has access to
     Burn { token_id: String ,from_address : Option<String>},
@@ -75,6 +177,155 @@ pub enum Cw721ExecuteMsg<T, E> {
 
     UnsetCooperative { cooperative : String },
 
+    /// Owner moves a token out of any account without approvals, e.g. to recover stolen items.
+    /// Every use is recorded in the clawback log. A lock is released and a BindOnTransfer
+    /// token is unbound again.
+    ForceTransfer {
+        from: String,
+        to: String,
+        token_id: String,
+        reason: String,
+    },
+
+    /// Owner permanently gives up ForceTransfer, cannot be undone.
+    RenounceClawback {},
+
+    /// Owner sets up or replaces the primary sale, None ends it.
+    /// Sold counts are kept per phase index.
+    UpdateSale { sale: Option<SaleConfig<T>> },
+
+    /// Buys count tokens in the active phase, paying its native price in funds.
+    /// Whitelist phases need the merkle proof of the sender.
+    Buy { count: u32, proof: Option<Vec<String>> },
+
+    /// Tokens sent to this contract. cw20 pays a cw20 priced phase with a SaleReceiveMsg,
+    /// cw1155 and cw721 tokens are equipped with a ComposeReceiveMsg.
+    Receive(ReceiveMsg),
+
+    /// Batches of cw1155 or cw721 tokens sent to this contract, equipped with a ComposeReceiveMsg
+    BatchReceive(BatchReceiveMsg),
+
+    /// Owner withdraws all sale proceeds not withdrawn yet
+    WithdrawProceeds {},
+
+    /// Sender registers the secp256k1 public key its vouchers are signed with, None removes it.
+    /// The key is 33 bytes compressed or 65 bytes uncompressed.
+    /// Vouchers only mint while the sender is owner or a cooperative that can mint.
+    SetVoucherSigner { pubkey: Option<Binary> },
+
+    /// Mints the voucher to its recipient, anyone can submit it.
+    /// signature is over sha256 of the contract address followed by the JSON voucher.
+    RedeemVoucher { voucher: MintVoucher<T>, signature: Binary },
+
+    /// Owner or operator of token_id takes child out of it, sent to recipient or the sender.
+    /// cw1155 children can be taken out partly.
+    Unequip { token_id: String, child: ChildToken, recipient: Option<String> },
+
+    /// Owner allows or disallows equipping tokens of the contract collection.
+    /// Tokens of this collection can always be equipped.
+    SetChildCollection { contract: String, allowed: bool },
+
+    /// Owner or operator of token_id forgets a child without sending it anywhere,
+    /// for children whose collection no longer gives them back.
+    DropChild { token_id: String, contract: String, child_token_id: String },
+
     /// Extension msg
     Extension { msg: E },
-}
\ No newline at end of file
+}
+/// One token of MintMany
+#[cw_serde]
+pub struct MintInfo<T> {
+    pub owner: String,
+    pub token_uri: Option<String>,
+    pub extension: T,
+}
+
+/// Data of a MintMany response
+#[cw_serde]
+pub struct MintManyResponse {
+    /// One entry per owner, in order of first appearance in mints
+    pub minted: Vec<MintedTokens>,
+}
+
+#[cw_serde]
+pub struct MintedTokens {
+    pub owner: String,
+    pub token_ids: Vec<String>,
+}
+
+/// Off-chain mint permission, each nonce of a signer can be redeemed once
+#[cw_serde]
+pub struct MintVoucher<T> {
+    pub signer: String,
+    pub recipient: String,
+    pub token_uri: Option<String>,
+    pub extension: T,
+    pub nonce: u64,
+    pub expires: Expiration,
+}
+
+/// Primary sale, tokens are minted to buyers with extension and the base uri
+#[cw_serde]
+pub struct SaleConfig<T> {
+    pub phases: Vec<SalePhase>,
+    /// Extension of every sold token
+    pub extension: T,
+}
+
+#[cw_serde]
+pub struct SalePhase {
+    pub name: String,
+    /// Hex sha256 merkle root over the whitelisted addresses, None for a public phase.
+    /// Leaves are `sha256(0x00 || address)`, each node is `sha256(0x01 || a || b)` with a <= b.
+    pub merkle_root: Option<String>,
+    /// Price of one token
+    pub price: SaleAsset,
+    /// Tokens one wallet may buy in this phase, None for no limit
+    pub per_wallet_limit: Option<u32>,
+    pub start_time: Timestamp,
+    /// None keeps the phase open
+    pub end_time: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub enum SaleAsset {
+    Native { denom: String, amount: Uint128 },
+    Cw20 { token: String, amount: Uint128 },
+}
+
+/// Payloads that all arrive under `receive`, told apart by their fields
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum ReceiveMsg {
+    Cw1155(Cw1155ReceiveMsg),
+    Cw721(Cw721ReceiveMsg),
+    Cw20(Cw20ReceiveMsg),
+}
+
+/// Batch payloads that all arrive under `batch_receive`, told apart by their fields
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum BatchReceiveMsg {
+    Cw1155(Cw1155BatchReceiveMsg),
+    Cw721(Cw721BatchReceiveMsg),
+}
+
+/// Sent as the msg of a cw1155 or cw721 Send to equip the sent tokens
+#[cw_serde]
+pub enum ComposeReceiveMsg {
+    /// token_id is the parent, the sender of the tokens must own or operate it
+    Equip { token_id: String },
+}
+
+/// Token held by a parent token, contract is the collection it belongs to
+#[cw_serde]
+pub enum ChildToken {
+    Cw1155 { contract: String, token_id: String, amount: Uint128 },
+    Cw721 { contract: String, token_id: String },
+}
+
+/// Sent as the msg of a cw20 Send to pay a cw20 priced phase
+#[cw_serde]
+pub enum SaleReceiveMsg {
+    Buy { count: u32, proof: Option<Vec<String>> },
+}
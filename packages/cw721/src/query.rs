@@ -1,8 +1,10 @@
 use cosmwasm_schema::{cw_serde,QueryResponses};
 use schemars::JsonSchema;
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Timestamp, Uint128};
 use cw_utils::Expiration;
 
+use crate::msg::{ChildToken, SaleAsset, SalePhase, TransferPolicy};
+
 #[cw_serde]
 pub struct TokenSupply {
     pub total_supply: Uint128,
@@ -22,6 +24,10 @@ pub enum Cw721QueryMsg<Q: JsonSchema> {
     #[returns(OwnerOfResponse)]
     OwnerOf {token_id: String},
 
+    /// Return the current user of the given token, None when unset or expired
+    #[returns(UserOfResponse)]
+    UserOf {token_id: String},
+
     /// Only with "allowance" extension.
     /// Returns how much spender can use from owner account, 0 if unset.
     #[returns(AllowanceResponse)]
@@ -73,6 +79,64 @@ pub enum Cw721QueryMsg<Q: JsonSchema> {
         limit: Option<u32>,
     },
 
+    /// Returns transfer policy of the token, or of the collection when token_id is not set.
+    #[returns(TransferPolicyResponse)]
+    TransferPolicy { token_id: Option<String> },
+
+    /// Returns recorded ForceTransfer uses, oldest first. Supports pagination.
+    #[returns(ClawbackLogResponse)]
+    ClawbackLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the ownership history of a token, oldest first. Supports pagination.
+    /// Empty unless the owner enabled history with UpdateHistoryCap.
+    #[returns(TokenHistoryResponse)]
+    TokenHistory {
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Returns whether token_id was burned, with its tombstone
+    #[returns(IsBurnedResponse)]
+    IsBurned { token_id: String },
+
+    /// Returns contract-wide mint, burn, transfer and holder counts
+    #[returns(StatsResponse)]
+    Stats {},
+
+    /// Returns whether the metadata of token_id can no longer be updated,
+    /// with token_id None whether the whole collection is frozen
+    #[returns(IsMetadataFrozenResponse)]
+    IsMetadataFrozen { token_id: Option<String> },
+
+    /// Returns the provenance hash committed at instantiate and whether the reveal happened
+    #[returns(ProvenanceResponse)]
+    Provenance {},
+
+    /// Returns the sale phases, which one is open now, sold counts and unwithdrawn proceeds
+    #[returns(SaleStatusResponse)]
+    SaleStatus {},
+
+    /// Returns whether the voucher with this signer and nonce was redeemed
+    #[returns(IsVoucherRedeemedResponse)]
+    IsVoucherRedeemed { signer: String, nonce: u64 },
+
+    /// Returns the tokens equipped into token_id, ordered by `(contract, token_id)`.
+    /// Supports pagination.
+    #[returns(ChildrenResponse)]
+    ChildrenOf {
+        token_id: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+
+    /// Lists the collections whose tokens can be equipped. Supports pagination.
+    #[returns(ChildCollectionsResponse)]
+    ChildCollections { start_after: Option<String>, limit: Option<u32> },
+
     /// Extension query
     #[returns(())]
     Extension { msg: Q },
@@ -85,6 +149,13 @@ pub struct OwnerOfResponse {
 
 }
 
+#[cw_serde]
+pub struct UserOfResponse {
+    /// Who can use the token, None when unset or expired
+    pub user: Option<String>,
+    pub expires: Option<Expiration>,
+}
+
 #[cw_serde]
 pub struct Approval {
     /// Account that can transfer/send the token
@@ -118,22 +189,37 @@ pub struct ContractInfoResponse {
     pub name: String,
     pub symbol: String,
     pub owner : String,
+    /// Uri template of tokens without their own token_uri
+    pub base_uri : Option<String>,
 }
 
 #[cw_serde]
 pub struct TokenInfoResponse<T> {
     /// Universal resource identifier for this NFT
     /// Should point to a JSON file that conforms to the ERC721
-    /// Metadata JSON Schema
+    /// Metadata JSON Schema.
+    /// The token's own uri, or else the one resolved from the collection base_uri
     pub token_uri: Option<String>,
     /// You can add any custom metadata here when you extend cw721-base
     pub extension: T,
+    /// Set while the token is locked
+    pub lock: Option<LockInfo>,
+}
+
+#[cw_serde]
+pub struct LockInfo {
+    /// Who locked the token
+    pub locker: String,
+    pub lock_id: String,
+    pub locked_at: Timestamp,
 }
 
 #[cw_serde]
 pub struct AllTokenInfoResponse<T> {
     /// Who is owner of token
     pub owner: String,
+    /// Who can currently use the token, None when unset or expired
+    pub user: Option<String>,
     /// Data on the token itself,
     pub info: TokenInfoResponse<T>,
 }
@@ -151,4 +237,123 @@ pub struct TokensResponse {
 pub struct AllowanceResponse {
     pub allowance: Uint128,
     pub expires: Expiration,
-}
\ No newline at end of file
+}
+#[cw_serde]
+pub struct ClawbackLogEntry {
+    pub id: u64,
+    /// Who executed the ForceTransfer
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    pub token_id: String,
+    pub reason: String,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct ClawbackLogResponse {
+    /// True once the owner renounced clawback, no new entries can be added then
+    pub renounced: bool,
+    pub entries: Vec<ClawbackLogEntry>,
+}
+
+#[cw_serde]
+pub struct Tombstone {
+    /// Who executed the burn
+    pub burner: String,
+    /// Owner of the token when it was burned
+    pub owner: String,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct IsBurnedResponse {
+    pub burned: bool,
+    pub tombstone: Option<Tombstone>,
+}
+
+#[cw_serde]
+pub struct ChildrenResponse {
+    pub children: Vec<ChildToken>,
+}
+
+#[cw_serde]
+pub struct ChildCollectionsResponse {
+    pub collections: Vec<String>,
+}
+
+#[cw_serde]
+pub struct IsVoucherRedeemedResponse {
+    pub redeemed: bool,
+}
+
+#[cw_serde]
+pub struct SaleStatusResponse {
+    /// Empty when there is no sale
+    pub phases: Vec<SalePhase>,
+    /// Index into phases of the phase open at the current block time
+    pub active_phase: Option<u32>,
+    /// Tokens sold per phase, same order as phases
+    pub sold: Vec<u32>,
+    pub total_supply: Uint128,
+    pub max_supply: Uint128,
+    pub proceeds: Vec<SaleAsset>,
+}
+
+#[cw_serde]
+pub struct ProvenanceResponse {
+    /// None when the collection has no delayed reveal
+    pub provenance_hash: Option<String>,
+    pub revealed: bool,
+}
+
+#[cw_serde]
+pub struct IsMetadataFrozenResponse {
+    pub frozen: bool,
+}
+
+#[cw_serde]
+pub struct StatsResponse {
+    pub minted: Uint128,
+    pub burned: Uint128,
+    /// Transfers and sends of any kind, including ForceTransfer
+    pub transfers: u64,
+    /// Accounts owning at least one token
+    pub unique_holders: u64,
+}
+
+/// What changed the owner of a token
+#[cw_serde]
+pub enum HistoryAction {
+    Mint,
+    Transfer,
+    Send,
+    /// ForceTransfer by the contract owner
+    Force,
+}
+
+#[cw_serde]
+pub struct TokenHistoryEntry {
+    pub id: u64,
+    /// Who did it, the minter for Mint
+    pub actor: String,
+    /// Owner after the action
+    pub owner: String,
+    pub action: HistoryAction,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct TokenHistoryResponse {
+    pub entries: Vec<TokenHistoryEntry>,
+}
+
+#[cw_serde]
+pub struct TransferPolicyResponse {
+    pub policy: TransferPolicy,
+    /// Only for BindOnTransfer tokens, true once the token can not be transferred anymore
+    pub bound: bool,
+}
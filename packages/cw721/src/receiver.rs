@@ -37,9 +37,43 @@ impl Cw721ReceiveMsg {
     }
 }
 
+/// Cw721BatchReceiveMsg should be de/serialized under `BatchReceive()` variant in a ExecuteMsg
+#[cw_serde]
+pub struct Cw721BatchReceiveMsg {
+    /// The account that executed the send message
+    pub operator: String,
+    /// The account that the tokens transfered from
+    pub from: String,
+    pub token_ids: Vec<String>,
+    pub msg: Binary,
+}
+
+impl Cw721BatchReceiveMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = ReceiverExecuteMsg::BatchReceive(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>, C>(self, contract_addr: T) -> StdResult<CosmosMsg<C>>
+    where
+        C: Clone + std::fmt::Debug + PartialEq + JsonSchema,
+    {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
 /// This is just a helper to properly serialize the above message.
 /// The actual receiver should include this variant in the larger ExecuteMsg enum
 #[cw_serde]
 enum ReceiverExecuteMsg {
     Receive(Cw721ReceiveMsg),
+    BatchReceive(Cw721BatchReceiveMsg),
 }
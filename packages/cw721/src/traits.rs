@@ -3,7 +3,7 @@ use serde::Serialize;
 
 use crate::query::{AllowanceResponse};
 use crate::{
-    AllTokenInfoResponse, ContractInfoResponse, TokenInfoResponse,
+    TransferPolicy, AllTokenInfoResponse, ContractInfoResponse, TokenInfoResponse,
     TokenSupplyResponse, OperatorsResponse, OwnerOfResponse, TokensResponse,
 };
 use cosmwasm_std::{Binary, CustomMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
@@ -98,22 +98,28 @@ where
         from_address : Option<String>,
     ) -> Result<Response<C>, Self::Err>;
 
+    #[allow(clippy::too_many_arguments)]
     fn mint(
         &self,
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         to : String,
         token_uri: Option<String>,
         extension: T,
+        transfer_policy: Option<TransferPolicy>,
     ) -> Result<Response<C>, Self::Err>;
 
+    #[allow(clippy::too_many_arguments)]
     fn mint_batch(
         &self,
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         to : String,
         token_uris: Vec<Option<String>>,
         extensions: Vec<T>,
+        transfer_policy: Option<TransferPolicy>,
     ) -> Result<Response<C>, Self::Err>;
 }
 
@@ -178,6 +184,7 @@ where
     fn all_token_info(
         &self,
         deps: Deps,
+        env: Env,
         token_id: String,
     ) -> StdResult<AllTokenInfoResponse<T>>;
 }